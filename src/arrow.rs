@@ -0,0 +1,164 @@
+//! Conversion of query results into Apache Arrow record batches.
+
+use std::sync::Arc;
+
+use arrow_array::builder::{BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+/// Number of rows inspected when inferring a schema
+pub const DEFAULT_INFER_ROWS: usize = 100;
+
+/// Infer an Arrow schema from the first `max_rows` JSON object rows.
+///
+/// Columns appear in first-seen order. Integers widen to `Float64` when mixed
+/// with floats, and any other mix of types (or nested objects/arrays) falls
+/// back to `Utf8`. Every column is nullable.
+pub fn infer_schema(rows: &[Value], max_rows: usize) -> Schema {
+    let mut columns: Vec<(String, Option<DataType>)> = Vec::new();
+
+    for row in rows.iter().take(max_rows) {
+        let Some(obj) = row.as_object() else {
+            continue;
+        };
+        for (key, value) in obj {
+            let observed = value_type(value);
+            match columns.iter_mut().find(|(name, _)| name == key) {
+                Some((_, current)) => *current = merge_types(current.take(), observed),
+                None => columns.push((key.clone(), observed)),
+            }
+        }
+    }
+
+    Schema::new(
+        columns
+            .into_iter()
+            .map(|(name, ty)| Field::new(name, ty.unwrap_or(DataType::Utf8), true))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn value_type(value: &Value) -> Option<DataType> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some(DataType::Boolean),
+        Value::Number(n) if n.is_i64() => Some(DataType::Int64),
+        Value::Number(_) => Some(DataType::Float64),
+        Value::String(_) | Value::Array(_) | Value::Object(_) => Some(DataType::Utf8),
+    }
+}
+
+fn merge_types(current: Option<DataType>, observed: Option<DataType>) -> Option<DataType> {
+    match (current, observed) {
+        (None, t) | (t, None) => t,
+        (Some(a), Some(b)) if a == b => Some(a),
+        (Some(DataType::Int64), Some(DataType::Float64))
+        | (Some(DataType::Float64), Some(DataType::Int64)) => Some(DataType::Float64),
+        _ => Some(DataType::Utf8),
+    }
+}
+
+/// Convert JSON object rows into a `RecordBatch`.
+///
+/// When `schema` is `None` it is inferred from the first
+/// [`DEFAULT_INFER_ROWS`] rows. Missing keys and JSON `null` become Arrow
+/// nulls; numeric strings are parsed for numeric columns. A value that cannot
+/// be coerced into its column type is an `Error::Serialization`.
+pub fn rows_to_record_batch(rows: &[Value], schema: Option<SchemaRef>) -> Result<RecordBatch> {
+    let schema = schema.unwrap_or_else(|| Arc::new(infer_schema(rows, DEFAULT_INFER_ROWS)));
+
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| build_column(rows, field))
+        .collect::<Result<Vec<ArrayRef>>>()?;
+
+    // The row count is explicit so an empty result, which infers no
+    // columns, still makes a batch
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    RecordBatch::try_new_with_options(schema, columns, &options)
+        .map_err(|e| Error::Serialization(Box::new(e)))
+}
+
+fn build_column(rows: &[Value], field: &Field) -> Result<ArrayRef> {
+    let name = field.name();
+    let values = rows.iter().map(|row| row.get(name).filter(|v| !v.is_null()));
+
+    let mismatch = |v: &Value| {
+        Error::Serialization(
+            format!(
+                "Cannot convert {} to {} for column {}",
+                v,
+                field.data_type(),
+                name
+            )
+            .into(),
+        )
+    };
+
+    let array: ArrayRef = match field.data_type() {
+        DataType::Boolean => {
+            let mut builder = BooleanBuilder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(v) => builder.append_value(v.as_bool().ok_or_else(|| mismatch(v))?),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Int64 => {
+            let mut builder = Int64Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(v) => {
+                        let i = match v {
+                            Value::String(s) => s.parse().ok(),
+                            _ => v.as_i64(),
+                        };
+                        builder.append_value(i.ok_or_else(|| mismatch(v))?);
+                    }
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Float64 => {
+            let mut builder = Float64Builder::with_capacity(rows.len());
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(v) => {
+                        let f = match v {
+                            Value::String(s) => s.parse().ok(),
+                            _ => v.as_f64(),
+                        };
+                        builder.append_value(f.ok_or_else(|| mismatch(v))?);
+                    }
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Utf8 => {
+            let mut builder = StringBuilder::new();
+            for value in values {
+                match value {
+                    None => builder.append_null(),
+                    Some(Value::String(s)) => builder.append_value(s),
+                    Some(v) => builder.append_value(v.to_string()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        other => {
+            return Err(Error::Serialization(
+                format!("Unsupported Arrow type {} for column {}", other, name).into(),
+            ))
+        }
+    };
+
+    Ok(array)
+}
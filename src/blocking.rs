@@ -0,0 +1,240 @@
+//! Synchronous wrappers around the async clients, enabled by the `blocking`
+//! feature.
+//!
+//! Each client owns a single-threaded Tokio runtime driven by a background
+//! thread, so callers need no runtime of their own. The methods block the
+//! calling thread and panic if called from inside an async context; use the
+//! async clients there.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::client;
+use crate::client::{ConnectOptions, SubEvent};
+use crate::error::Result;
+use crate::protocol::{Collection, Document, QueryPage, UpdateSpec};
+use crate::query::{QueryBuilder, StructuredQuery};
+
+/// A current-thread runtime running on its own thread until dropped
+#[derive(Debug)]
+pub(crate) struct Runtime {
+    handle: tokio::runtime::Handle,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Runtime {
+    pub(crate) fn new() -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        let handle = runtime.handle().clone();
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("squirreldb-blocking".to_string())
+            .spawn(move || {
+                runtime.block_on(async {
+                    let _ = stopped.await;
+                });
+            })?;
+
+        Ok(Self {
+            handle,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    pub(crate) fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.handle.block_on(future)
+    }
+
+    /// Enter the runtime, e.g. so an async value's `Drop` can spawn cleanup
+    pub(crate) fn enter(&self) -> tokio::runtime::EnterGuard<'_> {
+        self.handle.enter()
+    }
+}
+
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Blocking SquirrelDB client
+#[derive(Debug)]
+pub struct SquirrelDB {
+    // Dropped before the runtime it runs on
+    inner: Option<client::SquirrelDB>,
+    runtime: Arc<Runtime>,
+}
+
+impl SquirrelDB {
+    /// Connect to SquirrelDB server
+    pub fn connect(addr: &str) -> Result<Self> {
+        let runtime = Arc::new(Runtime::new()?);
+        let inner = runtime.block_on(client::SquirrelDB::connect(addr))?;
+        Ok(Self { inner: Some(inner), runtime })
+    }
+
+    /// Connect with full options
+    pub fn connect_with_options(opts: ConnectOptions) -> Result<Self> {
+        let runtime = Arc::new(Runtime::new()?);
+        let inner = runtime.block_on(client::SquirrelDB::connect_with_options(opts))?;
+        Ok(Self { inner: Some(inner), runtime })
+    }
+
+    fn inner(&self) -> &client::SquirrelDB {
+        self.inner.as_ref().expect("client is only taken on close")
+    }
+
+    /// Execute a query
+    pub fn query<T: serde::de::DeserializeOwned>(&self, query: &str) -> Result<T> {
+        self.runtime.block_on(self.inner().query(query))
+    }
+
+    /// Run a structured query as-is
+    pub fn query_structured(&self, query: &StructuredQuery) -> Result<Vec<serde_json::Value>> {
+        self.runtime.block_on(self.inner().query_structured(query))
+    }
+
+    /// Run a query and return one page of results with the total match count
+    pub fn query_page<T: serde::de::DeserializeOwned>(&self, query: &QueryBuilder) -> Result<QueryPage<T>> {
+        self.runtime.block_on(self.inner().query_page(query))
+    }
+
+    /// Insert a document
+    pub fn insert(&self, collection: &str, data: serde_json::Value) -> Result<Document> {
+        self.runtime.block_on(self.inner().insert(collection, data))
+    }
+
+    /// Insert a typed document; see the async `insert_typed`
+    pub fn insert_typed<T: serde::Serialize>(&self, collection: &str, data: &T) -> Result<Document> {
+        self.runtime.block_on(self.inner().insert_typed(collection, data))
+    }
+
+    /// Update a document
+    pub fn update(&self, collection: &str, document_id: Uuid, data: serde_json::Value) -> Result<Document> {
+        self.runtime.block_on(self.inner().update(collection, document_id, data))
+    }
+
+    /// Shallow-merge `partial` into a document
+    pub fn patch(&self, collection: &str, document_id: Uuid, partial: serde_json::Value) -> Result<Document> {
+        self.runtime.block_on(self.inner().patch(collection, document_id, partial))
+    }
+
+    /// Apply atomic field operators to a document; see the async `modify`
+    pub fn modify(&self, collection: &str, document_id: Uuid, update: UpdateSpec) -> Result<Document> {
+        self.runtime.block_on(self.inner().modify(collection, document_id, update))
+    }
+
+    /// Delete a document, returning whether it existed
+    pub fn delete(&self, collection: &str, document_id: Uuid) -> Result<bool> {
+        self.runtime.block_on(self.inner().delete(collection, document_id))
+    }
+
+    /// List collections
+    pub fn list_collections(&self) -> Result<Vec<Collection>> {
+        self.runtime.block_on(self.inner().list_collections())
+    }
+
+    /// Subscribe to changes
+    pub fn subscribe(&self, query: &str) -> Result<Subscription> {
+        let inner = self.runtime.block_on(self.inner().subscribe(query))?;
+        Ok(Subscription {
+            inner: Some(inner),
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    /// Subscribe to changes using a structured query
+    pub fn subscribe_query(&self, query: &QueryBuilder) -> Result<Subscription> {
+        let inner = self.runtime.block_on(self.inner().subscribe_query(query))?;
+        Ok(Subscription {
+            inner: Some(inner),
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    /// Subscribe to changes, collecting the initial results first; see the
+    /// async `subscribe_with_snapshot`
+    pub fn subscribe_with_snapshot(&self, query: &str) -> Result<(Vec<Document>, Subscription)> {
+        let (snapshot, inner) = self.runtime.block_on(self.inner().subscribe_with_snapshot(query))?;
+        let subscription = Subscription {
+            inner: Some(inner),
+            runtime: self.runtime.clone(),
+        };
+        Ok((snapshot, subscription))
+    }
+
+    /// Largest frame this connection will send
+    pub fn max_message_size(&self) -> u32 {
+        self.inner().max_message_size()
+    }
+
+    /// Ping the server
+    pub fn ping(&self) -> Result<()> {
+        self.runtime.block_on(self.inner().ping())
+    }
+
+    /// Ping the server and return the round-trip time
+    pub fn ping_rtt(&self) -> Result<std::time::Duration> {
+        self.runtime.block_on(self.inner().ping_rtt())
+    }
+
+    /// Close the connection deterministically; see the async `close`
+    pub fn close(mut self) -> Result<()> {
+        let inner = self.inner.take().expect("client is only taken on close");
+        self.runtime.block_on(inner.close())
+    }
+}
+
+impl Drop for SquirrelDB {
+    fn drop(&mut self) {
+        let _guard = self.runtime.enter();
+        self.inner.take();
+    }
+}
+
+/// Blocking subscription handle; iterate it to wait for events
+pub struct Subscription {
+    inner: Option<client::Subscription>,
+    runtime: Arc<Runtime>,
+}
+
+impl Subscription {
+    /// Block until the next event, or `None` once the subscription has ended
+    pub fn next_event(&mut self) -> Option<SubEvent> {
+        let inner = self.inner.as_mut()?;
+        self.runtime.block_on(inner.next())
+    }
+
+    pub fn unsubscribe(mut self) -> Result<()> {
+        match self.inner.take() {
+            Some(inner) => self.runtime.block_on(inner.unsubscribe()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = SubEvent;
+
+    fn next(&mut self) -> Option<SubEvent> {
+        self.next_event()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Lets the async subscription schedule its unsubscribe
+        let _guard = self.runtime.enter();
+        self.inner.take();
+    }
+}
@@ -3,13 +3,32 @@
 // DO NOT EDIT MANUALLY
 
 use std::collections::HashMap;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use std::time::Duration;
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
 use tokio::net::TcpStream;
 
 /// Cache client options
 pub struct CacheOptions {
     pub host: String,
     pub port: u16,
+    /// Give up connecting (including the TLS handshake) after this long
+    pub connect_timeout: Option<Duration>,
+    /// Fail a command whose reply takes longer than this
+    pub command_timeout: Option<Duration>,
+    /// Send commands in the inline form (`GET foo\r\n`) for servers that
+    /// don't accept RESP arrays
+    pub inline_commands: bool,
+    /// Disable Nagle's algorithm so small commands go out immediately
+    pub tcp_nodelay: bool,
+    /// Connect to this Unix domain socket instead of `host:port`; TLS is not
+    /// applied; connecting fails on non-Unix targets
+    pub unix_socket: Option<PathBuf>,
+    /// Wrap the connection in TLS; connecting fails unless the `tls`
+    /// feature is enabled
+    pub tls: bool,
+    /// PEM file with a custom CA to trust instead of the webpki roots
+    pub ca_cert: Option<PathBuf>,
 }
 
 impl Default for CacheOptions {
@@ -17,10 +36,22 @@ impl Default for CacheOptions {
         Self {
             host: "localhost".to_string(),
             port: 6379,
+            connect_timeout: None,
+            command_timeout: None,
+            inline_commands: false,
+            tcp_nodelay: true,
+            unix_socket: None,
+            tls: false,
+            ca_cert: None,
         }
     }
 }
 
+/// Byte stream the cache client can speak RESP over
+trait CacheStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> CacheStream for T {}
+
 /// Cache error types
 #[derive(Debug)]
 pub enum CacheError {
@@ -28,6 +59,9 @@ pub enum CacheError {
     Io(std::io::Error),
     Protocol(String),
     Server(String),
+    Timeout,
+    /// The server closed the connection, possibly partway through a reply
+    ConnectionClosed,
 }
 
 impl std::fmt::Display for CacheError {
@@ -37,6 +71,8 @@ impl std::fmt::Display for CacheError {
             CacheError::Io(e) => write!(f, "IO error: {}", e),
             CacheError::Protocol(msg) => write!(f, "Protocol error: {}", msg),
             CacheError::Server(msg) => write!(f, "Server error: {}", msg),
+            CacheError::Timeout => write!(f, "Command timed out"),
+            CacheError::ConnectionClosed => write!(f, "Connection closed by server"),
         }
     }
 }
@@ -75,6 +111,11 @@ impl RespValue {
         }
     }
 
+    /// Floats come back as bulk strings, e.g. `$4\r\n10.5\r\n`
+    fn as_float(&self) -> Option<f64> {
+        self.as_string().and_then(|s| s.parse().ok())
+    }
+
     fn as_array(&self) -> Option<&Vec<RespValue>> {
         match self {
             RespValue::Array(Some(arr)) => Some(arr),
@@ -87,23 +128,178 @@ impl RespValue {
     }
 }
 
-fn encode_command(args: &[&str]) -> Vec<u8> {
+/// A message delivered to a subscribed connection
+#[derive(Debug, Clone, PartialEq)]
+pub enum PubSubMessage {
+    /// Published to a channel subscribed with `subscribe`
+    Message { channel: String, payload: String },
+    /// Published to a channel matching a `psubscribe` pattern
+    PMessage { pattern: String, channel: String, payload: String },
+}
+
+impl PubSubMessage {
+    /// `None` for anything that isn't a `message`/`pmessage` push, such as
+    /// subscription confirmations
+    fn from_resp(value: &RespValue) -> Option<Self> {
+        let parts: Vec<Option<String>> = value.as_array()?.iter().map(|v| v.as_string()).collect();
+        match parts.as_slice() {
+            [Some(kind), Some(channel), Some(payload)] if kind == "message" => Some(Self::Message {
+                channel: channel.clone(),
+                payload: payload.clone(),
+            }),
+            [Some(kind), Some(pattern), Some(channel), Some(payload)] if kind == "pmessage" => {
+                Some(Self::PMessage {
+                    pattern: pattern.clone(),
+                    channel: channel.clone(),
+                    payload: payload.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One entry of the server's slow query log
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowLogEntry {
+    pub id: i64,
+    /// Unix time the command was processed, in seconds
+    pub timestamp: i64,
+    pub duration_micros: i64,
+    pub args: Vec<String>,
+    /// Reported by newer servers only
+    pub client_addr: Option<String>,
+    pub client_name: Option<String>,
+}
+
+impl SlowLogEntry {
+    fn from_resp(value: &RespValue) -> Option<Self> {
+        let fields = value.as_array()?;
+        let args = fields.get(3)?.as_array()?.iter().filter_map(|a| a.as_string()).collect();
+        Some(Self {
+            id: fields.first()?.as_integer()?,
+            timestamp: fields.get(1)?.as_integer()?,
+            duration_micros: fields.get(2)?.as_integer()?,
+            args,
+            client_addr: fields.get(4).and_then(|v| v.as_string()),
+            client_name: fields.get(5).and_then(|v| v.as_string()).filter(|n| !n.is_empty()),
+        })
+    }
+}
+
+/// Encode a command whose arguments may be arbitrary bytes
+fn encode_command_bytes(args: &[&[u8]]) -> Vec<u8> {
     let mut buf = Vec::new();
     buf.extend_from_slice(format!("*{}\r\n", args.len()).as_bytes());
     for arg in args {
         buf.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
-        buf.extend_from_slice(arg.as_bytes());
+        buf.extend_from_slice(arg);
         buf.extend_from_slice(b"\r\n");
     }
     buf
 }
 
+fn encode_command(args: &[&str]) -> Vec<u8> {
+    let args: Vec<&[u8]> = args.iter().map(|arg| arg.as_bytes()).collect();
+    encode_command_bytes(&args)
+}
+
+/// Encode a command in the inline form, quoting arguments that contain
+/// whitespace, quotes or control characters
+fn encode_inline_command(args: &[&str]) -> Vec<u8> {
+    let mut line = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        let plain = !arg.is_empty()
+            && arg.chars().all(|c| {
+                !c.is_whitespace() && !c.is_control() && !matches!(c, '"' | '\'' | '\\')
+            });
+        if plain {
+            line.push_str(arg);
+            continue;
+        }
+        line.push('"');
+        for c in arg.chars() {
+            match c {
+                '"' => line.push_str("\\\""),
+                '\\' => line.push_str("\\\\"),
+                '\r' => line.push_str("\\r"),
+                '\n' => line.push_str("\\n"),
+                '\t' => line.push_str("\\t"),
+                c if c.is_control() => line.push_str(&format!("\\x{:02x}", c as u32)),
+                c => line.push(c),
+            }
+        }
+        line.push('"');
+    }
+    line.push_str("\r\n");
+    line.into_bytes()
+}
+
+/// Format a sorted set score the way the server parses it
+fn format_score(score: f64) -> String {
+    if score == f64::INFINITY {
+        "+inf".to_string()
+    } else if score == f64::NEG_INFINITY {
+        "-inf".to_string()
+    } else {
+        score.to_string()
+    }
+}
+
+/// A reply whose bulk strings are still raw bytes
+#[derive(Debug)]
+enum RawReply {
+    SimpleString(String),
+    Error(String),
+    Integer(i64),
+    BulkString(Option<Vec<u8>>),
+    Array(Option<Vec<RawReply>>),
+}
+
+impl RawReply {
+    /// Convert to a `RespValue`, failing on bulk strings that are not UTF-8
+    fn into_resp(self) -> Result<RespValue, CacheError> {
+        Ok(match self {
+            RawReply::SimpleString(s) => RespValue::SimpleString(s),
+            RawReply::Error(e) => RespValue::Error(e),
+            RawReply::Integer(i) => RespValue::Integer(i),
+            RawReply::BulkString(None) => RespValue::BulkString(None),
+            RawReply::BulkString(Some(data)) => {
+                let s = String::from_utf8(data)
+                    .map_err(|_| CacheError::Protocol("Invalid UTF-8 in bulk string".to_string()))?;
+                RespValue::BulkString(Some(s))
+            }
+            RawReply::Array(None) => RespValue::Array(None),
+            RawReply::Array(Some(items)) => RespValue::Array(Some(
+                items.into_iter().map(RawReply::into_resp).collect::<Result<_, _>>()?,
+            )),
+        })
+    }
+}
+
 async fn parse_resp<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<RespValue, CacheError> {
+    parse_raw(reader).await?.into_resp()
+}
+
+/// A read that hit end of stream means the server hung up mid-reply
+fn read_error(e: std::io::Error) -> CacheError {
+    if e.kind() == std::io::ErrorKind::UnexpectedEof {
+        CacheError::ConnectionClosed
+    } else {
+        CacheError::Io(e)
+    }
+}
+
+async fn parse_raw<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<RawReply, CacheError> {
     let mut line = String::new();
-    reader.read_line(&mut line).await?;
+    reader.read_line(&mut line).await.map_err(read_error)?;
 
-    if line.is_empty() {
-        return Err(CacheError::Protocol("Empty response".to_string()));
+    // Nothing at all, or a line cut off before its terminator
+    if !line.ends_with('\n') {
+        return Err(CacheError::ConnectionClosed);
     }
 
     let line = line.trim_end_matches("\r\n").trim_end_matches('\n');
@@ -115,86 +311,230 @@ async fn parse_resp<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<RespVa
     let content = &line[1..];
 
     match prefix {
-        '+' => Ok(RespValue::SimpleString(content.to_string())),
-        '-' => Ok(RespValue::Error(content.to_string())),
+        '+' => Ok(RawReply::SimpleString(content.to_string())),
+        '-' => Ok(RawReply::Error(content.to_string())),
         ':' => {
             let i = content.parse::<i64>()
                 .map_err(|_| CacheError::Protocol(format!("Invalid integer: {}", content)))?;
-            Ok(RespValue::Integer(i))
+            Ok(RawReply::Integer(i))
         }
         '$' => {
             let len = content.parse::<i64>()
                 .map_err(|_| CacheError::Protocol(format!("Invalid bulk string length: {}", content)))?;
 
             if len < 0 {
-                return Ok(RespValue::BulkString(None));
+                return Ok(RawReply::BulkString(None));
             }
 
             let len = len as usize;
             let mut data = vec![0u8; len];
-            reader.read_exact(&mut data).await?;
+            reader.read_exact(&mut data).await.map_err(read_error)?;
 
             let mut crlf = [0u8; 2];
-            reader.read_exact(&mut crlf).await?;
-
-            let s = String::from_utf8(data)
-                .map_err(|_| CacheError::Protocol("Invalid UTF-8 in bulk string".to_string()))?;
+            reader.read_exact(&mut crlf).await.map_err(read_error)?;
 
-            Ok(RespValue::BulkString(Some(s)))
+            Ok(RawReply::BulkString(Some(data)))
         }
         '*' => {
             let count = content.parse::<i64>()
                 .map_err(|_| CacheError::Protocol(format!("Invalid array length: {}", content)))?;
 
             if count < 0 {
-                return Ok(RespValue::Array(None));
+                return Ok(RawReply::Array(None));
             }
 
             let count = count as usize;
             let mut items = Vec::with_capacity(count);
 
             for _ in 0..count {
-                items.push(Box::pin(parse_resp(reader)).await?);
+                items.push(Box::pin(parse_raw(reader)).await?);
             }
 
-            Ok(RespValue::Array(Some(items)))
+            Ok(RawReply::Array(Some(items)))
         }
         _ => Err(CacheError::Protocol(format!("Unknown RESP prefix: {}", prefix))),
     }
 }
 
+#[cfg(feature = "tls")]
+async fn connect_tls(stream: TcpStream, opts: &CacheOptions) -> Result<Box<dyn CacheStream>, CacheError> {
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::TlsConnector;
+
+    let mut roots = RootCertStore::empty();
+    match &opts.ca_cert {
+        Some(path) => {
+            let certs = CertificateDer::pem_file_iter(path)
+                .map_err(|e| CacheError::Connection(format!("Failed to read CA {}: {}", path.display(), e)))?;
+            for cert in certs {
+                let cert = cert
+                    .map_err(|e| CacheError::Connection(format!("Invalid CA {}: {}", path.display(), e)))?;
+                roots
+                    .add(cert)
+                    .map_err(|e| CacheError::Connection(format!("Invalid CA {}: {}", path.display(), e)))?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(opts.host.clone())
+        .map_err(|e| CacheError::Connection(format!("Invalid TLS server name {}: {}", opts.host, e)))?;
+
+    let stream = TlsConnector::from(std::sync::Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| CacheError::Connection(format!("TLS handshake failed: {}", e)))?;
+
+    Ok(Box::new(stream))
+}
+
 /// Redis-compatible async cache client
 pub struct CacheClient {
-    stream: BufReader<TcpStream>,
+    stream: BufReader<Box<dyn CacheStream>>,
+    command_timeout: Option<Duration>,
+    inline_commands: bool,
+    /// Set once a reply is abandoned mid-read; the stream is out of sync after that
+    poisoned: bool,
 }
 
 impl CacheClient {
     /// Connect to a cache server
     pub async fn connect(opts: Option<CacheOptions>) -> Result<Self, CacheError> {
         let opts = opts.unwrap_or_default();
+        let stream = match opts.connect_timeout {
+            Some(limit) => tokio::time::timeout(limit, Self::establish(&opts))
+                .await
+                .map_err(|_| CacheError::Connection(format!(
+                    "Timed out connecting to {}:{} after {:?}",
+                    opts.host, opts.port, limit
+                )))??,
+            None => Self::establish(&opts).await?,
+        };
+
+        Ok(Self::from_boxed(stream, &opts))
+    }
+
+    /// Speak RESP over an already-connected stream, e.g. a `UnixStream` or an
+    /// in-memory duplex. Only the command options apply; `host`, `port`, TLS
+    /// and connect settings are ignored.
+    pub fn from_stream<S>(stream: S, opts: Option<CacheOptions>) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        Self::from_boxed(Box::new(stream), &opts.unwrap_or_default())
+    }
+
+    fn from_boxed(stream: Box<dyn CacheStream>, opts: &CacheOptions) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            command_timeout: opts.command_timeout,
+            inline_commands: opts.inline_commands,
+            poisoned: false,
+        }
+    }
+
+    async fn establish(opts: &CacheOptions) -> Result<Box<dyn CacheStream>, CacheError> {
+        #[cfg(not(feature = "tls"))]
+        if opts.tls {
+            return Err(CacheError::Connection(
+                "TLS requires the `tls` feature".to_string(),
+            ));
+        }
+
+        #[cfg(unix)]
+        if let Some(path) = &opts.unix_socket {
+            let stream = tokio::net::UnixStream::connect(path).await.map_err(|e| {
+                CacheError::Connection(format!("Failed to connect to {}: {}", path.display(), e))
+            })?;
+            return Ok(Box::new(stream));
+        }
+        #[cfg(not(unix))]
+        if opts.unix_socket.is_some() {
+            return Err(CacheError::Connection(
+                "Unix domain sockets are not supported on this platform".to_string(),
+            ));
+        }
+
         let addr = format!("{}:{}", opts.host, opts.port);
 
         let stream = TcpStream::connect(&addr)
             .await
             .map_err(|e| CacheError::Connection(format!("Failed to connect to {}: {}", addr, e)))?;
+        stream.set_nodelay(opts.tcp_nodelay)?;
+
+        #[cfg(feature = "tls")]
+        let stream: Box<dyn CacheStream> = if opts.tls {
+            connect_tls(stream, opts).await?
+        } else {
+            Box::new(stream)
+        };
+        #[cfg(not(feature = "tls"))]
+        let stream: Box<dyn CacheStream> = Box::new(stream);
 
-        Ok(Self { stream: BufReader::new(stream) })
+        Ok(stream)
     }
 
     async fn command(&mut self, args: &[&str]) -> Result<RespValue, CacheError> {
-        let cmd = encode_command(args);
-        self.stream.get_mut().write_all(&cmd).await?;
-        self.stream.get_mut().flush().await?;
+        let cmd = if self.inline_commands {
+            encode_inline_command(args)
+        } else {
+            encode_command(args)
+        };
+        self.exchange(&cmd).await?.into_resp()
+    }
 
-        let resp = parse_resp(&mut self.stream).await?;
+    /// Send a command with binary arguments and keep bulk replies as bytes.
+    /// Always uses the multi-bulk form, since inline commands cannot carry
+    /// arbitrary bytes.
+    async fn command_bytes(&mut self, args: &[&[u8]]) -> Result<RawReply, CacheError> {
+        self.exchange(&encode_command_bytes(args)).await
+    }
 
-        if let RespValue::Error(msg) = &resp {
-            return Err(CacheError::Server(msg.clone()));
+    async fn exchange(&mut self, cmd: &[u8]) -> Result<RawReply, CacheError> {
+        if self.poisoned {
+            return Err(CacheError::Connection(
+                "Connection unusable after a timed out command".to_string(),
+            ));
+        }
+
+        let resp = match self.command_timeout {
+            Some(limit) => match tokio::time::timeout(limit, self.roundtrip(cmd)).await {
+                Ok(resp) => resp?,
+                Err(_) => {
+                    self.poisoned = true;
+                    return Err(CacheError::Timeout);
+                }
+            },
+            None => self.roundtrip(cmd).await?,
+        };
+
+        if let RawReply::Error(msg) = resp {
+            return Err(CacheError::Server(msg));
         }
 
         Ok(resp)
     }
 
+    /// Send an arbitrary command and return the reply as-is.
+    ///
+    /// Error replies become `CacheError::Server`; nothing else about the reply
+    /// is checked, so interpreting it is up to the caller.
+    pub async fn raw(&mut self, args: &[&str]) -> Result<RespValue, CacheError> {
+        self.command(args).await
+    }
+
+    async fn roundtrip(&mut self, cmd: &[u8]) -> Result<RawReply, CacheError> {
+        self.stream.get_mut().write_all(cmd).await?;
+        self.stream.get_mut().flush().await?;
+
+        parse_raw(&mut self.stream).await
+    }
+
     /// Get a value by key
     pub async fn get(&mut self, key: &str) -> Result<Option<String>, CacheError> {
         let resp = self.command(&["GET", key]).await?;
@@ -218,6 +558,25 @@ impl CacheClient {
         }
     }
 
+    /// Set a value and return the previous one
+    pub async fn getset(&mut self, key: &str, value: &str) -> Result<Option<String>, CacheError> {
+        let resp = self.command(&["GETSET", key, value]).await?;
+        Ok(resp.as_string())
+    }
+
+    /// Get a value and delete the key
+    pub async fn getdel(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+        let resp = self.command(&["GETDEL", key]).await?;
+        Ok(resp.as_string())
+    }
+
+    /// Append to a value, returning the new length
+    pub async fn append(&mut self, key: &str, value: &str) -> Result<i64, CacheError> {
+        let resp = self.command(&["APPEND", key, value]).await?;
+        resp.as_integer()
+            .ok_or_else(|| CacheError::Protocol("APPEND did not return integer".to_string()))
+    }
+
     /// Delete a key
     pub async fn del(&mut self, key: &str) -> Result<bool, CacheError> {
         let resp = self.command(&["DEL", key]).await?;
@@ -230,6 +589,29 @@ impl CacheClient {
         Ok(resp.as_integer().unwrap_or(0) > 0)
     }
 
+    /// Get the type of the value stored at a key (`none` if missing)
+    pub async fn key_type(&mut self, key: &str) -> Result<String, CacheError> {
+        let resp = self.command(&["TYPE", key]).await?;
+        resp.as_string()
+            .ok_or_else(|| CacheError::Protocol("TYPE did not return string".to_string()))
+    }
+
+    /// Rename a key, overwriting the destination
+    pub async fn rename(&mut self, src: &str, dst: &str) -> Result<(), CacheError> {
+        let resp = self.command(&["RENAME", src, dst]).await?;
+        if resp.is_ok() {
+            Ok(())
+        } else {
+            Err(CacheError::Protocol("RENAME did not return OK".to_string()))
+        }
+    }
+
+    /// Rename a key only if the destination does not exist
+    pub async fn renamenx(&mut self, src: &str, dst: &str) -> Result<bool, CacheError> {
+        let resp = self.command(&["RENAMENX", src, dst]).await?;
+        Ok(resp.as_integer().unwrap_or(0) > 0)
+    }
+
     /// Set expiration on a key
     pub async fn expire(&mut self, key: &str, seconds: u64) -> Result<bool, CacheError> {
         let ttl_str = seconds.to_string();
@@ -243,6 +625,33 @@ impl CacheClient {
         Ok(resp.as_integer().unwrap_or(-2))
     }
 
+    /// Set expiration on a key in milliseconds
+    pub async fn pexpire(&mut self, key: &str, millis: u64) -> Result<bool, CacheError> {
+        let ttl_str = millis.to_string();
+        let resp = self.command(&["PEXPIRE", key, &ttl_str]).await?;
+        Ok(resp.as_integer().unwrap_or(0) > 0)
+    }
+
+    /// Expire a key at a Unix timestamp in seconds
+    pub async fn expireat(&mut self, key: &str, unix_secs: u64) -> Result<bool, CacheError> {
+        let at_str = unix_secs.to_string();
+        let resp = self.command(&["EXPIREAT", key, &at_str]).await?;
+        Ok(resp.as_integer().unwrap_or(0) > 0)
+    }
+
+    /// Expire a key at a Unix timestamp in milliseconds
+    pub async fn pexpireat(&mut self, key: &str, unix_millis: u64) -> Result<bool, CacheError> {
+        let at_str = unix_millis.to_string();
+        let resp = self.command(&["PEXPIREAT", key, &at_str]).await?;
+        Ok(resp.as_integer().unwrap_or(0) > 0)
+    }
+
+    /// Get TTL of a key in milliseconds; -1 without expiry, -2 if missing
+    pub async fn pttl(&mut self, key: &str) -> Result<i64, CacheError> {
+        let resp = self.command(&["PTTL", key]).await?;
+        Ok(resp.as_integer().unwrap_or(-2))
+    }
+
     /// Increment a key's value
     pub async fn incr(&mut self, key: &str) -> Result<i64, CacheError> {
         let resp = self.command(&["INCR", key]).await?;
@@ -265,6 +674,22 @@ impl CacheClient {
             .ok_or_else(|| CacheError::Protocol("INCRBY did not return integer".to_string()))
     }
 
+    /// Increment by a floating point amount
+    pub async fn incrbyfloat(&mut self, key: &str, amount: f64) -> Result<f64, CacheError> {
+        let amount_str = amount.to_string();
+        let resp = self.command(&["INCRBYFLOAT", key, &amount_str]).await?;
+        resp.as_float()
+            .ok_or_else(|| CacheError::Protocol("INCRBYFLOAT did not return float".to_string()))
+    }
+
+    /// Increment a hash field by amount
+    pub async fn hincrby(&mut self, key: &str, field: &str, amount: i64) -> Result<i64, CacheError> {
+        let amount_str = amount.to_string();
+        let resp = self.command(&["HINCRBY", key, field, &amount_str]).await?;
+        resp.as_integer()
+            .ok_or_else(|| CacheError::Protocol("HINCRBY did not return integer".to_string()))
+    }
+
     /// Get all keys matching a pattern
     pub async fn keys(&mut self, pattern: &str) -> Result<Vec<String>, CacheError> {
         let resp = self.command(&["KEYS", pattern]).await?;
@@ -306,6 +731,137 @@ impl CacheClient {
         }
     }
 
+    /// Get multiple values as raw bytes, for values that need not be UTF-8
+    pub async fn mget_bytes(&mut self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>, CacheError> {
+        let mut args: Vec<&[u8]> = vec![b"MGET"];
+        args.extend(keys.iter().map(|k| k.as_bytes()));
+        let resp = self.command_bytes(&args).await?;
+
+        match resp {
+            RawReply::Array(Some(arr)) => Ok(arr.into_iter().map(|v| match v {
+                RawReply::BulkString(data) => data,
+                RawReply::SimpleString(s) => Some(s.into_bytes()),
+                _ => None,
+            }).collect()),
+            _ => Ok(vec![None; keys.len()]),
+        }
+    }
+
+    /// Set multiple values from raw bytes
+    pub async fn mset_bytes(&mut self, pairs: &[(&str, &[u8])]) -> Result<(), CacheError> {
+        let mut args: Vec<&[u8]> = vec![b"MSET"];
+        for (k, v) in pairs {
+            args.push(k.as_bytes());
+            args.push(v);
+        }
+        let resp = self.command_bytes(&args).await?;
+
+        if matches!(&resp, RawReply::SimpleString(s) if s == "OK") {
+            Ok(())
+        } else {
+            Err(CacheError::Protocol("MSET did not return OK".to_string()))
+        }
+    }
+
+    /// Add a member to a sorted set, returning the number of new members
+    pub async fn zadd(&mut self, key: &str, score: f64, member: &str) -> Result<i64, CacheError> {
+        let score_str = format_score(score);
+        let resp = self.command(&["ZADD", key, &score_str, member]).await?;
+        resp.as_integer()
+            .ok_or_else(|| CacheError::Protocol("ZADD did not return integer".to_string()))
+    }
+
+    /// Get members of a sorted set by rank, lowest score first.
+    /// Scores are only populated when `withscores` is set.
+    pub async fn zrange(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+        withscores: bool,
+    ) -> Result<Vec<(String, Option<f64>)>, CacheError> {
+        self.range_command("ZRANGE", key, start, stop, withscores).await
+    }
+
+    /// Get members of a sorted set by rank, highest score first
+    pub async fn zrevrange(
+        &mut self,
+        key: &str,
+        start: i64,
+        stop: i64,
+        withscores: bool,
+    ) -> Result<Vec<(String, Option<f64>)>, CacheError> {
+        self.range_command("ZREVRANGE", key, start, stop, withscores).await
+    }
+
+    async fn range_command(
+        &mut self,
+        cmd: &str,
+        key: &str,
+        start: i64,
+        stop: i64,
+        withscores: bool,
+    ) -> Result<Vec<(String, Option<f64>)>, CacheError> {
+        let start_str = start.to_string();
+        let stop_str = stop.to_string();
+        let mut args = vec![cmd, key, &start_str, &stop_str];
+        if withscores {
+            args.push("WITHSCORES");
+        }
+        let resp = self.command(&args).await?;
+        let arr = match resp.as_array() {
+            Some(arr) => arr,
+            None => return Ok(Vec::new()),
+        };
+
+        let invalid = || CacheError::Protocol(format!("{} returned an invalid member list", cmd));
+        if !withscores {
+            return arr.iter()
+                .map(|v| v.as_string().map(|m| (m, None)).ok_or_else(invalid))
+                .collect();
+        }
+        if arr.len() % 2 != 0 {
+            return Err(invalid());
+        }
+        arr.chunks(2)
+            .map(|pair| {
+                let member = pair[0].as_string().ok_or_else(invalid)?;
+                let score = pair[1].as_float().ok_or_else(invalid)?;
+                Ok((member, Some(score)))
+            })
+            .collect()
+    }
+
+    /// Get the score of a sorted set member
+    pub async fn zscore(&mut self, key: &str, member: &str) -> Result<Option<f64>, CacheError> {
+        let resp = self.command(&["ZSCORE", key, member]).await?;
+        match resp {
+            RespValue::BulkString(None) => Ok(None),
+            _ => resp.as_float()
+                .map(Some)
+                .ok_or_else(|| CacheError::Protocol("ZSCORE did not return float".to_string())),
+        }
+    }
+
+    /// Get the rank of a sorted set member, lowest score first
+    pub async fn zrank(&mut self, key: &str, member: &str) -> Result<Option<i64>, CacheError> {
+        let resp = self.command(&["ZRANK", key, member]).await?;
+        Ok(resp.as_integer())
+    }
+
+    /// Block until `num_replicas` replicas have acknowledged all previous
+    /// writes on this connection, or `timeout_ms` elapses (0 waits forever).
+    /// Returns how many replicas acknowledged, which may be fewer than asked
+    /// for on timeout. A `command_timeout` shorter than `timeout_ms` cuts the
+    /// wait short with `CacheError::Timeout`.
+    pub async fn wait(&mut self, num_replicas: usize, timeout_ms: u64) -> Result<i64, CacheError> {
+        let replicas_str = num_replicas.to_string();
+        let timeout_str = timeout_ms.to_string();
+        let resp = self.command(&["WAIT", &replicas_str, &timeout_str]).await?;
+        resp.as_integer()
+            .ok_or_else(|| CacheError::Protocol("WAIT did not return integer".to_string()))
+    }
+
     /// Get number of keys in database
     pub async fn dbsize(&mut self) -> Result<i64, CacheError> {
         let resp = self.command(&["DBSIZE"]).await?;
@@ -341,6 +897,144 @@ impl CacheClient {
         Ok(result)
     }
 
+    /// Watch `keys` for the next [`exec`](Self::exec): if any of them changes
+    /// before it runs, the transaction is aborted. Cleared by `exec`,
+    /// [`discard`](Self::discard) or [`unwatch`](Self::unwatch).
+    pub async fn watch(&mut self, keys: &[&str]) -> Result<(), CacheError> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let mut args = vec!["WATCH"];
+        args.extend(keys);
+        self.expect_ok(&args).await
+    }
+
+    /// Stop watching all keys
+    pub async fn unwatch(&mut self) -> Result<(), CacheError> {
+        self.expect_ok(&["UNWATCH"]).await
+    }
+
+    /// Start a transaction. Until [`exec`](Self::exec) or
+    /// [`discard`](Self::discard), the server queues commands and replies
+    /// `QUEUED`, so send them with [`raw`](Self::raw); the typed methods
+    /// expect their normal replies.
+    pub async fn multi(&mut self) -> Result<(), CacheError> {
+        self.expect_ok(&["MULTI"]).await
+    }
+
+    /// Run the queued commands and return their replies in order, or
+    /// `Ok(None)` if a watched key changed and the transaction was aborted,
+    /// in which case nothing ran and it can be retried. A command that failed
+    /// inside the transaction shows up as a `RespValue::Error` reply.
+    pub async fn exec(&mut self) -> Result<Option<Vec<RespValue>>, CacheError> {
+        match self.command(&["EXEC"]).await? {
+            RespValue::Array(replies) => Ok(replies),
+            _ => Err(CacheError::Protocol("EXEC did not return array".to_string())),
+        }
+    }
+
+    /// Drop the queued commands and end the transaction
+    pub async fn discard(&mut self) -> Result<(), CacheError> {
+        self.expect_ok(&["DISCARD"]).await
+    }
+
+    async fn expect_ok(&mut self, args: &[&str]) -> Result<(), CacheError> {
+        if self.command(args).await?.is_ok() {
+            Ok(())
+        } else {
+            Err(CacheError::Protocol(format!("{} did not return OK", args[0])))
+        }
+    }
+
+    /// Subscribe to channels. The connection then only receives messages;
+    /// read them with [`next_message`](Self::next_message) and use a separate
+    /// client for other commands.
+    pub async fn subscribe(&mut self, channels: &[&str]) -> Result<(), CacheError> {
+        self.subscribe_command("SUBSCRIBE", channels).await
+    }
+
+    /// Subscribe to every channel matching the glob `patterns`, e.g. `news.*`.
+    /// Matching messages arrive as [`PubSubMessage::PMessage`] carrying both the
+    /// pattern and the concrete channel.
+    pub async fn psubscribe(&mut self, patterns: &[&str]) -> Result<(), CacheError> {
+        self.subscribe_command("PSUBSCRIBE", patterns).await
+    }
+
+    async fn subscribe_command(&mut self, cmd: &str, names: &[&str]) -> Result<(), CacheError> {
+        if names.is_empty() {
+            return Ok(());
+        }
+        let mut args = vec![cmd];
+        args.extend(names);
+        // The server confirms each name with its own reply
+        self.command(&args).await?;
+        for _ in 1..names.len() {
+            if let RespValue::Error(msg) = parse_resp(&mut self.stream).await? {
+                return Err(CacheError::Server(msg));
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for the next published message on a subscribed connection.
+    /// Not subject to `command_timeout`.
+    pub async fn next_message(&mut self) -> Result<PubSubMessage, CacheError> {
+        loop {
+            let resp = parse_resp(&mut self.stream).await?;
+            if let RespValue::Error(msg) = resp {
+                return Err(CacheError::Server(msg));
+            }
+            if let Some(message) = PubSubMessage::from_resp(&resp) {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Get up to `count` of the most recent slow log entries, newest first
+    pub async fn slowlog_get(&mut self, count: usize) -> Result<Vec<SlowLogEntry>, CacheError> {
+        let count_str = count.to_string();
+        let resp = self.command(&["SLOWLOG", "GET", &count_str]).await?;
+        let entries = resp.as_array()
+            .ok_or_else(|| CacheError::Protocol("SLOWLOG GET did not return array".to_string()))?;
+        entries.iter()
+            .map(|entry| {
+                SlowLogEntry::from_resp(entry)
+                    .ok_or_else(|| CacheError::Protocol("Malformed SLOWLOG entry".to_string()))
+            })
+            .collect()
+    }
+
+    /// Clear the slow log
+    pub async fn slowlog_reset(&mut self) -> Result<(), CacheError> {
+        let resp = self.command(&["SLOWLOG", "RESET"]).await?;
+        if resp.is_ok() {
+            Ok(())
+        } else {
+            Err(CacheError::Protocol("SLOWLOG RESET did not return OK".to_string()))
+        }
+    }
+
+    /// Bytes a key and its value take in memory, or `None` if it doesn't exist
+    pub async fn memory_usage(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+        let resp = self.command(&["MEMORY", "USAGE", key]).await?;
+        Self::optional_integer(resp, "MEMORY USAGE")
+    }
+
+    /// Seconds since a key was last read or written, or `None` if it doesn't exist
+    pub async fn object_idletime(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+        let resp = self.command(&["OBJECT", "IDLETIME", key]).await?;
+        Self::optional_integer(resp, "OBJECT IDLETIME")
+    }
+
+    fn optional_integer(resp: RespValue, cmd: &str) -> Result<Option<i64>, CacheError> {
+        match resp {
+            RespValue::BulkString(None) | RespValue::Array(None) => Ok(None),
+            _ => resp.as_integer()
+                .map(Some)
+                .ok_or_else(|| CacheError::Protocol(format!("{} did not return integer", cmd))),
+        }
+    }
+
     /// Ping the server
     pub async fn ping(&mut self) -> Result<(), CacheError> {
         let resp = self.command(&["PING"]).await?;
@@ -356,3 +1050,310 @@ impl CacheClient {
         Ok(())
     }
 }
+
+/// Synchronous cache client for code without an async runtime, enabled by the
+/// `blocking` feature. Wraps every command of the async client with the same
+/// RESP handling; `from_stream` has no blocking counterpart.
+#[cfg(feature = "blocking")]
+pub mod blocking {
+    use std::collections::HashMap;
+
+    use super::{CacheError, CacheOptions, PubSubMessage, RespValue, SlowLogEntry};
+    use crate::blocking::Runtime;
+
+    /// Blocking Redis-compatible cache client
+    pub struct CacheClient {
+        // Dropped before the runtime it runs on
+        inner: super::CacheClient,
+        runtime: Runtime,
+    }
+
+    impl CacheClient {
+        /// Connect to a cache server
+        pub fn connect(opts: Option<CacheOptions>) -> Result<Self, CacheError> {
+            let runtime = Runtime::new()?;
+            let inner = runtime.block_on(super::CacheClient::connect(opts))?;
+            Ok(Self { inner, runtime })
+        }
+
+        /// Send an arbitrary command and return the reply as-is
+        pub fn raw(&mut self, args: &[&str]) -> Result<RespValue, CacheError> {
+            self.runtime.block_on(self.inner.raw(args))
+        }
+
+        /// Get a value by key
+        pub fn get(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+            self.runtime.block_on(self.inner.get(key))
+        }
+
+        /// Set a value with optional TTL in seconds
+        pub fn set(&mut self, key: &str, value: &str, ttl: Option<u64>) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.set(key, value, ttl))
+        }
+
+        /// Set a value and return the previous one
+        pub fn getset(&mut self, key: &str, value: &str) -> Result<Option<String>, CacheError> {
+            self.runtime.block_on(self.inner.getset(key, value))
+        }
+
+        /// Get a value and delete the key
+        pub fn getdel(&mut self, key: &str) -> Result<Option<String>, CacheError> {
+            self.runtime.block_on(self.inner.getdel(key))
+        }
+
+        /// Append to a value, returning the new length
+        pub fn append(&mut self, key: &str, value: &str) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.append(key, value))
+        }
+
+        /// Delete a key
+        pub fn del(&mut self, key: &str) -> Result<bool, CacheError> {
+            self.runtime.block_on(self.inner.del(key))
+        }
+
+        /// Check if a key exists
+        pub fn exists(&mut self, key: &str) -> Result<bool, CacheError> {
+            self.runtime.block_on(self.inner.exists(key))
+        }
+
+        /// Get the type of the value stored at a key (`none` if missing)
+        pub fn key_type(&mut self, key: &str) -> Result<String, CacheError> {
+            self.runtime.block_on(self.inner.key_type(key))
+        }
+
+        /// Rename a key, overwriting the destination
+        pub fn rename(&mut self, src: &str, dst: &str) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.rename(src, dst))
+        }
+
+        /// Rename a key only if the destination does not exist
+        pub fn renamenx(&mut self, src: &str, dst: &str) -> Result<bool, CacheError> {
+            self.runtime.block_on(self.inner.renamenx(src, dst))
+        }
+
+        /// Set expiration on a key
+        pub fn expire(&mut self, key: &str, seconds: u64) -> Result<bool, CacheError> {
+            self.runtime.block_on(self.inner.expire(key, seconds))
+        }
+
+        /// Get TTL of a key
+        pub fn ttl(&mut self, key: &str) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.ttl(key))
+        }
+
+        /// Set expiration on a key in milliseconds
+        pub fn pexpire(&mut self, key: &str, millis: u64) -> Result<bool, CacheError> {
+            self.runtime.block_on(self.inner.pexpire(key, millis))
+        }
+
+        /// Expire a key at a Unix timestamp in seconds
+        pub fn expireat(&mut self, key: &str, unix_secs: u64) -> Result<bool, CacheError> {
+            self.runtime.block_on(self.inner.expireat(key, unix_secs))
+        }
+
+        /// Expire a key at a Unix timestamp in milliseconds
+        pub fn pexpireat(&mut self, key: &str, unix_millis: u64) -> Result<bool, CacheError> {
+            self.runtime.block_on(self.inner.pexpireat(key, unix_millis))
+        }
+
+        /// Get TTL of a key in milliseconds; -1 without expiry, -2 if missing
+        pub fn pttl(&mut self, key: &str) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.pttl(key))
+        }
+
+        /// Increment a key's value
+        pub fn incr(&mut self, key: &str) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.incr(key))
+        }
+
+        /// Decrement a key's value
+        pub fn decr(&mut self, key: &str) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.decr(key))
+        }
+
+        /// Increment a key's value by `amount`
+        pub fn incrby(&mut self, key: &str, amount: i64) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.incrby(key, amount))
+        }
+
+        /// Increment by a floating point amount
+        pub fn incrbyfloat(&mut self, key: &str, amount: f64) -> Result<f64, CacheError> {
+            self.runtime.block_on(self.inner.incrbyfloat(key, amount))
+        }
+
+        /// Increment a hash field by amount
+        pub fn hincrby(&mut self, key: &str, field: &str, amount: i64) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.hincrby(key, field, amount))
+        }
+
+        /// Get keys matching a pattern
+        pub fn keys(&mut self, pattern: &str) -> Result<Vec<String>, CacheError> {
+            self.runtime.block_on(self.inner.keys(pattern))
+        }
+
+        /// Get multiple values
+        pub fn mget(&mut self, keys: &[&str]) -> Result<Vec<Option<String>>, CacheError> {
+            self.runtime.block_on(self.inner.mget(keys))
+        }
+
+        /// Set multiple values
+        pub fn mset(&mut self, pairs: &[(&str, &str)]) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.mset(pairs))
+        }
+
+        /// Get multiple values as raw bytes
+        pub fn mget_bytes(&mut self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>, CacheError> {
+            self.runtime.block_on(self.inner.mget_bytes(keys))
+        }
+
+        /// Set multiple values from raw bytes
+        pub fn mset_bytes(&mut self, pairs: &[(&str, &[u8])]) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.mset_bytes(pairs))
+        }
+
+        /// Add a member to a sorted set, returning the number of new members
+        pub fn zadd(&mut self, key: &str, score: f64, member: &str) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.zadd(key, score, member))
+        }
+
+        /// Get members of a sorted set by rank, lowest score first.
+        /// Scores are only populated when `withscores` is set.
+        pub fn zrange(
+            &mut self,
+            key: &str,
+            start: i64,
+            stop: i64,
+            withscores: bool,
+        ) -> Result<Vec<(String, Option<f64>)>, CacheError> {
+            self.runtime.block_on(self.inner.zrange(key, start, stop, withscores))
+        }
+
+        /// Get members of a sorted set by rank, highest score first
+        pub fn zrevrange(
+            &mut self,
+            key: &str,
+            start: i64,
+            stop: i64,
+            withscores: bool,
+        ) -> Result<Vec<(String, Option<f64>)>, CacheError> {
+            self.runtime.block_on(self.inner.zrevrange(key, start, stop, withscores))
+        }
+
+        /// Get the score of a sorted set member
+        pub fn zscore(&mut self, key: &str, member: &str) -> Result<Option<f64>, CacheError> {
+            self.runtime.block_on(self.inner.zscore(key, member))
+        }
+
+        /// Get the rank of a sorted set member, lowest score first
+        pub fn zrank(&mut self, key: &str, member: &str) -> Result<Option<i64>, CacheError> {
+            self.runtime.block_on(self.inner.zrank(key, member))
+        }
+
+        /// Block until `num_replicas` replicas have acknowledged all previous
+        /// writes on this connection, or `timeout_ms` elapses (0 waits forever).
+        /// Returns how many replicas acknowledged, which may be fewer than asked
+        /// for on timeout. A `command_timeout` shorter than `timeout_ms` cuts the
+        /// wait short with `CacheError::Timeout`.
+        pub fn wait(&mut self, num_replicas: usize, timeout_ms: u64) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.wait(num_replicas, timeout_ms))
+        }
+
+        /// Get database size
+        pub fn dbsize(&mut self) -> Result<i64, CacheError> {
+            self.runtime.block_on(self.inner.dbsize())
+        }
+
+        /// Delete all keys
+        pub fn flush(&mut self) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.flush())
+        }
+
+        /// Get server info
+        pub fn info(&mut self) -> Result<HashMap<String, String>, CacheError> {
+            self.runtime.block_on(self.inner.info())
+        }
+
+        /// Watch `keys` for the next [`exec`](Self::exec): if any of them changes
+        /// before it runs, the transaction is aborted. Cleared by `exec`,
+        /// [`discard`](Self::discard) or [`unwatch`](Self::unwatch).
+        pub fn watch(&mut self, keys: &[&str]) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.watch(keys))
+        }
+
+        /// Stop watching all keys
+        pub fn unwatch(&mut self) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.unwatch())
+        }
+
+        /// Start a transaction. Until [`exec`](Self::exec) or
+        /// [`discard`](Self::discard), the server queues commands and replies
+        /// `QUEUED`, so send them with [`raw`](Self::raw); the typed methods
+        /// expect their normal replies.
+        pub fn multi(&mut self) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.multi())
+        }
+
+        /// Run the queued commands and return their replies in order, or
+        /// `Ok(None)` if a watched key changed and the transaction was aborted,
+        /// in which case nothing ran and it can be retried. A command that failed
+        /// inside the transaction shows up as a `RespValue::Error` reply.
+        pub fn exec(&mut self) -> Result<Option<Vec<RespValue>>, CacheError> {
+            self.runtime.block_on(self.inner.exec())
+        }
+
+        /// Drop the queued commands and end the transaction
+        pub fn discard(&mut self) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.discard())
+        }
+
+        /// Subscribe to channels. The connection then only receives messages;
+        /// read them with [`next_message`](Self::next_message) and use a separate
+        /// client for other commands.
+        pub fn subscribe(&mut self, channels: &[&str]) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.subscribe(channels))
+        }
+
+        /// Subscribe to every channel matching the glob `patterns`, e.g. `news.*`.
+        /// Matching messages arrive as [`PubSubMessage::PMessage`] carrying both the
+        /// pattern and the concrete channel.
+        pub fn psubscribe(&mut self, patterns: &[&str]) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.psubscribe(patterns))
+        }
+
+        /// Wait for the next published message on a subscribed connection.
+        /// Not subject to `command_timeout`.
+        pub fn next_message(&mut self) -> Result<PubSubMessage, CacheError> {
+            self.runtime.block_on(self.inner.next_message())
+        }
+
+        /// Get up to `count` of the most recent slow log entries, newest first
+        pub fn slowlog_get(&mut self, count: usize) -> Result<Vec<SlowLogEntry>, CacheError> {
+            self.runtime.block_on(self.inner.slowlog_get(count))
+        }
+
+        /// Clear the slow log
+        pub fn slowlog_reset(&mut self) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.slowlog_reset())
+        }
+
+        /// Bytes a key and its value take in memory, or `None` if it doesn't exist
+        pub fn memory_usage(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+            self.runtime.block_on(self.inner.memory_usage(key))
+        }
+
+        /// Seconds since a key was last read or written, or `None` if it doesn't exist
+        pub fn object_idletime(&mut self, key: &str) -> Result<Option<i64>, CacheError> {
+            self.runtime.block_on(self.inner.object_idletime(key))
+        }
+
+        /// Ping the server
+        pub fn ping(&mut self) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.ping())
+        }
+
+        /// Close the connection
+        pub fn close(&mut self) -> Result<(), CacheError> {
+            self.runtime.block_on(self.inner.close())
+        }
+    }
+}
@@ -2,19 +2,94 @@
 // Generated by sdk-generator v0.1.0
 // DO NOT EDIT MANUALLY
 
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use futures::Stream;
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::io::{
+    AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, ReadHalf, WriteHalf,
+};
 use tokio::net::TcpStream;
-use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::sync::{oneshot, Notify, RwLock};
 use uuid::Uuid;
 
 use crate::error::{Error, Result};
+use crate::metrics::{error_kind, ClientMetrics, Counters, CountingTransport};
 use crate::protocol::*;
+use crate::query_cache::QueryCache;
+use crate::query::{ChangesOptions, Filter, QueryBuilder, StructuredQuery};
+
+/// Emit a `tracing` event when the `tracing` feature is enabled; expands to
+/// nothing otherwise
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+/// Byte stream the client can speak the SquirrelDB protocol over. TCP is the
+/// default; any duplex stream (e.g. `tokio::io::duplex`) can be passed to
+/// [`SquirrelDB::with_transport`] instead.
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> Transport for T {}
+
+type TransportReader = BufReader<ReadHalf<Box<dyn Transport>>>;
+type TransportWriter = BufWriter<WriteHalf<Box<dyn Transport>>>;
+
+/// Produces request ids; see [`ConnectOptions::with_id_generator`]
+#[derive(Clone)]
+pub struct IdGenerator(Arc<dyn Fn() -> String + Send + Sync>);
+
+impl IdGenerator {
+    pub fn new(f: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    fn next(&self) -> String {
+        (self.0)()
+    }
+}
+
+impl std::fmt::Debug for IdGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IdGenerator(..)")
+    }
+}
+
+/// Which way a logged frame travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Outbound,
+    Inbound,
+}
+
+/// Callback receiving raw frames; see [`ConnectOptions::with_frame_logger`]
+#[derive(Clone)]
+pub struct FrameLogger(Arc<FrameLogFn>);
+
+type FrameLogFn = dyn Fn(Direction, &[u8]) + Send + Sync;
+
+impl FrameLogger {
+    pub fn new(f: impl Fn(Direction, &[u8]) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    fn log(&self, direction: Direction, frame: &[u8]) {
+        (self.0)(direction, frame)
+    }
+}
+
+impl std::fmt::Debug for FrameLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FrameLogger(..)")
+    }
+}
 
 /// Options for connecting to SquirrelDB
 #[derive(Debug, Clone, Default)]
@@ -24,6 +99,39 @@ pub struct ConnectOptions {
     pub auth_token: Option<String>,
     pub use_messagepack: bool,
     pub json_fallback: bool,
+    pub required_features: Vec<Feature>,
+    /// Send a ping after this much idle time
+    pub keep_alive_interval: Option<Duration>,
+    /// How long to wait for the keep-alive pong (defaults to the interval)
+    pub keep_alive_timeout: Option<Duration>,
+    /// Reject new requests with `Error::Overloaded` once this many are awaiting a response
+    pub max_in_flight: Option<usize>,
+    /// Serve `list_collections` from memory for this long after a fetch
+    pub collections_cache_ttl: Option<Duration>,
+    /// Memoize query results for `(ttl, capacity)`; see [`ConnectOptions::with_query_cache`]
+    pub query_cache: Option<(Duration, usize)>,
+    /// Compress request payloads of at least this many bytes when the server
+    /// negotiates compression; connecting fails unless the `compression`
+    /// feature is enabled
+    pub compression_threshold: Option<usize>,
+    /// Request id source; defaults to a per-connection counter starting at 1
+    pub id_generator: Option<IdGenerator>,
+    /// Disable Nagle's algorithm so small requests go out immediately
+    pub tcp_nodelay: bool,
+    /// Capacity of the buffered reader (tokio's default, 8 KiB, if unset)
+    pub read_buffer_size: Option<usize>,
+    /// Capacity of the buffered writer (tokio's default, 8 KiB, if unset)
+    pub write_buffer_size: Option<usize>,
+    /// Called with every complete frame sent or received
+    pub frame_logger: Option<FrameLogger>,
+    /// Connect to this Unix domain socket instead of `host:port`; connecting
+    /// fails on non-Unix targets
+    pub unix_socket: Option<PathBuf>,
+    /// How [`SquirrelDB::subscribe_resilient`] redials after a disconnect
+    pub reconnect: ReconnectPolicy,
+    /// Results fetched per round trip by [`SquirrelDB::query_stream`]
+    /// (1000 if unset)
+    pub cursor_batch_size: Option<u32>,
 }
 
 impl ConnectOptions {
@@ -34,13 +142,151 @@ impl ConnectOptions {
             auth_token: None,
             use_messagepack: true,
             json_fallback: true,
+            required_features: Vec::new(),
+            keep_alive_interval: None,
+            keep_alive_timeout: None,
+            max_in_flight: None,
+            collections_cache_ttl: None,
+            query_cache: None,
+            compression_threshold: None,
+            id_generator: None,
+            tcp_nodelay: true,
+            read_buffer_size: None,
+            write_buffer_size: None,
+            frame_logger: None,
+            unix_socket: None,
+            reconnect: ReconnectPolicy::default(),
+            cursor_batch_size: None,
         }
     }
 
+    /// Use JSON framing only, e.g. to read traffic in a packet capture
+    pub fn force_json(mut self) -> Self {
+        self.use_messagepack = false;
+        self.json_fallback = true;
+        self
+    }
+
     pub fn with_auth(mut self, token: impl Into<String>) -> Self {
         self.auth_token = Some(token.into());
         self
     }
+
+    /// Ping the server when the connection has been idle for `interval`,
+    /// treating it as dead if no pong arrives within `timeout`
+    pub fn with_keep_alive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.keep_alive_interval = Some(interval);
+        self.keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Cap the number of requests awaiting a response
+    pub fn with_max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Cache the `list_collections` result for `ttl`
+    pub fn with_collections_cache(mut self, ttl: Duration) -> Self {
+        self.collections_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Serve repeated identical queries from memory for up to `ttl`, keeping
+    /// at most `capacity` results and evicting the least recently used.
+    ///
+    /// Writes made through this client drop the cached results for the
+    /// collections they touch, but writes from anyone else go unnoticed until
+    /// the entry expires, so results can be up to `ttl` stale. Only enable it
+    /// for reads that tolerate that, such as dashboards.
+    pub fn with_query_cache(mut self, ttl: Duration, capacity: usize) -> Self {
+        self.query_cache = Some((ttl, capacity));
+        self
+    }
+
+    /// Offer zstd compression in the handshake and compress request payloads
+    /// of at least `threshold` bytes if the server accepts
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, threshold: usize) -> Self {
+        self.compression_threshold = Some(threshold);
+        self
+    }
+
+    /// Generate request ids with `f` instead of the built-in counter. Ids must
+    /// be unique among in-flight requests.
+    pub fn with_id_generator(mut self, f: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        self.id_generator = Some(IdGenerator::new(f));
+        self
+    }
+
+    /// Set the reader and writer buffer capacities
+    pub fn with_buffer_sizes(mut self, read: usize, write: usize) -> Self {
+        self.read_buffer_size = Some(read);
+        self.write_buffer_size = Some(write);
+        self
+    }
+
+    /// Call `f` with the raw bytes of every frame, header included: outbound
+    /// frames as written (after any compression), inbound frames as read
+    /// (before decompression and decoding). Handshake bytes are not frames and
+    /// are not logged.
+    pub fn with_frame_logger(mut self, f: impl Fn(Direction, &[u8]) + Send + Sync + 'static) -> Self {
+        self.frame_logger = Some(FrameLogger::new(f));
+        self
+    }
+
+    /// Connect over a Unix domain socket at `path`; `host` and `port` are ignored
+    pub fn unix_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Fetch `n` results per round trip when streaming a query. Larger
+    /// batches mean fewer round trips but more documents held in memory.
+    pub fn with_cursor_batch_size(mut self, n: u32) -> Self {
+        self.cursor_batch_size = Some(n);
+        self
+    }
+
+    /// Set how resilient subscriptions redial after a disconnect
+    pub fn with_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Fail the connect if the server does not advertise these features
+    pub fn require_features(mut self, features: &[Feature]) -> Self {
+        self.required_features.extend_from_slice(features);
+        self
+    }
+}
+
+/// Redial schedule for [`SquirrelDB::subscribe_resilient`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Consecutive failed attempts before giving up
+    pub max_attempts: u32,
+    /// Wait before the second attempt; the first is made right away
+    pub initial_backoff: Duration,
+    /// The wait doubles after each failure up to this
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Write half of the connection, shared by the client, its keep-alive task
+/// and subscriptions
+struct SharedWriter {
+    stream: tokio::sync::Mutex<TransportWriter>,
+    frame_logger: Option<FrameLogger>,
 }
 
 #[derive(Debug)]
@@ -48,21 +294,338 @@ struct PendingRequest {
     tx: oneshot::Sender<Result<ServerMessage>>,
 }
 
+/// Results per cursor batch unless [`ConnectOptions::cursor_batch_size`] is set
+const DEFAULT_CURSOR_BATCH_SIZE: u32 = 1000;
+
+/// Error code the server uses when a write precondition fails
+const CONFLICT_CODE: &str = "CONFLICT";
+
+/// Error code the server uses when the addressed document does not exist
+const NOT_FOUND_CODE: &str = "NOT_FOUND";
+
+/// Server error tagged with the id of the request that caused it
+fn server_error(id: String, code: Option<String>, error: String) -> Error {
+    if code.as_deref() == Some(CONFLICT_CODE) {
+        return Error::Conflict(format!("{} (request {})", error, id));
+    }
+    Error::Server {
+        code,
+        message: format!("{} (request {})", error, id),
+    }
+}
+
+fn unexpected_response() -> Error {
+    Error::Server {
+        code: None,
+        message: "Unexpected response".to_string(),
+    }
+}
+
+/// Writes buffered for [`SquirrelDB::transaction`]
+#[derive(Debug, Default)]
+pub struct Transaction {
+    ops: Vec<TransactionOp>,
+}
+
+impl Transaction {
+    /// Buffer an insert
+    pub fn insert(&mut self, collection: &str, data: serde_json::Value) -> &mut Self {
+        self.ops.push(TransactionOp::Insert {
+            collection: collection.to_string(),
+            data,
+        });
+        self
+    }
+
+    /// Buffer an update
+    pub fn update(&mut self, collection: &str, document_id: Uuid, data: serde_json::Value) -> &mut Self {
+        self.ops.push(TransactionOp::Update {
+            collection: collection.to_string(),
+            document_id,
+            data,
+        });
+        self
+    }
+
+    /// Buffer a delete
+    pub fn delete(&mut self, collection: &str, document_id: Uuid) -> &mut Self {
+        self.ops.push(TransactionOp::Delete {
+            collection: collection.to_string(),
+            document_id,
+        });
+        self
+    }
+
+    /// Operations buffered so far
+    pub fn ops(&self) -> &[TransactionOp] {
+        &self.ops
+    }
+}
+
+/// Releases an in-flight slot when the request completes or is dropped
+struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Removes a request's `pending` entry unless its response arrived, so a
+/// failed write or a cancelled caller doesn't leave the id in flight
+struct PendingGuard<'a> {
+    pending: &'a Arc<RwLock<HashMap<String, PendingRequest>>>,
+    id: Option<String>,
+}
+
+impl PendingGuard<'_> {
+    /// The response arrived and the reader already removed the entry
+    fn disarm(mut self) {
+        self.id = None;
+    }
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        let Some(id) = self.id.take() else {
+            return;
+        };
+        if let Ok(mut pending) = self.pending.try_write() {
+            pending.remove(&id);
+        } else if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let pending = self.pending.clone();
+            runtime.spawn(async move {
+                pending.write().await.remove(&id);
+            });
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ActiveSubscription {
-    tx: mpsc::UnboundedSender<ChangeEvent>,
+    queue: Arc<SubQueue>,
 }
 
-/// SquirrelDB client
+impl Drop for ActiveSubscription {
+    fn drop(&mut self) {
+        self.queue.close_sender();
+    }
+}
+
+/// What a bounded subscription does with a change that arrives while its
+/// buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Discard the oldest buffered change to make room
+    #[default]
+    DropOldest,
+    /// Discard the incoming change
+    DropNewest,
+    /// Wait for the consumer to make room. Reading from the connection pauses
+    /// meanwhile, so every request and subscription on it stalls too.
+    Block,
+}
+
+/// Buffering limits for a subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscribeOptions {
+    /// Most events held before `overflow` applies
+    pub buffer: usize,
+    pub overflow: Overflow,
+}
+
+impl Default for SubscribeOptions {
+    fn default() -> Self {
+        Self {
+            buffer: 1024,
+            overflow: Overflow::DropOldest,
+        }
+    }
+}
+
+/// Event buffer between the reader task and a `Subscription`. Only changes
+/// are subject to the limit; lifecycle events are always queued.
 #[derive(Debug)]
+struct SubQueue {
+    limit: Option<SubscribeOptions>,
+    state: std::sync::Mutex<SubQueueState>,
+    /// Signalled when the consumer takes an event or goes away
+    space: Notify,
+}
+
+#[derive(Debug, Default)]
+struct SubQueueState {
+    events: VecDeque<SubEvent>,
+    /// Changes currently in `events`
+    changes: usize,
+    /// Changes dropped from the head of the queue (`DropOldest`) since the
+    /// consumer last heard about it. `DropNewest` drops at the tail and
+    /// records the gap in `events` instead, behind the changes kept.
+    lagged: u64,
+    waker: Option<std::task::Waker>,
+    sender_closed: bool,
+    receiver_closed: bool,
+}
+
+impl SubQueue {
+    fn new(limit: Option<SubscribeOptions>) -> Self {
+        Self {
+            limit,
+            state: std::sync::Mutex::default(),
+            space: Notify::new(),
+        }
+    }
+
+    fn push(&self, event: SubEvent) {
+        let mut state = self.state.lock().unwrap();
+        if state.receiver_closed {
+            return;
+        }
+        if matches!(event, SubEvent::Change(_)) {
+            state.changes += 1;
+        }
+        state.events.push_back(event);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Put an event the consumer already took back at the head of the queue
+    fn push_front(&self, event: SubEvent) {
+        let mut state = self.state.lock().unwrap();
+        if state.receiver_closed {
+            return;
+        }
+        if matches!(event, SubEvent::Change(_)) {
+            state.changes += 1;
+        }
+        state.events.push_front(event);
+    }
+
+    async fn push_change(&self, change: ChangeEvent) {
+        let Some(limit) = self.limit else {
+            return self.push(SubEvent::Change(change));
+        };
+
+        loop {
+            let space = self.space.notified();
+            let full = {
+                let mut state = self.state.lock().unwrap();
+                if state.receiver_closed {
+                    return;
+                }
+                let full = state.changes >= limit.buffer.max(1);
+                match limit.overflow {
+                    Overflow::DropNewest if full => {
+                        match state.events.back_mut() {
+                            Some(SubEvent::Lagged(n)) => *n += 1,
+                            _ => state.events.push_back(SubEvent::Lagged(1)),
+                        }
+                        if let Some(waker) = state.waker.take() {
+                            waker.wake();
+                        }
+                        return;
+                    }
+                    Overflow::DropOldest if full => {
+                        if let Some(i) = state.events.iter().position(|e| matches!(e, SubEvent::Change(_))) {
+                            state.events.remove(i);
+                            state.changes -= 1;
+                            state.lagged += 1;
+                        }
+                        false
+                    }
+                    _ => full,
+                }
+            };
+            if !full {
+                return self.push(SubEvent::Change(change));
+            }
+            space.await;
+        }
+    }
+
+    fn poll_recv(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<SubEvent>> {
+        let mut state = self.state.lock().unwrap();
+        // Changes dropped by DropOldest were older than everything queued, so
+        // report that gap first
+        if state.lagged > 0 {
+            return std::task::Poll::Ready(Some(SubEvent::Lagged(std::mem::take(&mut state.lagged))));
+        }
+        if let Some(event) = state.events.pop_front() {
+            if matches!(event, SubEvent::Change(_)) {
+                state.changes -= 1;
+            }
+            self.space.notify_one();
+            return std::task::Poll::Ready(Some(event));
+        }
+        if state.sender_closed {
+            return std::task::Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        std::task::Poll::Pending
+    }
+
+    fn close_sender(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.sender_closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn close_receiver(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.receiver_closed = true;
+        state.events.clear();
+        state.changes = 0;
+        self.space.notify_one();
+    }
+}
+
+/// SquirrelDB client
 pub struct SquirrelDB {
     session_id: Uuid,
+    server_version: u8,
+    server_flags: ProtocolFlags,
     encoding: Encoding,
-    writer: Arc<tokio::sync::Mutex<BufWriter<OwnedWriteHalf>>>,
+    max_message_size: u32,
+    writer: Arc<SharedWriter>,
     pending: Arc<RwLock<HashMap<String, PendingRequest>>>,
     subscriptions: Arc<RwLock<HashMap<String, ActiveSubscription>>>,
     request_id: AtomicU64,
-    _reader_task: tokio::task::JoinHandle<()>,
+    id_generator: Option<IdGenerator>,
+    in_flight: AtomicUsize,
+    counters: Arc<Counters>,
+    max_in_flight: Option<usize>,
+    #[cfg(feature = "compression")]
+    compression_threshold: Option<usize>,
+    collections_cache_ttl: Option<Duration>,
+    collections_cache: std::sync::Mutex<Option<(Instant, Vec<Collection>)>>,
+    query_cache: Option<QueryCache>,
+    cursor_batch_size: u32,
+    /// Options to redial with; `None` for caller-supplied transports
+    redial: Option<ConnectOptions>,
+    last_activity: Arc<std::sync::Mutex<Instant>>,
+    /// Set by the reader or keep-alive once the connection is gone, before
+    /// they fail what is pending
+    closed: Arc<AtomicBool>,
+    /// Set once the caller closes or drops this client, before `closed`;
+    /// resilient subscriptions never redial after it
+    released: Arc<AtomicBool>,
+    shutdown: Option<oneshot::Sender<()>>,
+    reader_task: tokio::task::JoinHandle<()>,
+    keep_alive_task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for SquirrelDB {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SquirrelDB")
+            .field("session_id", &self.session_id)
+            .field("server_version", &self.server_version)
+            .field("server_flags", &self.server_flags)
+            .field("encoding", &self.encoding)
+            .finish_non_exhaustive()
+    }
 }
 
 impl SquirrelDB {
@@ -76,36 +639,139 @@ impl SquirrelDB {
 
     /// Connect with full options
     pub async fn connect_with_options(opts: ConnectOptions) -> Result<Self> {
+        #[cfg(unix)]
+        if let Some(path) = &opts.unix_socket {
+            let stream = tokio::net::UnixStream::connect(path)
+                .await
+                .map_err(|e| Error::Connection(format!("{}: {}", path.display(), e)))?;
+            let mut client = Self::with_transport(stream, opts.clone()).await?;
+            client.redial = Some(opts);
+            return Ok(client);
+        }
+        #[cfg(not(unix))]
+        if opts.unix_socket.is_some() {
+            return Err(Error::Config(
+                "Unix domain sockets are not supported on this platform".to_string(),
+            ));
+        }
+
         let addr = format!("{}:{}", opts.host, opts.port);
-        let mut stream = TcpStream::connect(&addr)
+        let stream = TcpStream::connect(&addr)
             .await
             .map_err(|e| Error::Connection(e.to_string()))?;
+        stream.set_nodelay(opts.tcp_nodelay)?;
+
+        let mut client = Self::with_transport(stream, opts.clone()).await?;
+        client.redial = Some(opts);
+        Ok(client)
+    }
+
+    /// Run the handshake and protocol over an already-open stream.
+    /// `opts.host` and `opts.port` are ignored.
+    pub async fn with_transport(transport: impl Transport, opts: ConnectOptions) -> Result<Self> {
+        #[cfg(not(feature = "compression"))]
+        if opts.compression_threshold.is_some() {
+            return Err(Error::Config(
+                "compression requires the `compression` feature".to_string(),
+            ));
+        }
+        let counters = Arc::new(Counters::default());
+        let mut stream: Box<dyn Transport> =
+            Box::new(CountingTransport::new(Box::new(transport), counters.clone()));
 
-        let (session_id, encoding) = Self::handshake(&mut stream, &opts).await?;
-        let (read_half, write_half) = stream.into_split();
-        let reader = BufReader::new(read_half);
-        let writer = Arc::new(tokio::sync::Mutex::new(BufWriter::new(write_half)));
+        let (session_id, encoding, server_version, server_flags, max_message_size) =
+            match Self::handshake(&mut stream, &opts).await {
+                Ok(negotiated) => negotiated,
+                Err(e) => {
+                    trace_event!(warn, error = %e, "squirreldb handshake failed");
+                    return Err(e);
+                }
+            };
+        trace_event!(
+            debug,
+            session_id = %session_id,
+            server_version,
+            encoding = ?encoding,
+            max_message_size,
+            "squirreldb handshake complete"
+        );
+        // Only compress once the server has agreed to it
+        #[cfg(feature = "compression")]
+        let compression_threshold = opts.compression_threshold.filter(|_| server_flags.compression);
+        let (read_half, write_half) = tokio::io::split(stream);
+        let reader = match opts.read_buffer_size {
+            Some(capacity) => BufReader::with_capacity(capacity, read_half),
+            None => BufReader::new(read_half),
+        };
+        let writer = match opts.write_buffer_size {
+            Some(capacity) => BufWriter::with_capacity(capacity, write_half),
+            None => BufWriter::new(write_half),
+        };
+        let writer = Arc::new(SharedWriter {
+            stream: tokio::sync::Mutex::new(writer),
+            frame_logger: opts.frame_logger.clone(),
+        });
 
         let pending: Arc<RwLock<HashMap<String, PendingRequest>>> =
             Arc::new(RwLock::new(HashMap::new()));
         let subscriptions: Arc<RwLock<HashMap<String, ActiveSubscription>>> =
             Arc::new(RwLock::new(HashMap::new()));
 
+        let last_activity = Arc::new(std::sync::Mutex::new(Instant::now()));
+        let closed = Arc::new(AtomicBool::new(false));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
         let reader_task = tokio::spawn(Self::reader_loop(
             reader,
-            encoding,
+            opts.frame_logger.clone(),
             pending.clone(),
             subscriptions.clone(),
+            last_activity.clone(),
+            closed.clone(),
+            shutdown_rx,
         ));
 
+        let keep_alive_task = opts.keep_alive_interval.map(|interval| {
+            tokio::spawn(Self::keep_alive_loop(
+                interval,
+                opts.keep_alive_timeout.unwrap_or(interval),
+                encoding,
+                writer.clone(),
+                pending.clone(),
+                subscriptions.clone(),
+                last_activity.clone(),
+                closed.clone(),
+                reader_task.abort_handle(),
+            ))
+        });
+
         Ok(Self {
             session_id,
+            server_version,
+            server_flags,
             encoding,
+            max_message_size,
             writer,
             pending,
             subscriptions,
             request_id: AtomicU64::new(1),
-            _reader_task: reader_task,
+            id_generator: opts.id_generator,
+            in_flight: AtomicUsize::new(0),
+            counters,
+            max_in_flight: opts.max_in_flight,
+            #[cfg(feature = "compression")]
+            compression_threshold,
+            collections_cache_ttl: opts.collections_cache_ttl,
+            collections_cache: std::sync::Mutex::new(None),
+            query_cache: opts.query_cache.map(|(ttl, capacity)| QueryCache::new(ttl, capacity)),
+            cursor_batch_size: opts.cursor_batch_size.unwrap_or(DEFAULT_CURSOR_BATCH_SIZE),
+            redial: None,
+            last_activity,
+            closed,
+            released: Arc::new(AtomicBool::new(false)),
+            shutdown: Some(shutdown_tx),
+            reader_task,
+            keep_alive_task,
         })
     }
 
@@ -113,13 +779,64 @@ impl SquirrelDB {
         self.session_id
     }
 
-    async fn handshake(stream: &mut TcpStream, opts: &ConnectOptions) -> Result<(Uuid, Encoding)> {
+    /// Protocol version reported by the server in the handshake
+    pub fn server_version(&self) -> u8 {
+        self.server_version
+    }
+
+    /// Capabilities the server advertised in the handshake
+    pub fn server_flags(&self) -> ProtocolFlags {
+        self.server_flags
+    }
+
+    /// Payload encoding chosen for this connection
+    pub fn negotiated_encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Largest frame this connection will send: the smaller of
+    /// [`MAX_MESSAGE_SIZE`] and the limit the server advertised, if any
+    pub fn max_message_size(&self) -> u32 {
+        self.max_message_size
+    }
+
+    /// Number of requests currently awaiting a response
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Snapshot of the request, error and byte counters for this connection
+    pub fn metrics(&self) -> ClientMetrics {
+        self.counters.snapshot(self.in_flight())
+    }
+
+    /// Count this connection as a redial of `previous`, keeping its tally
+    pub(crate) fn record_reconnect_of(&self, previous: &SquirrelDB) {
+        let reconnects = previous.counters.reconnects.load(Ordering::Relaxed) + 1;
+        self.counters.reconnects.store(reconnects, Ordering::Relaxed);
+    }
+
+    /// Whether the connection is still up. Turns false once the background
+    /// reader sees the socket close or the keep-alive gives up; never blocks.
+    pub fn is_connected(&self) -> bool {
+        !self.closed.load(Ordering::SeqCst) && !self.reader_task.is_finished()
+    }
+
+    async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: &mut S,
+        opts: &ConnectOptions,
+    ) -> Result<(Uuid, Encoding, u8, ProtocolFlags, u32)> {
         stream.write_all(MAGIC).await?;
         stream.write_u8(PROTOCOL_VERSION).await?;
 
         let flags = ProtocolFlags {
             messagepack: opts.use_messagepack,
             json_fallback: opts.json_fallback,
+            #[cfg(feature = "compression")]
+            compression: opts.compression_threshold.is_some(),
+            message_size_limit: true,
+            snapshot_sync: true,
+            ..Default::default()
         };
         stream.write_u8(flags.into()).await?;
 
@@ -156,28 +873,124 @@ impl SquirrelDB {
         }
 
         let server_flags = ProtocolFlags::from(server_flags_byte);
-        let encoding = if server_flags.messagepack {
+        let max_message_size = if server_flags.message_size_limit {
+            stream.read_u32().await?.min(MAX_MESSAGE_SIZE)
+        } else {
+            MAX_MESSAGE_SIZE
+        };
+
+        let missing: Vec<Feature> = opts
+            .required_features
+            .iter()
+            .copied()
+            .filter(|f| !server_flags.supports(*f))
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::UnsupportedServer { missing });
+        }
+
+        // MessagePack only when both sides want it; otherwise JSON end to end
+        let encoding = if opts.use_messagepack && server_flags.messagepack {
             Encoding::MessagePack
         } else {
             Encoding::Json
         };
 
-        Ok((session_id, encoding))
+        Ok((session_id, encoding, server_version, server_flags, max_message_size))
     }
 
     async fn reader_loop(
-        mut reader: BufReader<OwnedReadHalf>,
+        mut reader: TransportReader,
+        frame_logger: Option<FrameLogger>,
+        pending: Arc<RwLock<HashMap<String, PendingRequest>>>,
+        subscriptions: Arc<RwLock<HashMap<String, ActiveSubscription>>>,
+        last_activity: Arc<std::sync::Mutex<Instant>>,
+        closed: Arc<AtomicBool>,
+        mut shutdown: oneshot::Receiver<()>,
+    ) {
+        loop {
+            tokio::select! {
+                frame = Self::read_frame(&mut reader, frame_logger.as_ref()) => match frame {
+                    Ok(msg) => {
+                        *last_activity.lock().unwrap() = Instant::now();
+                        Self::dispatch_message(msg, &pending, &subscriptions).await;
+                    }
+                    Err(e) => {
+                        trace_event!(debug, error = %e, "squirreldb connection closed");
+                        closed.store(true, Ordering::SeqCst);
+                        Self::fail_pending(&pending, &e).await;
+                        break;
+                    }
+                },
+                _ = &mut shutdown => break,
+            }
+        }
+        closed.store(true, Ordering::SeqCst);
+
+        Self::close_subscriptions(&subscriptions).await;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn keep_alive_loop(
+        interval: Duration,
+        timeout: Duration,
         encoding: Encoding,
+        writer: Arc<SharedWriter>,
         pending: Arc<RwLock<HashMap<String, PendingRequest>>>,
         subscriptions: Arc<RwLock<HashMap<String, ActiveSubscription>>>,
+        last_activity: Arc<std::sync::Mutex<Instant>>,
+        closed: Arc<AtomicBool>,
+        reader_task: tokio::task::AbortHandle,
     ) {
+        let mut seq = 0u64;
         loop {
-            match Self::read_frame(&mut reader, encoding).await {
-                Ok(msg) => {
-                    Self::dispatch_message(msg, &pending, &subscriptions).await;
-                }
-                Err(_) => break,
+            let idle = last_activity.lock().unwrap().elapsed();
+            if idle < interval {
+                tokio::time::sleep(interval - idle).await;
+                continue;
+            }
+
+            seq += 1;
+            let id = format!("keepalive-{}", seq);
+            let (tx, rx) = oneshot::channel();
+            pending.write().await.insert(id.clone(), PendingRequest { tx });
+
+            let msg = ClientMessage::Ping { id: id.clone() };
+            let alive = Self::send_frame(&writer, encoding, &msg).await.is_ok()
+                && matches!(tokio::time::timeout(timeout, rx).await, Ok(Ok(_)));
+            if !alive {
+                trace_event!(warn, "squirreldb keep-alive ping unanswered, closing connection");
+                break;
             }
+            *last_activity.lock().unwrap() = Instant::now();
+        }
+
+        // The connection is dead: stop reading and fail everything waiting on it
+        closed.store(true, Ordering::SeqCst);
+        reader_task.abort();
+        for (_, req) in pending.write().await.drain() {
+            let _ = req.tx.send(Err(Error::Timeout));
+        }
+        Self::close_subscriptions(&subscriptions).await;
+    }
+
+    /// Fail every outstanding request after the connection broke.
+    /// Protocol violations are reported as such; anything else surfaces as
+    /// `ChannelClosed`.
+    async fn fail_pending(pending: &RwLock<HashMap<String, PendingRequest>>, error: &Error) {
+        for (_, req) in pending.write().await.drain() {
+            let err = match error {
+                Error::Protocol(msg) => Error::Protocol(msg.clone()),
+                _ => Error::ChannelClosed,
+            };
+            let _ = req.tx.send(Err(err));
+        }
+    }
+
+    /// Tell every subscription its stream has ended and drop the senders
+    async fn close_subscriptions(subscriptions: &RwLock<HashMap<String, ActiveSubscription>>) {
+        for (_, sub) in subscriptions.write().await.drain() {
+            sub.queue.push(SubEvent::Closed);
         }
     }
 
@@ -188,197 +1001,1082 @@ impl SquirrelDB {
     ) {
         match &msg {
             ServerMessage::Change { id, change } => {
+                // Release the map before a blocking push so other tasks can
+                // still subscribe, unsubscribe and close
+                let queue = subscriptions.read().await.get(id).map(|sub| sub.queue.clone());
+                if let Some(queue) = queue {
+                    queue.push_change(change.clone()).await;
+                }
+                return;
+            }
+            ServerMessage::Subscribed { id } => {
+                if let Some(sub) = subscriptions.read().await.get(id) {
+                    sub.queue.push(SubEvent::Ready);
+                }
+            }
+            ServerMessage::Unsubscribed { id } => {
+                if let Some(sub) = subscriptions.write().await.remove(id) {
+                    sub.queue.push(SubEvent::Closed);
+                }
+            }
+            ServerMessage::Synced { id } => {
                 if let Some(sub) = subscriptions.read().await.get(id) {
-                    let _ = sub.tx.send(change.clone());
+                    sub.queue.push(SubEvent::Synced);
                 }
+                return;
             }
+            ServerMessage::Error { id, error, .. } => {
+                if !pending.read().await.contains_key(id) {
+                    if let Some(sub) = subscriptions.read().await.get(id) {
+                        sub.queue.push(SubEvent::Error(error.clone()));
+                    }
+                    return;
+                }
+            }
+            ServerMessage::Result { .. } | ServerMessage::CursorBatch { .. } | ServerMessage::Pong { .. } => {}
+        }
+
+        let id = match &msg {
             ServerMessage::Result { id, .. }
+            | ServerMessage::Change { id, .. }
             | ServerMessage::Subscribed { id }
             | ServerMessage::Unsubscribed { id }
+            | ServerMessage::Synced { id }
             | ServerMessage::Error { id, .. }
-            | ServerMessage::Pong { id } => {
-                if let Some(req) = pending.write().await.remove(id) {
-                    let _ = req.tx.send(Ok(msg));
-                }
-            }
+            | ServerMessage::CursorBatch { id, .. }
+            | ServerMessage::Pong { id } => id,
+        };
+        if let Some(req) = pending.write().await.remove(id) {
+            let _ = req.tx.send(Ok(msg));
         }
     }
 
     async fn read_frame(
-        reader: &mut BufReader<OwnedReadHalf>,
-        encoding: Encoding,
+        reader: &mut TransportReader,
+        frame_logger: Option<&FrameLogger>,
     ) -> Result<ServerMessage> {
+        // Validate the declared length before allocating the payload buffer
         let length = reader.read_u32().await?;
         if length > MAX_MESSAGE_SIZE {
-            return Err(Error::Server(format!("Message too large: {}", length)));
+            return Err(Error::Protocol(format!(
+                "Message too large: {} bytes (max {})",
+                length, MAX_MESSAGE_SIZE
+            )));
+        }
+        if length < 2 {
+            return Err(Error::Protocol(format!("Invalid frame length: {}", length)));
         }
 
-        let _msg_type = reader.read_u8().await?;
-        let _encoding = reader.read_u8().await?;
+        let msg_type = reader.read_u8().await?;
+        let frame_encoding = reader.read_u8().await?;
 
         let payload_len = length as usize - 2;
         let mut payload = vec![0u8; payload_len];
         reader.read_exact(&mut payload).await?;
 
-        let msg: ServerMessage = match encoding {
-            Encoding::MessagePack => rmp_serde::from_slice(&payload)?,
-            Encoding::Json => serde_json::from_slice(&payload)?,
-        };
+        if let Some(logger) = frame_logger {
+            let mut frame = Vec::with_capacity(FRAME_HEADER_SIZE + payload_len);
+            frame.extend_from_slice(&length.to_be_bytes());
+            frame.push(msg_type);
+            frame.push(frame_encoding);
+            frame.extend_from_slice(&payload);
+            logger.log(Direction::Inbound, &frame);
+        }
 
-        Ok(msg)
+        // Decode by the frame's own encoding: a request sent with a
+        // per-request override is answered in that encoding
+        let encoding = Encoding::try_from(frame_encoding & !COMPRESSED_BIT)
+            .map_err(|_| Error::Protocol(format!("Invalid encoding: {}", frame_encoding)))?;
+        if frame_encoding & COMPRESSED_BIT != 0 {
+            payload = decompress_payload(&payload)?;
+        }
+        decode_payload(&payload, encoding)
     }
 
-    async fn write_frame(&self, msg: &ClientMessage) -> Result<()> {
-        let payload = match self.encoding {
-            Encoding::MessagePack => rmp_serde::to_vec(msg)?,
-            Encoding::Json => serde_json::to_vec(msg)?,
+    async fn write_frame(&self, msg: &ClientMessage, encoding: Encoding) -> Result<()> {
+        let frame = encode_frame(msg, encoding)?;
+        let size = frame.len() - 4;
+        if size > self.max_message_size as usize {
+            return Err(Error::MessageTooLarge { size, max: self.max_message_size as usize });
+        }
+        #[cfg(feature = "compression")]
+        let frame = match self.compression_threshold {
+            Some(threshold) => compress_frame(frame, threshold)?,
+            None => frame,
         };
-
-        let length = (payload.len() + 2) as u32;
-        let mut writer = self.writer.lock().await;
-        writer.write_u32(length).await?;
-        writer.write_u8(MessageType::Request as u8).await?;
-        writer.write_u8(self.encoding as u8).await?;
-        writer.write_all(&payload).await?;
-        writer.flush().await?;
-
+        Self::write_bytes(&self.writer, &frame).await?;
+        *self.last_activity.lock().unwrap() = Instant::now();
         Ok(())
     }
 
-    fn next_id(&self) -> String {
-        self.request_id.fetch_add(1, Ordering::SeqCst).to_string()
+    async fn send_frame(
+        writer: &SharedWriter,
+        encoding: Encoding,
+        msg: &ClientMessage,
+    ) -> Result<()> {
+        let frame = encode_frame(msg, encoding)?;
+        Self::write_bytes(writer, &frame).await
     }
 
-    async fn request(&self, msg: ClientMessage) -> Result<ServerMessage> {
-        let id = match &msg {
-            ClientMessage::Query { id, .. }
-            | ClientMessage::Subscribe { id, .. }
-            | ClientMessage::Unsubscribe { id }
-            | ClientMessage::Insert { id, .. }
-            | ClientMessage::Update { id, .. }
-            | ClientMessage::Delete { id, .. }
-            | ClientMessage::ListCollections { id }
-            | ClientMessage::Ping { id } => id.clone(),
-        };
+    async fn write_bytes(
+        writer: &SharedWriter,
+        frame: &[u8],
+    ) -> Result<()> {
+        let mut stream = writer.stream.lock().await;
+        stream.write_all(frame).await?;
+        stream.flush().await?;
+        if let Some(logger) = &writer.frame_logger {
+            logger.log(Direction::Outbound, frame);
+        }
+
+        Ok(())
+    }
+
+    fn next_id(&self) -> String {
+        match &self.id_generator {
+            Some(generator) => generator.next(),
+            None => self.request_id.fetch_add(1, Ordering::SeqCst).to_string(),
+        }
+    }
+
+    /// Send any client message and return the correlated server response
+    /// unchanged, including `ServerMessage::Error` replies. A message with an
+    /// empty id is given the next request id.
+    ///
+    /// This is an escape hatch for server features the SDK doesn't wrap yet;
+    /// a `Subscribe` sent this way gets no change stream.
+    pub async fn request(&self, msg: ClientMessage) -> Result<ServerMessage> {
+        self.request_with_encoding(msg, self.encoding).await
+    }
+
+    /// Like [`request`](Self::request), but send this one message in
+    /// `encoding` instead of the negotiated one, e.g. JSON to capture a
+    /// readable frame. The server must support that encoding.
+    pub async fn request_with_encoding(&self, mut msg: ClientMessage, encoding: Encoding) -> Result<ServerMessage> {
+        let feature = match encoding {
+            Encoding::MessagePack => Feature::MessagePack,
+            Encoding::Json => Feature::JsonFallback,
+        };
+        if encoding != self.encoding && !self.server_flags.supports(feature) {
+            return Err(Error::UnsupportedServer { missing: vec![feature] });
+        }
+        if msg.id().is_empty() {
+            msg.set_id(self.next_id());
+        }
+
+        // Invalidate again once the write is done: a query that ran
+        // alongside it may have fetched, and cached, the old data
+        let written = self.query_cache.as_ref().map(|cache| cache.invalidate_for(&msg));
+
+        let result = self.send_request_traced(msg, encoding).await;
+        if let (Some(cache), Some(written)) = (&self.query_cache, &written) {
+            cache.invalidate_tables(written);
+        }
+        match &result {
+            Ok(ServerMessage::Error { .. }) => self.counters.record_error("Server"),
+            Err(e) => self.counters.record_error(error_kind(e)),
+            Ok(_) => {}
+        }
+        result
+    }
+
+    /// Send a request inside a span carrying its type, id, duration and any error
+    #[cfg(feature = "tracing")]
+    async fn send_request_traced(&self, msg: ClientMessage, encoding: Encoding) -> Result<ServerMessage> {
+        use tracing::Instrument;
+
+        let span = tracing::debug_span!(
+            "squirreldb.request",
+            kind = msg.kind(),
+            id = msg.id(),
+            duration_ms = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        let started = Instant::now();
+        let result = self.send_request(msg, encoding).instrument(span.clone()).await;
+        span.record("duration_ms", started.elapsed().as_millis() as u64);
+        match &result {
+            Ok(ServerMessage::Error { error, .. }) => {
+                span.record("error", error.as_str());
+            }
+            Err(e) => {
+                span.record("error", tracing::field::display(e));
+            }
+            Ok(_) => {}
+        }
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    async fn send_request_traced(&self, msg: ClientMessage, encoding: Encoding) -> Result<ServerMessage> {
+        self.send_request(msg, encoding).await
+    }
+
+    async fn send_request(&self, msg: ClientMessage, encoding: Encoding) -> Result<ServerMessage> {
+        let id = msg.id().to_string();
+
+        let max = self.max_in_flight.unwrap_or(usize::MAX);
+        self.in_flight
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < max).then_some(n + 1))
+            .map_err(|_| Error::Overloaded)?;
+        let _guard = InFlightGuard(&self.in_flight);
 
         let (tx, rx) = oneshot::channel();
-        self.pending.write().await.insert(id.clone(), PendingRequest { tx });
+        {
+            let mut pending = self.pending.write().await;
+            if pending.contains_key(&id) {
+                return Err(Error::Protocol(format!("Request id already in flight: {}", id)));
+            }
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(Error::ChannelClosed);
+            }
+            pending.insert(id.clone(), PendingRequest { tx });
+        }
+        let guard = PendingGuard {
+            pending: &self.pending,
+            id: Some(id),
+        };
+        // The connection may have closed, and drained `pending`, while the
+        // entry went in; nothing would ever answer it then
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::ChannelClosed);
+        }
 
-        self.write_frame(&msg).await?;
+        self.write_frame(&msg, encoding).await?;
+        self.counters.requests_sent.fetch_add(1, Ordering::Relaxed);
 
-        rx.await.map_err(|_| Error::ChannelClosed)?
+        let response = rx.await;
+        guard.disarm();
+        let response = response.map_err(|_| Error::ChannelClosed)??;
+        self.counters.responses_received.fetch_add(1, Ordering::Relaxed);
+        Ok(response)
     }
 
     /// Execute a query
     pub async fn query<T: serde::de::DeserializeOwned>(&self, query: &str) -> Result<T> {
+        self.query_with_id(self.next_id(), query).await
+    }
+
+    /// Execute a query sent in `encoding` regardless of the negotiated one.
+    /// Bypasses the query cache so the request always reaches the server.
+    pub async fn query_with_encoding<T: serde::de::DeserializeOwned>(
+        &self,
+        query: &str,
+        encoding: Encoding,
+    ) -> Result<T> {
         let msg = ClientMessage::Query {
             id: self.next_id(),
             query: query.to_string(),
         };
 
+        match self.request_with_encoding(msg, encoding).await? {
+            ServerMessage::Result { data, .. } => serde_json::from_value(data).map_err(Error::from),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Execute a query under a caller-supplied request id, e.g. a tracing
+    /// correlation id. The id is echoed in any `Error::Server` and must not
+    /// collide with another request still in flight.
+    pub async fn query_with_id<T: serde::de::DeserializeOwned>(
+        &self,
+        id: impl Into<String>,
+        query: &str,
+    ) -> Result<T> {
+        if let Some(data) = self.query_cache.as_ref().and_then(|cache| cache.get(query)) {
+            return serde_json::from_value(data).map_err(Error::from);
+        }
+        let generation = self.query_cache.as_ref().map(QueryCache::generation);
+
+        let msg = ClientMessage::Query {
+            id: id.into(),
+            query: query.to_string(),
+        };
+
         match self.request(msg).await? {
             ServerMessage::Result { data, .. } => {
-                serde_json::from_value(data).map_err(|e| Error::Serialization(e.to_string()))
+                if let (Some(cache), Some(generation)) = (&self.query_cache, generation) {
+                    cache.insert(query, &data, generation);
+                }
+                serde_json::from_value(data).map_err(Error::from)
             }
-            ServerMessage::Error { error, .. } => Err(Error::Server(error)),
-            _ => Err(Error::Server("Unexpected response".to_string())),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
         }
     }
 
+    /// Execute a query and collect the result rows into an Arrow `RecordBatch`,
+    /// inferring the schema from the rows unless one is given
+    #[cfg(feature = "arrow")]
+    pub async fn query_arrow(
+        &self,
+        query: &str,
+        schema: Option<arrow_schema::SchemaRef>,
+    ) -> Result<arrow_array::RecordBatch> {
+        let rows: Vec<serde_json::Value> = self.query(query).await?;
+        crate::arrow::rows_to_record_batch(&rows, schema)
+    }
+
+    /// Run a structured query as-is, e.g. one deserialized from config.
+    /// Unknown filter operators are rejected before anything is sent.
+    pub async fn query_structured(&self, query: &StructuredQuery) -> Result<Vec<serde_json::Value>> {
+        query.validate()?;
+        self.query(&serde_json::to_string(query)?).await
+    }
+
+    /// Stream the results of `query` instead of buffering them all.
+    ///
+    /// Results come from a [`Cursor`] in batches of
+    /// [`ConnectOptions::cursor_batch_size`]; the next batch is only requested
+    /// once the current one has been consumed, so at most one batch is held in
+    /// memory. The server releases the cursor when the results run out;
+    /// dropping the stream early, or an error, sends a best-effort close.
+    pub fn query_stream<'a, T: serde::de::DeserializeOwned + 'a>(
+        &'a self,
+        query: &str,
+    ) -> impl Stream<Item = Result<T>> + 'a {
+        let state = StreamState {
+            query: Some(query.to_string()),
+            cursor: None,
+            buffer: VecDeque::new(),
+        };
+
+        futures::stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(document) = state.buffer.pop_front() {
+                    return Some((serde_json::from_value(document).map_err(Error::from), state));
+                }
+                let batch = match (&mut state.cursor, state.query.take()) {
+                    (Some(cursor), _) => cursor.next_batch().await,
+                    (None, Some(query)) => match self.open_cursor(&query, self.cursor_batch_size).await {
+                        Ok(cursor) => state.cursor.insert(cursor).next_batch().await,
+                        Err(e) => Err(e),
+                    },
+                    (None, None) => return None,
+                };
+                match batch {
+                    Ok(Some(documents)) => state.buffer = documents.into(),
+                    Ok(None) => return None,
+                    Err(e) => {
+                        // Dropping the cursor closes it; the stream ends next poll
+                        state.cursor = None;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Open a server-side cursor over `query`, fetching up to `batch_size`
+    /// results per round trip. See [`Cursor`] for reading and closing it, or
+    /// [`query_stream`](Self::query_stream) for a stream of typed results.
+    pub async fn open_cursor(&self, query: &str, batch_size: u32) -> Result<Cursor<'_>> {
+        let msg = ClientMessage::OpenCursor {
+            id: self.next_id(),
+            query: query.to_string(),
+            batch_size,
+        };
+        let (cursor_id, documents, exhausted) = self.cursor_request(msg).await?;
+
+        Ok(Cursor {
+            client: self,
+            first: Some(documents),
+            guard: CursorGuard {
+                cursor_id: (!exhausted).then(|| cursor_id.clone()),
+                close_id: self.next_id(),
+                writer: self.writer.clone(),
+                encoding: self.encoding,
+            },
+            cursor_id,
+        })
+    }
+
+    async fn cursor_request(&self, msg: ClientMessage) -> Result<(String, Vec<serde_json::Value>, bool)> {
+        match self.request(msg).await? {
+            ServerMessage::CursorBatch {
+                cursor_id,
+                documents,
+                exhausted,
+                ..
+            } => Ok((cursor_id, documents, exhausted)),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Run a query and return one page of results with the total match
+    /// count, when the server reports it, so list views can paginate without
+    /// a separate count
+    pub async fn query_page<T: serde::de::DeserializeOwned>(&self, query: &QueryBuilder) -> Result<QueryPage<T>> {
+        self.query_builder(query).await
+    }
+
+    /// Fetch the unique values of the query's `distinct` field.
+    ///
+    /// The server answers with a flat array of values rather than documents.
+    pub async fn query_distinct(&self, query: &QueryBuilder) -> Result<Vec<serde_json::Value>> {
+        self.query_builder(query).await
+    }
+
+    /// Run a `group_by` query and return one row per group, keyed by the
+    /// group field and the accumulator output names
+    pub async fn query_aggregate(&self, query: &QueryBuilder) -> Result<Vec<serde_json::Value>> {
+        self.query_builder(query).await
+    }
+
+    /// Validate and compile a builder, then run it, decoding the result as `T`
+    async fn query_builder<T: serde::de::DeserializeOwned>(&self, query: &QueryBuilder) -> Result<T> {
+        query.validate()?;
+        self.query(&query.clone().compile()?).await
+    }
+
     /// Insert a document
     pub async fn insert(&self, collection: &str, data: serde_json::Value) -> Result<Document> {
+        self.insert_with_id(self.next_id(), collection, data).await
+    }
+
+    /// Insert a typed document. `data` is converted to a JSON value with
+    /// `serde_json` before framing, so `#[serde(flatten)]` fields and tagged
+    /// enums are stored the same way whichever wire encoding the connection
+    /// uses, and read back with [`Document::into_typed`].
+    pub async fn insert_typed<T: serde::Serialize>(&self, collection: &str, data: &T) -> Result<Document> {
+        self.insert(collection, serde_json::to_value(data)?).await
+    }
+
+    /// Insert a document under a caller-supplied request id; see [`Self::query_with_id`]
+    pub async fn insert_with_id(
+        &self,
+        id: impl Into<String>,
+        collection: &str,
+        data: serde_json::Value,
+    ) -> Result<Document> {
         let msg = ClientMessage::Insert {
-            id: self.next_id(),
+            id: id.into(),
             collection: collection.to_string(),
             data,
         };
 
         match self.request(msg).await? {
             ServerMessage::Result { data, .. } => {
-                serde_json::from_value(data).map_err(|e| Error::Serialization(e.to_string()))
+                serde_json::from_value(data).map_err(Error::from)
             }
-            ServerMessage::Error { error, .. } => Err(Error::Server(error)),
-            _ => Err(Error::Server("Unexpected response".to_string())),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
         }
     }
 
     /// Update a document
     pub async fn update(&self, collection: &str, document_id: Uuid, data: serde_json::Value) -> Result<Document> {
+        self.update_with_precondition(collection, document_id, data, None).await
+    }
+
+    /// Update a document only if it hasn't changed since it was read, i.e.
+    /// its `updated_at` still equals `expected_updated_at`. Fails with
+    /// `Error::Conflict` otherwise.
+    pub async fn update_if_unchanged(
+        &self,
+        collection: &str,
+        document_id: Uuid,
+        data: serde_json::Value,
+        expected_updated_at: &str,
+    ) -> Result<Document> {
+        self.update_with_precondition(collection, document_id, data, Some(expected_updated_at.to_string()))
+            .await
+    }
+
+    async fn update_with_precondition(
+        &self,
+        collection: &str,
+        document_id: Uuid,
+        data: serde_json::Value,
+        expected_updated_at: Option<String>,
+    ) -> Result<Document> {
         let msg = ClientMessage::Update {
             id: self.next_id(),
             collection: collection.to_string(),
             document_id,
             data,
+            expected_updated_at,
         };
 
         match self.request(msg).await? {
             ServerMessage::Result { data, .. } => {
-                serde_json::from_value(data).map_err(|e| Error::Serialization(e.to_string()))
+                serde_json::from_value(data).map_err(Error::from)
             }
-            ServerMessage::Error { error, .. } => Err(Error::Server(error)),
-            _ => Err(Error::Server("Unexpected response".to_string())),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
         }
     }
 
-    /// Delete a document
-    pub async fn delete(&self, collection: &str, document_id: Uuid) -> Result<Document> {
+    /// Merge `partial` into a document on the server and return the result.
+    ///
+    /// The merge is shallow: top-level fields in `partial` overwrite the
+    /// document's fields and everything else is kept, so concurrent patches
+    /// to different fields don't clobber each other. A nested object in
+    /// `partial` replaces the existing object as a whole.
+    pub async fn patch(&self, collection: &str, document_id: Uuid, partial: serde_json::Value) -> Result<Document> {
+        self.patch_with_precondition(collection, document_id, partial, None).await
+    }
+
+    /// Patch a document only if its `updated_at` still equals
+    /// `expected_updated_at`. Fails with `Error::Conflict` otherwise.
+    pub async fn patch_if_unchanged(
+        &self,
+        collection: &str,
+        document_id: Uuid,
+        partial: serde_json::Value,
+        expected_updated_at: &str,
+    ) -> Result<Document> {
+        self.patch_with_precondition(collection, document_id, partial, Some(expected_updated_at.to_string()))
+            .await
+    }
+
+    async fn patch_with_precondition(
+        &self,
+        collection: &str,
+        document_id: Uuid,
+        partial: serde_json::Value,
+        expected_updated_at: Option<String>,
+    ) -> Result<Document> {
+        let msg = ClientMessage::Patch {
+            id: self.next_id(),
+            collection: collection.to_string(),
+            document_id,
+            data: partial,
+            expected_updated_at,
+        };
+
+        match self.request(msg).await? {
+            ServerMessage::Result { data, .. } => {
+                serde_json::from_value(data).map_err(Error::from)
+            }
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Apply JSON Patch operations to a document and return the result.
+    ///
+    /// The server applies `ops` in order and atomically: if any operation
+    /// fails (a missing path, or a `test` that doesn't match) the document is
+    /// left unchanged and the error is returned as `Error::Server`.
+    pub async fn json_patch(&self, collection: &str, document_id: Uuid, ops: Vec<PatchOp>) -> Result<Document> {
+        let msg = ClientMessage::JsonPatch {
+            id: self.next_id(),
+            collection: collection.to_string(),
+            document_id,
+            ops,
+        };
+
+        match self.request(msg).await? {
+            ServerMessage::Result { data, .. } => {
+                serde_json::from_value(data).map_err(Error::from)
+            }
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Apply atomic field operators such as `$inc`, `$push` or `$addToSet`
+    /// to a document and return the result, e.g. to read a counter's new
+    /// value. Unlike [`update`](Self::update) and [`patch`](Self::patch),
+    /// nothing is read first, so concurrent modifications of the same field
+    /// don't overwrite each other.
+    pub async fn modify(&self, collection: &str, document_id: Uuid, update: UpdateSpec) -> Result<Document> {
+        let msg = ClientMessage::Modify {
+            id: self.next_id(),
+            collection: collection.to_string(),
+            document_id,
+            update,
+        };
+
+        match self.request(msg).await? {
+            ServerMessage::Result { data, .. } => {
+                serde_json::from_value(data).map_err(Error::from)
+            }
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Delete a document, returning whether it existed.
+    ///
+    /// A missing document is not an error. The server may answer with the
+    /// deleted document, `{"deleted": bool}`, `null` or a `NOT_FOUND` error;
+    /// all are mapped to the boolean.
+    pub async fn delete(&self, collection: &str, document_id: Uuid) -> Result<bool> {
         let msg = ClientMessage::Delete {
             id: self.next_id(),
             collection: collection.to_string(),
             document_id,
         };
 
+        match self.request(msg).await? {
+            ServerMessage::Result { data, .. } => Ok(match data {
+                serde_json::Value::Null => false,
+                serde_json::Value::Object(obj) => obj
+                    .get("deleted")
+                    .and_then(serde_json::Value::as_bool)
+                    .unwrap_or(true),
+                _ => true,
+            }),
+            ServerMessage::Error { code, .. } if code.as_deref() == Some(NOT_FOUND_CODE) => Ok(false),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Merge `patch` into every document in `collection` matching `filter`,
+    /// returning how many were changed.
+    ///
+    /// Unlike [`update`](Self::update), which replaces the whole document,
+    /// this is a shallow merge: each top-level field in `patch` overwrites the
+    /// same field in the document and all other fields are kept. Nested
+    /// objects are replaced as a whole, not merged. An empty filter matches
+    /// every document.
+    pub async fn update_many(
+        &self,
+        collection: &str,
+        filter: &Filter,
+        patch: serde_json::Value,
+    ) -> Result<u64> {
+        let msg = ClientMessage::UpdateMany {
+            id: self.next_id(),
+            collection: collection.to_string(),
+            filter: filter.clone(),
+            patch,
+        };
+
+        match self.request(msg).await? {
+            ServerMessage::Result { data, .. } => {
+                serde_json::from_value(data).map_err(Error::from)
+            }
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Delete every document in `query.table` matching `query.filter`,
+    /// returning how many were removed (`Ok(0)` if none match).
+    ///
+    /// The server applies the delete as a single operation: documents either
+    /// match at the time it runs or are untouched, with no window for a
+    /// concurrent insert to slip between a read and the deletes. A query
+    /// without a filter deletes every document in the table. Sort, limit and
+    /// skip are ignored.
+    pub async fn delete_many(&self, query: &StructuredQuery) -> Result<u64> {
+        // Only the table and filter are sent, so only they are validated
+        StructuredQuery {
+            table: query.table.clone(),
+            filter: query.filter.clone(),
+            ..Default::default()
+        }
+        .validate()?;
+        let msg = ClientMessage::DeleteMany {
+            id: self.next_id(),
+            collection: query.table.clone(),
+            filter: query.filter.clone().unwrap_or_default(),
+        };
+
+        match self.request(msg).await? {
+            ServerMessage::Result { data, .. } => {
+                serde_json::from_value(data).map_err(Error::from)
+            }
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Run a set of writes atomically.
+    ///
+    /// The closure buffers operations on a [`Transaction`]; nothing is sent
+    /// until it returns, at which point every operation is committed as a
+    /// single `Transaction` message. The server applies the batch all or
+    /// nothing: if any operation fails the whole batch is rolled back and the
+    /// error is returned as `Error::Server`. Other clients never observe a
+    /// partially applied batch. Transactions are write-only, so there are no
+    /// reads that could see the buffered, uncommitted writes.
+    ///
+    /// Returns the resulting document for each operation, in order.
+    pub async fn transaction<F>(&self, build: F) -> Result<Vec<Document>>
+    where
+        F: FnOnce(&mut Transaction),
+    {
+        let mut tx = Transaction::default();
+        build(&mut tx);
+        if tx.ops.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let msg = ClientMessage::Transaction {
+            id: self.next_id(),
+            ops: tx.ops,
+        };
+
         match self.request(msg).await? {
             ServerMessage::Result { data, .. } => {
-                serde_json::from_value(data).map_err(|e| Error::Serialization(e.to_string()))
+                serde_json::from_value(data).map_err(Error::from)
             }
-            ServerMessage::Error { error, .. } => Err(Error::Server(error)),
-            _ => Err(Error::Server("Unexpected response".to_string())),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
         }
     }
 
-    /// List all collections
-    pub async fn list_collections(&self) -> Result<Vec<String>> {
+    /// List all collections, served from the collections cache when enabled
+    /// and still fresh
+    pub async fn list_collections(&self) -> Result<Vec<Collection>> {
+        if let Some(ttl) = self.collections_cache_ttl {
+            if let Some((fetched, collections)) = &*self.collections_cache.lock().unwrap() {
+                if fetched.elapsed() < ttl {
+                    return Ok(collections.clone());
+                }
+            }
+        }
+        self.refresh_collections().await
+    }
+
+    /// The frame length `msg` would be sent with on this connection. Anything
+    /// over [`max_message_size`](Self::max_message_size) fails with
+    /// `Error::MessageTooLarge`.
+    pub fn encoded_size(&self, msg: &ClientMessage) -> Result<usize> {
+        encoded_size(msg, self.encoding)
+    }
+
+    /// Forget every cached query result
+    pub fn clear_query_cache(&self) {
+        if let Some(cache) = &self.query_cache {
+            cache.clear();
+        }
+    }
+
+    /// Fetch the collection list from the server, bypassing and updating the cache
+    pub async fn refresh_collections(&self) -> Result<Vec<Collection>> {
         let msg = ClientMessage::ListCollections { id: self.next_id() };
 
+        let collections: Vec<Collection> = match self.request(msg).await? {
+            ServerMessage::Result { data, .. } => {
+                serde_json::from_value(data).map_err(Error::from)?
+            }
+            ServerMessage::Error { id, code, error } => return Err(server_error(id, code, error)),
+            _ => return Err(unexpected_response()),
+        };
+
+        if self.collections_cache_ttl.is_some() {
+            *self.collections_cache.lock().unwrap() = Some((Instant::now(), collections.clone()));
+        }
+        Ok(collections)
+    }
+
+    /// Create a collection. Fails with `Error::Server` if it already exists.
+    pub async fn create_collection(&self, name: &str, options: CreateCollectionOptions) -> Result<()> {
+        let msg = ClientMessage::CreateCollection {
+            id: self.next_id(),
+            name: name.to_string(),
+            options,
+        };
+        self.collection_admin(msg).await
+    }
+
+    /// Drop a collection and its documents. Fails with `Error::Server` if it
+    /// does not exist.
+    pub async fn drop_collection(&self, name: &str) -> Result<()> {
+        let msg = ClientMessage::DropCollection {
+            id: self.next_id(),
+            name: name.to_string(),
+        };
+        self.collection_admin(msg).await
+    }
+
+    async fn collection_admin(&self, msg: ClientMessage) -> Result<()> {
+        let result = self.request_ack(msg).await;
+        // The cached collection list is stale either way
+        *self.collections_cache.lock().unwrap() = None;
+        result
+    }
+
+    /// Create an index on `field`
+    pub async fn create_index(&self, collection: &str, field: &str, unique: bool) -> Result<()> {
+        let msg = ClientMessage::CreateIndex {
+            id: self.next_id(),
+            collection: collection.to_string(),
+            field: field.to_string(),
+            unique,
+        };
+        self.request_ack(msg).await
+    }
+
+    /// Drop the index on `field`
+    pub async fn drop_index(&self, collection: &str, field: &str) -> Result<()> {
+        let msg = ClientMessage::DropIndex {
+            id: self.next_id(),
+            collection: collection.to_string(),
+            field: field.to_string(),
+        };
+        self.request_ack(msg).await
+    }
+
+    /// List the indexes on a collection
+    pub async fn list_indexes(&self, collection: &str) -> Result<Vec<IndexInfo>> {
+        let msg = ClientMessage::ListIndexes {
+            id: self.next_id(),
+            collection: collection.to_string(),
+        };
+
         match self.request(msg).await? {
             ServerMessage::Result { data, .. } => {
-                serde_json::from_value(data).map_err(|e| Error::Serialization(e.to_string()))
+                serde_json::from_value(data).map_err(Error::from)
             }
-            ServerMessage::Error { error, .. } => Err(Error::Server(error)),
-            _ => Err(Error::Server("Unexpected response".to_string())),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Send a request whose successful result carries no data
+    async fn request_ack(&self, msg: ClientMessage) -> Result<()> {
+        match self.request(msg).await? {
+            ServerMessage::Result { .. } => Ok(()),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
         }
     }
 
     /// Subscribe to changes
-    pub async fn subscribe(&self, query: &str) -> Result<Subscription<'_>> {
+    pub async fn subscribe(&self, query: &str) -> Result<Subscription> {
+        self.subscribe_buffered(query, None).await
+    }
+
+    /// Subscribe to changes, holding at most `opts.buffer` undelivered events.
+    /// When `opts.overflow` drops changes the subscription yields
+    /// [`SubEvent::Lagged`] with the number lost where the gap is: ahead of
+    /// the buffered changes for `DropOldest`, after them for `DropNewest`.
+    /// Plain [`subscribe`](Self::subscribe) buffers without limit.
+    pub async fn subscribe_with_options(&self, query: &str, opts: SubscribeOptions) -> Result<Subscription> {
+        self.subscribe_buffered(query, Some(opts)).await
+    }
+
+    async fn subscribe_buffered(&self, query: &str, limit: Option<SubscribeOptions>) -> Result<Subscription> {
         let id = self.next_id();
         let msg = ClientMessage::Subscribe {
             id: id.clone(),
             query: query.to_string(),
         };
 
-        let (tx, rx) = mpsc::unbounded_channel();
-        self.subscriptions.write().await.insert(id.clone(), ActiveSubscription { tx });
+        let queue = Arc::new(SubQueue::new(limit));
+        self.subscriptions
+            .write()
+            .await
+            .insert(id.clone(), ActiveSubscription { queue: queue.clone() });
 
         match self.request(msg).await? {
-            ServerMessage::Subscribed { .. } => Ok(Subscription {
-                id,
-                rx,
-                client_subs: self.subscriptions.clone(),
-                client_writer: self.writer.clone(),
-                encoding: self.encoding,
-                request_id: &self.request_id,
-            }),
-            ServerMessage::Error { error, .. } => {
+            ServerMessage::Subscribed { .. } => {
+                trace_event!(debug, subscription_id = %id, "squirreldb subscribed");
+                Ok(Subscription {
+                    id,
+                    queue,
+                    client_subs: self.subscriptions.clone(),
+                    client_writer: self.writer.clone(),
+                    encoding: self.encoding,
+                })
+            }
+            ServerMessage::Error { code, error, .. } => {
                 self.subscriptions.write().await.remove(&id);
-                Err(Error::Server(error))
+                Err(server_error(id, code, error))
             }
             _ => {
                 self.subscriptions.write().await.remove(&id);
-                Err(Error::Server("Unexpected response".to_string()))
+                Err(unexpected_response())
+            }
+        }
+    }
+
+    /// Subscribe to changes using a structured query instead of a JS query string.
+    ///
+    /// Uses the builder's `changes` options when set, otherwise subscribes with
+    /// `include_initial: true`.
+    pub async fn subscribe_query(&self, query: &QueryBuilder) -> Result<Subscription> {
+        query.validate()?;
+        let mut structured = query.clone().compile_structured();
+        structured.changes.get_or_insert(ChangesOptions {
+            include_initial: true,
+        });
+        self.subscribe(&serde_json::to_string(&structured)?).await
+    }
+
+    /// Subscribe to changes, collecting the initial results before returning.
+    ///
+    /// Every [`ChangeEvent::Initial`] document up to the server's
+    /// [`SubEvent::Synced`] marker is returned as the snapshot, and the
+    /// subscription yields only the live changes that follow; a query
+    /// without `include_initial` gives an empty snapshot. A live change that
+    /// arrives before the marker also ends the snapshot and is the
+    /// subscription's first event.
+    ///
+    /// Fails with [`Error::UnsupportedServer`] if the server doesn't send
+    /// the marker, since the end of the snapshot couldn't be told apart
+    /// from a quiet collection.
+    pub async fn subscribe_with_snapshot(&self, query: &str) -> Result<(Vec<Document>, Subscription)> {
+        if !self.server_flags.supports(Feature::SnapshotSync) {
+            return Err(Error::UnsupportedServer {
+                missing: vec![Feature::SnapshotSync],
+            });
+        }
+        let mut subscription = self.subscribe(query).await?;
+        let mut snapshot = Vec::new();
+        loop {
+            match subscription.next().await {
+                Some(SubEvent::Change(ChangeEvent::Initial { document })) => snapshot.push(document),
+                Some(SubEvent::Change(change)) => {
+                    subscription.queue.push_front(SubEvent::Change(change));
+                    break;
+                }
+                Some(SubEvent::Synced) => break,
+                Some(SubEvent::Error(error)) => {
+                    return Err(Error::Server {
+                        code: None,
+                        message: error,
+                    })
+                }
+                Some(SubEvent::Lagged(n)) => {
+                    return Err(Error::Protocol(format!("Snapshot lost {} changes", n)));
+                }
+                Some(SubEvent::Closed) | None => return Err(Error::ChannelClosed),
+                Some(SubEvent::Ready | SubEvent::Reconnected) => {}
+            }
+        }
+        Ok((snapshot, subscription))
+    }
+
+    /// Subscribe to changes, deserializing each document's `data` into `T`
+    pub async fn subscribe_as<T: serde::de::DeserializeOwned>(
+        &self,
+        query: &str,
+    ) -> Result<TypedSubscription<T>> {
+        Ok(TypedSubscription {
+            inner: self.subscribe(query).await?,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Subscribe to changes and keep the feed going across disconnects.
+    ///
+    /// When the connection drops, the subscription redials with this client's
+    /// options and subscribes again, following
+    /// [`ConnectOptions::reconnect`]. Each success yields
+    /// [`SubEvent::Reconnected`]: changes made while disconnected are not
+    /// replayed, so there may be a gap. Once the attempts run out it yields
+    /// [`SubEvent::Closed`] and ends. If the server ends the subscription
+    /// while the connection is still up, it is not redialed and ends the same
+    /// way, as it does once this client is closed or dropped. Clients built
+    /// with [`with_transport`](Self::with_transport) cannot redial, so theirs
+    /// end on the first disconnect like a plain subscription.
+    ///
+    /// Redialed connections belong to the subscription and close with it.
+    pub async fn subscribe_resilient(&self, query: &str) -> Result<ResilientSubscription> {
+        let subscription = self.subscribe(query).await?;
+        let queue = Arc::new(SubQueue::new(None));
+        let task = tokio::spawn(Self::resubscribe_loop(
+            subscription,
+            query.to_string(),
+            self.redial.clone(),
+            self.closed.clone(),
+            self.released.clone(),
+            queue.clone(),
+            self.counters.clone(),
+        ));
+        Ok(ResilientSubscription { queue, task })
+    }
+
+    async fn resubscribe_loop(
+        mut subscription: Subscription,
+        query: String,
+        redial: Option<ConnectOptions>,
+        mut closed: Arc<AtomicBool>,
+        mut released: Arc<AtomicBool>,
+        queue: Arc<SubQueue>,
+        counters: Arc<Counters>,
+    ) {
+        // Keeps the current redialed connection open
+        let mut _connection = None;
+        loop {
+            while let Some(event) = subscription.next().await {
+                match event {
+                    SubEvent::Closed => break,
+                    event => queue.push(event),
+                }
+            }
+            // Only a failed connection is redialed: not one the caller closed
+            // or dropped, nor a live one the server ended the subscription on
+            if released.load(Ordering::SeqCst) || !closed.load(Ordering::SeqCst) {
+                break;
             }
+            let Some(opts) = &redial else { break };
+            trace_event!(debug, "squirreldb subscription lost its connection; redialing");
+            let Some((connection, resubscribed)) = Self::redial_and_subscribe(opts, &query).await else {
+                trace_event!(warn, "squirreldb subscription gave up reconnecting");
+                break;
+            };
+            counters.reconnects.fetch_add(1, Ordering::Relaxed);
+            subscription = resubscribed;
+            closed = connection.closed.clone();
+            released = connection.released.clone();
+            _connection = Some(connection);
+            queue.push(SubEvent::Reconnected);
         }
+        queue.push(SubEvent::Closed);
+        queue.close_sender();
+    }
+
+    async fn redial_and_subscribe(opts: &ConnectOptions, query: &str) -> Option<(SquirrelDB, Subscription)> {
+        let policy = opts.reconnect;
+        let mut backoff = policy.initial_backoff;
+        for attempt in 0..policy.max_attempts {
+            if attempt > 0 {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+            }
+            let Ok(connection) = Self::connect_with_options(opts.clone()).await else {
+                continue;
+            };
+            if let Ok(subscription) = connection.subscribe(query).await {
+                return Some((connection, subscription));
+            }
+        }
+        None
+    }
+
+    /// Close the connection deterministically.
+    ///
+    /// Unsubscribes every active subscription, flushes and shuts down the
+    /// socket, then stops the background tasks and waits for them to finish.
+    /// Requests still awaiting a response fail with `Error::ChannelClosed`.
+    pub async fn close(mut self) -> Result<()> {
+        self.released.store(true, Ordering::SeqCst);
+        let subscription_ids: Vec<String> =
+            self.subscriptions.write().await.drain().map(|(id, _)| id).collect();
+
+        let mut result = Ok(());
+        for id in subscription_ids {
+            let msg = ClientMessage::Unsubscribe { id };
+            if let Err(e) = Self::send_frame(&self.writer, self.encoding, &msg).await {
+                result = Err(e);
+                break;
+            }
+        }
+        if result.is_ok() {
+            result = self.writer.stream.lock().await.shutdown().await.map_err(Error::from);
+        }
+
+        if let Some(task) = self.keep_alive_task.take() {
+            task.abort();
+        }
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = (&mut self.reader_task).await;
+        self.pending.write().await.clear();
+
+        result
     }
 
     /// Ping the server
@@ -387,56 +2085,377 @@ impl SquirrelDB {
 
         match self.request(msg).await? {
             ServerMessage::Pong { .. } => Ok(()),
-            ServerMessage::Error { error, .. } => Err(Error::Server(error)),
-            _ => Err(Error::Server("Unexpected response".to_string())),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Ping the server and return the time from sending the request to
+    /// receiving its `Pong`
+    pub async fn ping_rtt(&self) -> Result<Duration> {
+        let msg = ClientMessage::Ping { id: self.next_id() };
+
+        let started = Instant::now();
+        let response = self.request(msg).await?;
+        let rtt = started.elapsed();
+        match response {
+            ServerMessage::Pong { .. } => Ok(rtt),
+            ServerMessage::Error { id, code, error } => Err(server_error(id, code, error)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Ping the server and return the round-trip time, e.g. for a readiness
+    /// probe or a latency metric
+    pub async fn health_check(&self) -> Result<Duration> {
+        self.ping_rtt().await
+    }
+}
+
+impl Drop for SquirrelDB {
+    /// Stop the background tasks and end every subscription, which may
+    /// outlive the client. The socket is shut down when a runtime is
+    /// available to do it on.
+    fn drop(&mut self) {
+        self.released.store(true, Ordering::SeqCst);
+        self.closed.store(true, Ordering::SeqCst);
+        self.reader_task.abort();
+        if let Some(task) = &self.keep_alive_task {
+            task.abort();
+        }
+
+        let runtime = tokio::runtime::Handle::try_current().ok();
+        match self.subscriptions.try_write() {
+            Ok(mut subscriptions) => {
+                for (_, sub) in subscriptions.drain() {
+                    sub.queue.push(SubEvent::Closed);
+                }
+            }
+            Err(_) => {
+                if let Some(runtime) = &runtime {
+                    let subscriptions = self.subscriptions.clone();
+                    runtime.spawn(async move { Self::close_subscriptions(&subscriptions).await });
+                }
+            }
+        }
+        if let Some(runtime) = runtime {
+            let writer = self.writer.clone();
+            runtime.spawn(async move {
+                let _ = writer.stream.lock().await.shutdown().await;
+            });
+        }
+    }
+}
+
+/// Events delivered on a subscription stream
+#[derive(Debug)]
+pub enum SubEvent<E = ChangeEvent> {
+    /// The server confirmed the subscription
+    Ready,
+    /// A change matching the subscribed query
+    Change(E),
+    /// Every initial result has been delivered; later changes are live
+    Synced,
+    /// The server reported an error for this subscription
+    Error(String),
+    /// The subscription ended (unsubscribed or connection lost); no further events follow
+    Closed,
+    /// This many changes were dropped by the buffer's overflow policy
+    Lagged(u64),
+    /// A resilient subscription reconnected; changes made while it was
+    /// disconnected were missed
+    Reconnected,
+}
+
+impl<E> SubEvent<E> {
+    /// Map the change payload, keeping lifecycle events as they are
+    pub fn map<F, U>(self, f: F) -> SubEvent<U>
+    where
+        F: FnOnce(E) -> U,
+    {
+        match self {
+            SubEvent::Ready => SubEvent::Ready,
+            SubEvent::Change(change) => SubEvent::Change(f(change)),
+            SubEvent::Synced => SubEvent::Synced,
+            SubEvent::Error(error) => SubEvent::Error(error),
+            SubEvent::Closed => SubEvent::Closed,
+            SubEvent::Lagged(n) => SubEvent::Lagged(n),
+            SubEvent::Reconnected => SubEvent::Reconnected,
         }
     }
 }
 
 /// Active subscription handle
-pub struct Subscription<'a> {
+pub struct Subscription {
     id: String,
-    rx: mpsc::UnboundedReceiver<ChangeEvent>,
+    queue: Arc<SubQueue>,
     client_subs: Arc<RwLock<HashMap<String, ActiveSubscription>>>,
-    client_writer: Arc<tokio::sync::Mutex<BufWriter<OwnedWriteHalf>>>,
+    client_writer: Arc<SharedWriter>,
     encoding: Encoding,
-    request_id: &'a AtomicU64,
 }
 
-impl<'a> Subscription<'a> {
-    pub async fn next(&mut self) -> Option<ChangeEvent> {
-        self.rx.recv().await
+impl Subscription {
+    pub async fn next(&mut self) -> Option<SubEvent> {
+        std::future::poll_fn(|cx| self.queue.poll_recv(cx)).await
     }
 
-    pub async fn unsubscribe(self) -> Result<()> {
-        self.client_subs.write().await.remove(&self.id);
-        let id = self.request_id.fetch_add(1, Ordering::SeqCst).to_string();
+    pub async fn unsubscribe(mut self) -> Result<()> {
+        // Taking the id tells Drop there is nothing left to clean up
+        let id = std::mem::take(&mut self.id);
+        self.client_subs.write().await.remove(&id);
+        trace_event!(debug, subscription_id = %id, "squirreldb unsubscribed");
         let msg = ClientMessage::Unsubscribe { id };
 
-        let payload = match self.encoding {
-            Encoding::MessagePack => rmp_serde::to_vec(&msg)?,
-            Encoding::Json => serde_json::to_vec(&msg)?,
+        SquirrelDB::send_frame(&self.client_writer, self.encoding, &msg).await
+    }
+}
+
+impl Drop for Subscription {
+    /// Best-effort unsubscribe so the server stops the feed. Skipped if the
+    /// subscription already ended or there is no runtime to send from.
+    fn drop(&mut self) {
+        // Unblock the reader task if it is waiting for buffer space
+        self.queue.close_receiver();
+        if self.id.is_empty() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
         };
 
-        let length = (payload.len() + 2) as u32;
-        let mut writer = self.client_writer.lock().await;
-        writer.write_u32(length).await?;
-        writer.write_u8(MessageType::Request as u8).await?;
-        writer.write_u8(self.encoding as u8).await?;
-        writer.write_all(&payload).await?;
-        writer.flush().await?;
+        let id = std::mem::take(&mut self.id);
+        let subs = self.client_subs.clone();
+        let writer = self.client_writer.clone();
+        let encoding = self.encoding;
+        runtime.spawn(async move {
+            let Ok(frame) = encode_frame(&ClientMessage::Unsubscribe { id: id.clone() }, encoding) else {
+                return;
+            };
+            // Hold the writer while removing the entry so a concurrent close()
+            // either sends this unsubscribe itself or waits for ours
+            let mut stream = writer.stream.lock().await;
+            if subs.write().await.remove(&id).is_none() {
+                return;
+            }
+            trace_event!(debug, subscription_id = %id, "squirreldb unsubscribed on drop");
+            if stream.write_all(&frame).await.is_ok() && stream.flush().await.is_ok() {
+                if let Some(logger) = &writer.frame_logger {
+                    logger.log(Direction::Outbound, &frame);
+                }
+            }
+        });
+    }
+}
 
-        Ok(())
+impl Stream for Subscription {
+    type Item = SubEvent;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.queue.poll_recv(cx)
+    }
+}
+
+/// Subscription handle from [`SquirrelDB::subscribe_resilient`]. Dropping it
+/// unsubscribes and closes any connection it redialed.
+pub struct ResilientSubscription {
+    queue: Arc<SubQueue>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ResilientSubscription {
+    pub async fn next(&mut self) -> Option<SubEvent> {
+        std::future::poll_fn(|cx| self.queue.poll_recv(cx)).await
+    }
+}
+
+impl Drop for ResilientSubscription {
+    fn drop(&mut self) {
+        self.queue.close_receiver();
+        self.task.abort();
+    }
+}
+
+impl Stream for ResilientSubscription {
+    type Item = SubEvent;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.queue.poll_recv(cx)
+    }
+}
+
+/// Server-side cursor from [`SquirrelDB::open_cursor`].
+///
+/// Read it batch by batch with [`next_batch`](Self::next_batch) until it
+/// returns `None`. The server releases an exhausted cursor on its own;
+/// [`close`](Self::close) releases one early, and dropping an open cursor
+/// sends a best-effort close.
+pub struct Cursor<'a> {
+    client: &'a SquirrelDB,
+    cursor_id: String,
+    /// The batch that came with the open response, not yet handed out
+    first: Option<Vec<serde_json::Value>>,
+    /// Holds the cursor id while the server still has the cursor open
+    guard: CursorGuard,
+}
+
+impl Cursor<'_> {
+    /// Server-assigned cursor id
+    pub fn cursor_id(&self) -> &str {
+        &self.cursor_id
+    }
+
+    /// Whether the server has sent its last batch
+    pub fn is_exhausted(&self) -> bool {
+        self.guard.cursor_id.is_none()
+    }
+
+    /// The next batch of results, or `None` once the cursor is exhausted
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<serde_json::Value>>> {
+        if let Some(documents) = self.first.take() {
+            return Ok(Some(documents));
+        }
+        let Some(cursor_id) = self.guard.cursor_id.clone() else {
+            return Ok(None);
+        };
+
+        let msg = ClientMessage::FetchCursor {
+            id: self.client.next_id(),
+            cursor_id,
+        };
+        let (_, documents, exhausted) = self.client.cursor_request(msg).await?;
+        if exhausted {
+            self.guard.cursor_id = None;
+        }
+        Ok(Some(documents))
+    }
+
+    /// Release the cursor on the server without reading the rest
+    pub async fn close(mut self) -> Result<()> {
+        let Some(cursor_id) = self.guard.cursor_id.take() else {
+            return Ok(());
+        };
+        let msg = ClientMessage::CloseCursor {
+            id: self.guard.close_id.clone(),
+            cursor_id,
+        };
+        SquirrelDB::send_frame(&self.client.writer, self.client.encoding, &msg).await
+    }
+}
+
+struct StreamState<'a> {
+    /// Taken when the cursor is opened
+    query: Option<String>,
+    cursor: Option<Cursor<'a>>,
+    buffer: VecDeque<serde_json::Value>,
+}
+
+/// Closes a cursor that is dropped while still open on the server
+struct CursorGuard {
+    cursor_id: Option<String>,
+    close_id: String,
+    writer: Arc<SharedWriter>,
+    encoding: Encoding,
+}
+
+impl CursorGuard {
+    /// Best-effort close; skipped if there is no runtime to send from
+    fn close(&self, cursor_id: String) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let msg = ClientMessage::CloseCursor {
+            id: self.close_id.clone(),
+            cursor_id,
+        };
+        let writer = self.writer.clone();
+        let encoding = self.encoding;
+        runtime.spawn(async move {
+            let _ = SquirrelDB::send_frame(&writer, encoding, &msg).await;
+        });
+    }
+}
+
+impl Drop for CursorGuard {
+    fn drop(&mut self) {
+        if let Some(cursor_id) = self.cursor_id.take() {
+            self.close(cursor_id);
+        }
+    }
+}
+
+/// Change event with document data deserialized into `T`
+#[derive(Debug)]
+pub enum TypedChangeEvent<T> {
+    Initial { document: T },
+    Insert { new: T },
+    Update { old: T, new: T },
+    Delete { old: T },
+    /// The document data did not match `T`; the original event is kept for inspection
+    Error { error: Error, event: ChangeEvent },
+}
+
+impl<T: serde::de::DeserializeOwned> From<ChangeEvent> for TypedChangeEvent<T> {
+    fn from(event: ChangeEvent) -> Self {
+        fn decode<T: serde::de::DeserializeOwned>(data: &serde_json::Value) -> Result<T> {
+            serde_json::from_value(data.clone()).map_err(Error::from)
+        }
+
+        let typed = match &event {
+            ChangeEvent::Initial { document } => {
+                decode(&document.data).map(|document| TypedChangeEvent::Initial { document })
+            }
+            ChangeEvent::Insert { new } => decode(&new.data).map(|new| TypedChangeEvent::Insert { new }),
+            ChangeEvent::Update { old, new } => {
+                // `old` may be a full document or just its data
+                let old_data = match old.get("data") {
+                    Some(data) if data.is_object() => data,
+                    _ => old,
+                };
+                decode(old_data).and_then(|old| {
+                    decode(&new.data).map(|new| TypedChangeEvent::Update { old, new })
+                })
+            }
+            ChangeEvent::Delete { old } => decode(&old.data).map(|old| TypedChangeEvent::Delete { old }),
+        };
+
+        typed.unwrap_or_else(|error| TypedChangeEvent::Error { error, event })
+    }
+}
+
+/// Subscription yielding [`TypedChangeEvent`]s
+pub struct TypedSubscription<T> {
+    inner: Subscription,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: serde::de::DeserializeOwned> TypedSubscription<T> {
+    pub async fn next(&mut self) -> Option<SubEvent<TypedChangeEvent<T>>> {
+        self.inner
+            .next()
+            .await
+            .map(|event| event.map(TypedChangeEvent::from))
+    }
+
+    pub async fn unsubscribe(self) -> Result<()> {
+        self.inner.unsubscribe().await
     }
 }
 
-impl Stream for Subscription<'_> {
-    type Item = ChangeEvent;
+impl<T: serde::de::DeserializeOwned> Stream for TypedSubscription<T> {
+    type Item = SubEvent<TypedChangeEvent<T>>;
 
     fn poll_next(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        std::pin::Pin::new(&mut self.rx).poll_recv(cx)
+        std::pin::Pin::new(&mut self.inner)
+            .poll_next(cx)
+            .map(|event| event.map(|event| event.map(TypedChangeEvent::from)))
     }
 }
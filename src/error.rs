@@ -2,6 +2,9 @@
 
 use thiserror::Error;
 
+use crate::protocol::Feature;
+use crate::query::QueryError;
+
 #[derive(Error, Debug)]
 pub enum Error {
   #[error("Connection error: {0}")]
@@ -16,37 +19,99 @@ pub enum Error {
   #[error("Authentication failed")]
   AuthFailed,
 
+  #[error("Server does not support required features: {missing:?}")]
+  UnsupportedServer { missing: Vec<Feature> },
+
   #[error("IO error: {0}")]
   Io(#[from] std::io::Error),
 
   #[error("Serialization error: {0}")]
-  Serialization(String),
+  Serialization(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+  #[error("Invalid query: {0}")]
+  Query(#[source] Box<QueryError>),
+
+  #[error("Protocol error: {0}")]
+  Protocol(String),
+
+  #[error("Message too large: {size} bytes (max {max})")]
+  MessageTooLarge { size: usize, max: usize },
 
-  #[error("Server error: {0}")]
-  Server(String),
+  #[error("Server error: {message}")]
+  Server { code: Option<String>, message: String },
+
+  #[error("Write conflict: {0}")]
+  Conflict(String),
 
   #[error("Timeout")]
   Timeout,
 
   #[error("Channel closed")]
   ChannelClosed,
+
+  #[error("Too many requests in flight")]
+  Overloaded,
+
+  #[error("Invalid configuration: {0}")]
+  Config(String),
+}
+
+/// Server error codes for conditions that clear up on their own
+const TRANSIENT_SERVER_CODES: &[&str] = &["UNAVAILABLE", "TIMEOUT", "OVERLOADED"];
+
+impl Error {
+  /// Whether retrying the operation may succeed: timeouts, dropped
+  /// connections, backpressure and server errors with a transient code.
+  /// Authentication, version, protocol and serialization errors are
+  /// terminal.
+  pub fn is_retryable(&self) -> bool {
+    match self {
+      Self::Timeout | Self::ChannelClosed | Self::Overloaded | Self::Connection(_) => true,
+      Self::Io(e) => is_transient_io(e),
+      Self::Server { code: Some(code), .. } => TRANSIENT_SERVER_CODES.contains(&code.as_str()),
+      _ => false,
+    }
+  }
+
+  /// Whether the error means the connection is unusable and must be re-established
+  pub fn is_connection_error(&self) -> bool {
+    match self {
+      Self::Connection(_) | Self::ChannelClosed => true,
+      Self::Io(e) => is_transient_io(e),
+      _ => false,
+    }
+  }
+}
+
+fn is_transient_io(e: &std::io::Error) -> bool {
+  use std::io::ErrorKind::*;
+  matches!(
+    e.kind(),
+    ConnectionReset | ConnectionAborted | ConnectionRefused | BrokenPipe | UnexpectedEof | TimedOut
+  )
 }
 
 impl From<rmp_serde::encode::Error> for Error {
   fn from(e: rmp_serde::encode::Error) -> Self {
-    Self::Serialization(e.to_string())
+    Self::Serialization(Box::new(e))
   }
 }
 
 impl From<rmp_serde::decode::Error> for Error {
   fn from(e: rmp_serde::decode::Error) -> Self {
-    Self::Serialization(e.to_string())
+    Self::Serialization(Box::new(e))
+  }
+}
+
+impl From<QueryError> for Error {
+  fn from(e: QueryError) -> Self {
+    Self::Query(Box::new(e))
   }
 }
 
 impl From<serde_json::Error> for Error {
   fn from(e: serde_json::Error) -> Self {
-    Self::Serialization(e.to_string())
+    Self::Serialization(Box::new(e))
   }
 }
 
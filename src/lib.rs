@@ -2,22 +2,41 @@
 //! Generated by sdk-generator v0.1.0
 //! DO NOT EDIT MANUALLY
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod cache;
 mod client;
 mod error;
+mod metrics;
+mod pool;
 pub mod protocol;
 pub mod query;
+mod query_cache;
 pub mod storage;
 
-pub use client::{ConnectOptions, SquirrelDB, Subscription};
+pub use client::{
+    ConnectOptions, Cursor, Direction, FrameLogger, IdGenerator, Overflow, ReconnectPolicy,
+    ResilientSubscription, SquirrelDB, SubEvent, SubscribeOptions, Subscription, Transaction,
+    Transport, TypedChangeEvent, TypedSubscription,
+};
 pub use error::{Error, Result};
+pub use metrics::ClientMetrics;
+pub use pool::SquirrelDBPool;
 pub use protocol::{
-    ChangeEvent, ClientMessage, Document, Encoding, HandshakeStatus, MessageType, ProtocolFlags,
-    ServerMessage, MAGIC, MAX_MESSAGE_SIZE, PROTOCOL_VERSION,
+    ChangeEvent, ClientMessage, Collection, CreateCollectionOptions, Document, Encoding, Feature,
+    HandshakeStatus, IndexInfo, IndexSpec, MessageType, PatchOp, ProtocolFlags, QueryPage, ServerMessage,
+    TransactionOp, UpdateOp, UpdateSpec, decode_frame, encode_frame, encoded_size, COMPRESSED_BIT, FRAME_HEADER_SIZE, MAGIC, MAX_MESSAGE_SIZE, PROTOCOL_VERSION,
+};
+#[cfg(feature = "compression")]
+pub use protocol::compress_frame;
+pub use storage::{
+    Bucket, ListOptions, MultipartUpload, ObjectListing, PutOptions, StorageClient, StorageError,
+    StorageObject, StorageOptions, UploadPart,
 };
-pub use storage::{Bucket, StorageClient, StorageError, StorageObject, StorageOptions};
-pub use cache::{CacheClient, CacheError, CacheOptions, RespValue};
+pub use cache::{CacheClient, CacheError, CacheOptions, PubSubMessage, RespValue, SlowLogEntry};
 pub use query::{
-    ChangesOptions, FieldExpr, FilterCondition, QueryBuilder, SortDirection, SortSpec,
+    Accumulator, ChangesOptions, FieldExpr, Filter, GroupSpec, FilterCondition, QueryBuilder, QueryError, SortDirection, SortSpec,
     StructuredQuery, and, field, not, or, table,
 };
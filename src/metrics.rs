@@ -0,0 +1,136 @@
+//! Client metrics: counters updated on the request path and a snapshot type
+
+use std::collections::BTreeMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::client::Transport;
+use crate::error::Error;
+
+/// Point-in-time copy of a client's counters, see [`SquirrelDB::metrics`](crate::SquirrelDB::metrics)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientMetrics {
+    /// Requests written to the connection
+    pub requests_sent: u64,
+    /// Responses matched to a request, including server error replies
+    pub responses_received: u64,
+    /// Failed requests keyed by `Error` variant name; server error replies
+    /// count as `Server`
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Bytes read from the transport, handshake included
+    pub bytes_read: u64,
+    /// Bytes written to the transport, handshake included
+    pub bytes_written: u64,
+    /// Requests currently awaiting a response
+    pub in_flight: usize,
+    /// Successful redials made for this client: by its resilient
+    /// subscriptions, or by a pool replacing a dead connection (carried over
+    /// to the replacement)
+    pub reconnects: u64,
+}
+
+/// Live counters shared by the client and its transport
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    pub(crate) requests_sent: AtomicU64,
+    pub(crate) responses_received: AtomicU64,
+    pub(crate) reconnects: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl Counters {
+    pub(crate) fn record_error(&self, kind: &'static str) {
+        *self.errors.lock().unwrap().entry(kind).or_default() += 1;
+    }
+
+    pub(crate) fn snapshot(&self, in_flight: usize) -> ClientMetrics {
+        ClientMetrics {
+            requests_sent: self.requests_sent.load(Ordering::Relaxed),
+            responses_received: self.responses_received.load(Ordering::Relaxed),
+            errors: self.errors.lock().unwrap().clone(),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            in_flight,
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Name of an error's variant, used as its metrics key
+pub(crate) fn error_kind(error: &Error) -> &'static str {
+    match error {
+        Error::Connection(_) => "Connection",
+        Error::Handshake(_) => "Handshake",
+        Error::VersionMismatch { .. } => "VersionMismatch",
+        Error::AuthFailed => "AuthFailed",
+        Error::UnsupportedServer { .. } => "UnsupportedServer",
+        Error::Io(_) => "Io",
+        Error::Serialization(_) => "Serialization",
+        Error::Query(_) => "Query",
+        Error::Protocol(_) => "Protocol",
+        Error::MessageTooLarge { .. } => "MessageTooLarge",
+        Error::Server { .. } => "Server",
+        Error::Conflict(_) => "Conflict",
+        Error::Timeout => "Timeout",
+        Error::ChannelClosed => "ChannelClosed",
+        Error::Overloaded => "Overloaded",
+        Error::Config(_) => "Config",
+    }
+}
+
+/// Transport wrapper counting the bytes that pass through it
+pub(crate) struct CountingTransport {
+    inner: Box<dyn Transport>,
+    counters: Arc<Counters>,
+}
+
+impl CountingTransport {
+    pub(crate) fn new(inner: Box<dyn Transport>, counters: Arc<Counters>) -> Self {
+        Self { inner, counters }
+    }
+}
+
+impl AsyncRead for CountingTransport {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            let read = (buf.filled().len() - before) as u64;
+            self.counters.bytes_read.fetch_add(read, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl AsyncWrite for CountingTransport {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.counters.bytes_written.fetch_add(written as u64, Ordering::Relaxed);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,228 @@
+//! A fixed-size pool of client connections with least-loaded dispatch.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::client::{ConnectOptions, ResilientSubscription, SquirrelDB, Subscription, Transaction};
+use crate::error::{Error, Result};
+use crate::protocol::{Collection, Document, UpdateSpec};
+use crate::query::{Filter, QueryBuilder, StructuredQuery};
+
+/// How long a dead connection's replacement may take to connect
+const REDIAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pool of SquirrelDB connections.
+///
+/// Each request goes to the live connection with the fewest requests in
+/// flight, so one slow query only blocks the connection it runs on.
+/// Connections found dead are redialed in the background while requests go
+/// to the live ones; only when none is live does a request wait for a
+/// redial. A subscription stays on the connection it was opened on for its
+/// lifetime.
+pub struct SquirrelDBPool {
+    opts: ConnectOptions,
+    connections: Vec<Arc<Slot>>,
+}
+
+struct Slot {
+    // Never held across an await, so readers are never stalled by a redial
+    client: std::sync::RwLock<Arc<SquirrelDB>>,
+    redialing: AtomicBool,
+}
+
+impl Slot {
+    fn current(&self) -> Arc<SquirrelDB> {
+        self.client.read().unwrap().clone()
+    }
+
+    /// Put `client` in the slot unless another redial already revived it
+    fn install(&self, client: SquirrelDB) -> Arc<SquirrelDB> {
+        let mut current = self.client.write().unwrap();
+        if !current.is_connected() {
+            client.record_reconnect_of(&current);
+            *current = Arc::new(client);
+        }
+        current.clone()
+    }
+}
+
+impl SquirrelDBPool {
+    /// Open `size` connections with the same options
+    pub async fn connect(opts: ConnectOptions, size: usize) -> Result<Self> {
+        if size == 0 {
+            return Err(Error::Config("Pool size must be at least 1".to_string()));
+        }
+
+        let mut connections = Vec::with_capacity(size);
+        for _ in 0..size {
+            let client = SquirrelDB::connect_with_options(opts.clone()).await?;
+            connections.push(Arc::new(Slot {
+                client: std::sync::RwLock::new(Arc::new(client)),
+                redialing: AtomicBool::new(false),
+            }));
+        }
+        Ok(Self { opts, connections })
+    }
+
+    /// Number of connections in the pool
+    pub fn size(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// The least-loaded live connection. Dead ones found along the way are
+    /// redialed in the background; if none is live, waits for a redial and
+    /// fails only if that fails.
+    pub async fn get(&self) -> Result<Arc<SquirrelDB>> {
+        let mut best: Option<Arc<SquirrelDB>> = None;
+
+        for slot in &self.connections {
+            let client = slot.current();
+            if !client.is_connected() {
+                self.redial_in_background(slot);
+                continue;
+            }
+            if best.as_ref().is_none_or(|b| client.in_flight() < b.in_flight()) {
+                best = Some(client);
+            }
+        }
+
+        match best {
+            Some(client) => Ok(client),
+            None => self.redial_now().await,
+        }
+    }
+
+    fn redial_in_background(&self, slot: &Arc<Slot>) {
+        if slot.redialing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let slot = slot.clone();
+        let opts = self.opts.clone();
+        tokio::spawn(async move {
+            if let Ok(client) = Self::dial(opts).await {
+                slot.install(client);
+            }
+            slot.redialing.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Dial a replacement for the first dead slot and wait for it
+    async fn redial_now(&self) -> Result<Arc<SquirrelDB>> {
+        let client = Self::dial(self.opts.clone()).await?;
+        let slot = self
+            .connections
+            .iter()
+            .find(|slot| !slot.current().is_connected())
+            .unwrap_or(&self.connections[0]);
+        Ok(slot.install(client))
+    }
+
+    async fn dial(opts: ConnectOptions) -> Result<SquirrelDB> {
+        tokio::time::timeout(REDIAL_TIMEOUT, SquirrelDB::connect_with_options(opts))
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+
+    /// Execute a query
+    pub async fn query<T: serde::de::DeserializeOwned>(&self, query: &str) -> Result<T> {
+        self.get().await?.query(query).await
+    }
+
+    /// Run a structured query as-is
+    pub async fn query_structured(&self, query: &StructuredQuery) -> Result<Vec<serde_json::Value>> {
+        self.get().await?.query_structured(query).await
+    }
+
+    /// Insert a document
+    pub async fn insert(&self, collection: &str, data: serde_json::Value) -> Result<Document> {
+        self.get().await?.insert(collection, data).await
+    }
+
+    /// Insert a typed document; see [`SquirrelDB::insert_typed`]
+    pub async fn insert_typed<T: serde::Serialize>(&self, collection: &str, data: &T) -> Result<Document> {
+        self.get().await?.insert_typed(collection, data).await
+    }
+
+    /// Update a document
+    pub async fn update(&self, collection: &str, document_id: Uuid, data: serde_json::Value) -> Result<Document> {
+        self.get().await?.update(collection, document_id, data).await
+    }
+
+    /// Shallow-merge `partial` into a document
+    pub async fn patch(&self, collection: &str, document_id: Uuid, partial: serde_json::Value) -> Result<Document> {
+        self.get().await?.patch(collection, document_id, partial).await
+    }
+
+    /// Apply atomic field operators to a document; see [`SquirrelDB::modify`]
+    pub async fn modify(&self, collection: &str, document_id: Uuid, update: UpdateSpec) -> Result<Document> {
+        self.get().await?.modify(collection, document_id, update).await
+    }
+
+    /// Delete a document, returning whether it existed
+    pub async fn delete(&self, collection: &str, document_id: Uuid) -> Result<bool> {
+        self.get().await?.delete(collection, document_id).await
+    }
+
+    /// Merge `patch` into every document matching `filter`
+    pub async fn update_many(
+        &self,
+        collection: &str,
+        filter: &Filter,
+        patch: serde_json::Value,
+    ) -> Result<u64> {
+        self.get().await?.update_many(collection, filter, patch).await
+    }
+
+    /// Delete every document matching the query's filter
+    pub async fn delete_many(&self, query: &StructuredQuery) -> Result<u64> {
+        self.get().await?.delete_many(query).await
+    }
+
+    /// Run a set of writes atomically on one connection
+    pub async fn transaction<F>(&self, build: F) -> Result<Vec<Document>>
+    where
+        F: FnOnce(&mut Transaction),
+    {
+        self.get().await?.transaction(build).await
+    }
+
+    /// List collections
+    pub async fn list_collections(&self) -> Result<Vec<Collection>> {
+        self.get().await?.list_collections().await
+    }
+
+    /// Subscribe to changes; the subscription stays on the chosen connection
+    pub async fn subscribe(&self, query: &str) -> Result<Subscription> {
+        self.get().await?.subscribe(query).await
+    }
+
+    /// Subscribe to changes using a structured query
+    pub async fn subscribe_query(&self, query: &QueryBuilder) -> Result<Subscription> {
+        self.get().await?.subscribe_query(query).await
+    }
+
+    /// Subscribe to changes, collecting the initial results first; see
+    /// [`SquirrelDB::subscribe_with_snapshot`]
+    pub async fn subscribe_with_snapshot(&self, query: &str) -> Result<(Vec<Document>, Subscription)> {
+        self.get().await?.subscribe_with_snapshot(query).await
+    }
+
+    /// Subscribe to changes and keep the feed going across disconnects; see
+    /// [`SquirrelDB::subscribe_resilient`]
+    pub async fn subscribe_resilient(&self, query: &str) -> Result<ResilientSubscription> {
+        self.get().await?.subscribe_resilient(query).await
+    }
+
+    /// Ping one connection
+    pub async fn ping(&self) -> Result<()> {
+        self.get().await?.ping().await
+    }
+
+    /// Ping one connection and return the round-trip time
+    pub async fn ping_rtt(&self) -> Result<Duration> {
+        self.get().await?.ping_rtt().await
+    }
+}
@@ -1,8 +1,14 @@
 //! Wire protocol types and serialization for SquirrelDB.
 
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::error::{Error, Result};
+use crate::query::Filter;
+
 /// Protocol magic bytes
 pub const MAGIC: &[u8; 4] = b"SQRL";
 
@@ -23,7 +29,7 @@ pub enum HandshakeStatus {
 
 impl TryFrom<u8> for HandshakeStatus {
   type Error = ();
-  fn try_from(v: u8) -> Result<Self, Self::Error> {
+  fn try_from(v: u8) -> std::result::Result<Self, Self::Error> {
     match v {
       0x00 => Ok(Self::Success),
       0x01 => Ok(Self::VersionMismatch),
@@ -44,7 +50,7 @@ pub enum MessageType {
 
 impl TryFrom<u8> for MessageType {
   type Error = ();
-  fn try_from(v: u8) -> Result<Self, Self::Error> {
+  fn try_from(v: u8) -> std::result::Result<Self, Self::Error> {
     match v {
       0x01 => Ok(Self::Request),
       0x02 => Ok(Self::Response),
@@ -65,7 +71,7 @@ pub enum Encoding {
 
 impl TryFrom<u8> for Encoding {
   type Error = ();
-  fn try_from(v: u8) -> Result<Self, Self::Error> {
+  fn try_from(v: u8) -> std::result::Result<Self, Self::Error> {
     match v {
       0x01 => Ok(Self::MessagePack),
       0x02 => Ok(Self::Json),
@@ -79,6 +85,28 @@ impl TryFrom<u8> for Encoding {
 pub struct ProtocolFlags {
   pub messagepack: bool,
   pub json_fallback: bool,
+  pub structured_query: bool,
+  pub compression: bool,
+  /// Sent by the client to accept a size limit; set by a server that follows
+  /// the session id with its max message size as a big-endian `u32`
+  pub message_size_limit: bool,
+  /// Sent by the client to accept `synced` messages; set by a server that
+  /// sends one after each subscription's initial results
+  pub snapshot_sync: bool,
+}
+
+impl ProtocolFlags {
+  /// Whether these flags advertise the given feature
+  pub fn supports(&self, feature: Feature) -> bool {
+    match feature {
+      Feature::MessagePack => self.messagepack,
+      Feature::JsonFallback => self.json_fallback,
+      Feature::StructuredQuery => self.structured_query,
+      Feature::Compression => self.compression,
+      Feature::MessageSizeLimit => self.message_size_limit,
+      Feature::SnapshotSync => self.snapshot_sync,
+    }
+  }
 }
 
 impl From<u8> for ProtocolFlags {
@@ -86,6 +114,10 @@ impl From<u8> for ProtocolFlags {
     Self {
       messagepack: byte & 0x01 != 0,
       json_fallback: byte & 0x02 != 0,
+      structured_query: byte & 0x04 != 0,
+      compression: byte & 0x08 != 0,
+      message_size_limit: byte & 0x10 != 0,
+      snapshot_sync: byte & 0x20 != 0,
     }
   }
 }
@@ -99,12 +131,35 @@ impl From<ProtocolFlags> for u8 {
     if flags.json_fallback {
       byte |= 0x02;
     }
+    if flags.structured_query {
+      byte |= 0x04;
+    }
+    if flags.compression {
+      byte |= 0x08;
+    }
+    if flags.message_size_limit {
+      byte |= 0x10;
+    }
+    if flags.snapshot_sync {
+      byte |= 0x20;
+    }
     byte
   }
 }
 
+/// Server capabilities negotiated through the handshake flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+  MessagePack,
+  JsonFallback,
+  StructuredQuery,
+  Compression,
+  MessageSizeLimit,
+  SnapshotSync,
+}
+
 /// Client-to-server message types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum ClientMessage {
   Query {
@@ -128,20 +183,332 @@ pub enum ClientMessage {
     collection: String,
     document_id: Uuid,
     data: serde_json::Value,
+    /// Reject the write with a conflict unless the stored document's
+    /// `updated_at` still equals this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected_updated_at: Option<String>,
+  },
+  Patch {
+    id: String,
+    collection: String,
+    document_id: Uuid,
+    data: serde_json::Value,
+    /// Reject the write with a conflict unless the stored document's
+    /// `updated_at` still equals this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected_updated_at: Option<String>,
   },
   Delete {
     id: String,
     collection: String,
     document_id: Uuid,
   },
+  JsonPatch {
+    id: String,
+    collection: String,
+    document_id: Uuid,
+    ops: Vec<PatchOp>,
+  },
+  Modify {
+    id: String,
+    collection: String,
+    document_id: Uuid,
+    update: UpdateSpec,
+  },
+  UpdateMany {
+    id: String,
+    collection: String,
+    filter: Filter,
+    patch: serde_json::Value,
+  },
+  DeleteMany {
+    id: String,
+    collection: String,
+    filter: Filter,
+  },
   ListCollections {
     id: String,
   },
+  CreateCollection {
+    id: String,
+    name: String,
+    options: CreateCollectionOptions,
+  },
+  DropCollection {
+    id: String,
+    name: String,
+  },
+  CreateIndex {
+    id: String,
+    collection: String,
+    field: String,
+    unique: bool,
+  },
+  DropIndex {
+    id: String,
+    collection: String,
+    field: String,
+  },
+  ListIndexes {
+    id: String,
+    collection: String,
+  },
+  Transaction {
+    id: String,
+    ops: Vec<TransactionOp>,
+  },
+  /// Open a server-side cursor over `query`; answered with the first
+  /// [`ServerMessage::CursorBatch`] of up to `batch_size` results
+  OpenCursor {
+    id: String,
+    query: String,
+    batch_size: u32,
+  },
+  /// Fetch the next batch of an open cursor
+  FetchCursor {
+    id: String,
+    cursor_id: String,
+  },
+  /// Release a cursor before it is exhausted
+  CloseCursor {
+    id: String,
+    cursor_id: String,
+  },
   Ping {
     id: String,
   },
 }
 
+impl ClientMessage {
+  /// Correlation id the server echoes in its response
+  pub fn id(&self) -> &str {
+    match self {
+      Self::Query { id, .. }
+      | Self::Subscribe { id, .. }
+      | Self::Unsubscribe { id }
+      | Self::Insert { id, .. }
+      | Self::Update { id, .. }
+      | Self::Patch { id, .. }
+      | Self::JsonPatch { id, .. }
+      | Self::Modify { id, .. }
+      | Self::Delete { id, .. }
+      | Self::UpdateMany { id, .. }
+      | Self::DeleteMany { id, .. }
+      | Self::ListCollections { id }
+      | Self::CreateCollection { id, .. }
+      | Self::DropCollection { id, .. }
+      | Self::CreateIndex { id, .. }
+      | Self::DropIndex { id, .. }
+      | Self::ListIndexes { id, .. }
+      | Self::Transaction { id, .. }
+      | Self::OpenCursor { id, .. }
+      | Self::FetchCursor { id, .. }
+      | Self::CloseCursor { id, .. }
+      | Self::Ping { id } => id,
+    }
+  }
+
+  /// Wire name of the message type, as in the `type` tag
+  pub fn kind(&self) -> &'static str {
+    match self {
+      Self::Query { .. } => "query",
+      Self::Subscribe { .. } => "subscribe",
+      Self::Unsubscribe { .. } => "unsubscribe",
+      Self::Insert { .. } => "insert",
+      Self::Update { .. } => "update",
+      Self::Patch { .. } => "patch",
+      Self::JsonPatch { .. } => "jsonpatch",
+      Self::Modify { .. } => "modify",
+      Self::UpdateMany { .. } => "updatemany",
+      Self::Delete { .. } => "delete",
+      Self::DeleteMany { .. } => "deletemany",
+      Self::ListCollections { .. } => "listcollections",
+      Self::CreateCollection { .. } => "createcollection",
+      Self::DropCollection { .. } => "dropcollection",
+      Self::CreateIndex { .. } => "createindex",
+      Self::DropIndex { .. } => "dropindex",
+      Self::ListIndexes { .. } => "listindexes",
+      Self::Transaction { .. } => "transaction",
+      Self::OpenCursor { .. } => "opencursor",
+      Self::FetchCursor { .. } => "fetchcursor",
+      Self::CloseCursor { .. } => "closecursor",
+      Self::Ping { .. } => "ping",
+    }
+  }
+
+  pub(crate) fn set_id(&mut self, new_id: String) {
+    match self {
+      Self::Query { id, .. }
+      | Self::Subscribe { id, .. }
+      | Self::Unsubscribe { id }
+      | Self::Insert { id, .. }
+      | Self::Update { id, .. }
+      | Self::Patch { id, .. }
+      | Self::JsonPatch { id, .. }
+      | Self::Modify { id, .. }
+      | Self::Delete { id, .. }
+      | Self::UpdateMany { id, .. }
+      | Self::DeleteMany { id, .. }
+      | Self::ListCollections { id }
+      | Self::CreateCollection { id, .. }
+      | Self::DropCollection { id, .. }
+      | Self::CreateIndex { id, .. }
+      | Self::DropIndex { id, .. }
+      | Self::ListIndexes { id, .. }
+      | Self::Transaction { id, .. }
+      | Self::OpenCursor { id, .. }
+      | Self::FetchCursor { id, .. }
+      | Self::CloseCursor { id, .. }
+      | Self::Ping { id } => *id = new_id,
+    }
+  }
+}
+
+/// A write buffered in a transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum TransactionOp {
+  Insert {
+    collection: String,
+    data: serde_json::Value,
+  },
+  Update {
+    collection: String,
+    document_id: Uuid,
+    data: serde_json::Value,
+  },
+  Delete {
+    collection: String,
+    document_id: Uuid,
+  },
+}
+
+/// A JSON Patch (RFC 6902) operation. Paths are JSON Pointers into the
+/// document's `data`, e.g. `/tags/0` or `/address/city`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+  Add { path: String, value: serde_json::Value },
+  Remove { path: String },
+  Replace { path: String, value: serde_json::Value },
+  Move { from: String, path: String },
+  Copy { from: String, path: String },
+  /// Fail the whole patch unless the value at `path` equals `value`
+  Test { path: String, value: serde_json::Value },
+}
+
+/// An atomic server-side change to one field of a document's `data`
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateOp {
+  /// Append `value` to the array at `field`, creating it if missing
+  ArrayPush { field: String, value: serde_json::Value },
+  /// Remove every element equal to `value` from the array at `field`
+  ArrayPull { field: String, value: serde_json::Value },
+  /// Append `value` to the array at `field` unless an equal element is present
+  AddToSet { field: String, value: serde_json::Value },
+  /// Add `by` to the number at `field`, treating a missing field as 0; a
+  /// negative `by` decrements
+  Inc { field: String, by: f64 },
+}
+
+impl UpdateOp {
+  /// Wire operator, field and operand
+  fn into_parts(self) -> (&'static str, String, serde_json::Value) {
+    match self {
+      Self::ArrayPush { field, value } => ("$push", field, value),
+      Self::ArrayPull { field, value } => ("$pull", field, value),
+      Self::AddToSet { field, value } => ("$addToSet", field, value),
+      Self::Inc { field, by } => ("$inc", field, serde_json::Value::from(by)),
+    }
+  }
+}
+
+/// Field operators applied atomically by [`SquirrelDB::modify`](crate::SquirrelDB::modify),
+/// e.g. `UpdateSpec::new().inc("views", 1.0)` for a counter.
+///
+/// Sent grouped by operator, e.g.
+/// `{"$push": {"tags": "new"}, "$pull": {"tags": "old"}}`. Each field takes
+/// one value per operator; a later op on the same field and operator
+/// replaces the earlier one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(into = "UpdateOperators", try_from = "UpdateOperators")]
+pub struct UpdateSpec {
+  ops: Vec<UpdateOp>,
+}
+
+type UpdateOperators = BTreeMap<String, serde_json::Map<String, serde_json::Value>>;
+
+impl UpdateSpec {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add an operation
+  pub fn op(mut self, op: UpdateOp) -> Self {
+    self.ops.push(op);
+    self
+  }
+
+  /// Append `value` to the array at `field`
+  pub fn push(self, field: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+    self.op(UpdateOp::ArrayPush { field: field.into(), value: value.into() })
+  }
+
+  /// Remove every element equal to `value` from the array at `field`
+  pub fn pull(self, field: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+    self.op(UpdateOp::ArrayPull { field: field.into(), value: value.into() })
+  }
+
+  /// Append `value` to the array at `field` if not already present
+  pub fn add_to_set(self, field: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+    self.op(UpdateOp::AddToSet { field: field.into(), value: value.into() })
+  }
+
+  /// Atomically add `by` to the number at `field`
+  pub fn inc(self, field: impl Into<String>, by: f64) -> Self {
+    self.op(UpdateOp::Inc { field: field.into(), by })
+  }
+
+  /// Operations in the order they were added
+  pub fn ops(&self) -> &[UpdateOp] {
+    &self.ops
+  }
+}
+
+impl From<UpdateSpec> for UpdateOperators {
+  fn from(spec: UpdateSpec) -> Self {
+    let mut operators = Self::new();
+    for op in spec.ops {
+      let (operator, field, value) = op.into_parts();
+      operators.entry(operator.to_string()).or_default().insert(field, value);
+    }
+    operators
+  }
+}
+
+impl TryFrom<UpdateOperators> for UpdateSpec {
+  type Error = String;
+
+  fn try_from(operators: UpdateOperators) -> std::result::Result<Self, Self::Error> {
+    let mut spec = Self::new();
+    for (operator, fields) in operators {
+      for (field, value) in fields {
+        spec = spec.op(match operator.as_str() {
+          "$push" => UpdateOp::ArrayPush { field, value },
+          "$pull" => UpdateOp::ArrayPull { field, value },
+          "$addToSet" => UpdateOp::AddToSet { field, value },
+          "$inc" => match value.as_f64() {
+            Some(by) => UpdateOp::Inc { field, by },
+            None => return Err(format!("$inc on {} needs a number", field)),
+          },
+          _ => return Err(format!("unknown update operator {}", operator)),
+        });
+      }
+    }
+    Ok(spec)
+  }
+}
+
 /// Server-to-client message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -150,7 +517,24 @@ pub enum ServerMessage {
   Change { id: String, change: ChangeEvent },
   Subscribed { id: String },
   Unsubscribed { id: String },
-  Error { id: String, error: String },
+  /// Every initial result of subscription `id` has been sent; later changes
+  /// are live
+  Synced { id: String },
+  Error {
+    id: String,
+    error: String,
+    /// Machine-readable error code, when the server provides one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+  },
+  /// A batch of cursor results; once `exhausted` the server has released
+  /// the cursor
+  CursorBatch {
+    id: String,
+    cursor_id: String,
+    documents: Vec<serde_json::Value>,
+    exhausted: bool,
+  },
   Pong { id: String },
 }
 
@@ -173,3 +557,257 @@ pub struct Document {
   pub created_at: String,
   pub updated_at: String,
 }
+
+impl Document {
+  /// Deserialize the top-level field `key` of `data`, `Ok(None)` if it is
+  /// absent or null
+  pub fn get_as<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+    match self.data.get(key) {
+      None | Some(serde_json::Value::Null) => Ok(None),
+      Some(value) => Ok(Some(T::deserialize(value)?)),
+    }
+  }
+
+  /// Deserialize the whole of `data` into `T`
+  pub fn into_typed<T: serde::de::DeserializeOwned>(self) -> Result<T> {
+    Ok(serde_json::from_value(self.data)?)
+  }
+
+  /// `created_at` parsed as an RFC 3339 timestamp, normalised to UTC
+  pub fn created_at_dt(&self) -> std::result::Result<DateTime<Utc>, chrono::ParseError> {
+    parse_timestamp(&self.created_at)
+  }
+
+  /// `updated_at` parsed as an RFC 3339 timestamp, normalised to UTC
+  pub fn updated_at_dt(&self) -> std::result::Result<DateTime<Utc>, chrono::ParseError> {
+    parse_timestamp(&self.updated_at)
+  }
+}
+
+fn parse_timestamp(value: &str) -> std::result::Result<DateTime<Utc>, chrono::ParseError> {
+  DateTime::parse_from_rfc3339(value).map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Collection metadata returned by `ListCollections`
+///
+/// Older servers return bare collection names; those deserialize with only
+/// `name` set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "CollectionRepr")]
+pub struct Collection {
+  pub name: String,
+  pub document_count: Option<u64>,
+  pub created_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CollectionRepr {
+  Name(String),
+  Full {
+    name: String,
+    #[serde(default)]
+    document_count: Option<u64>,
+    #[serde(default)]
+    created_at: Option<String>,
+  },
+}
+
+impl From<CollectionRepr> for Collection {
+  fn from(repr: CollectionRepr) -> Self {
+    match repr {
+      CollectionRepr::Name(name) => Self { name, document_count: None, created_at: None },
+      CollectionRepr::Full { name, document_count, created_at } => Self { name, document_count, created_at },
+    }
+  }
+}
+
+/// One page of query results.
+///
+/// Servers that report pagination info answer with
+/// `{"documents": [...], "total": N}`, where `total` counts every match
+/// ignoring `limit` and `skip`. A bare array deserializes with `total: None`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "QueryPageRepr<T>")]
+pub struct QueryPage<T> {
+  pub items: Vec<T>,
+  pub total: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QueryPageRepr<T> {
+  Items(Vec<T>),
+  Page {
+    documents: Vec<T>,
+    #[serde(default)]
+    total: Option<u64>,
+  },
+}
+
+impl<T> From<QueryPageRepr<T>> for QueryPage<T> {
+  fn from(repr: QueryPageRepr<T>) -> Self {
+    match repr {
+      QueryPageRepr::Items(items) => Self { items, total: None },
+      QueryPageRepr::Page { documents, total } => Self { items: documents, total },
+    }
+  }
+}
+
+/// Index definition on a single field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexSpec {
+  pub field: String,
+  #[serde(default)]
+  pub unique: bool,
+}
+
+/// Index metadata returned by `ListIndexes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexInfo {
+  pub field: String,
+  #[serde(default)]
+  pub unique: bool,
+  /// Index structure reported by the server, e.g. `"btree"` or `"hash"`
+  #[serde(rename = "type")]
+  pub index_type: String,
+}
+
+/// Options for `CreateCollection`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CreateCollectionOptions {
+  /// Indexes to build when the collection is created
+  #[serde(default)]
+  pub indexes: Vec<IndexSpec>,
+}
+
+impl CreateCollectionOptions {
+  /// Add an index on `field`
+  pub fn index(mut self, field: impl Into<String>, unique: bool) -> Self {
+    self.indexes.push(IndexSpec { field: field.into(), unique });
+    self
+  }
+}
+
+/// Size of the frame header: 4-byte big-endian length, message type, encoding
+pub const FRAME_HEADER_SIZE: usize = 6;
+
+/// Bit set on a frame's encoding byte when its payload is zstd-compressed
+pub const COMPRESSED_BIT: u8 = 0x80;
+
+/// Encode a client message as a request frame.
+///
+/// The frame is a big-endian `u32` length (covering the type byte, encoding
+/// byte and payload), followed by `MessageType::Request`, the encoding and
+/// the MessagePack or JSON payload. Frames whose length exceeds
+/// `MAX_MESSAGE_SIZE` fail with `Error::MessageTooLarge`.
+pub fn encode_frame(msg: &ClientMessage, encoding: Encoding) -> Result<Vec<u8>> {
+  let payload = encode_payload(msg, encoding)?;
+
+  let length = payload.len() + 2;
+  if length > MAX_MESSAGE_SIZE as usize {
+    return Err(Error::MessageTooLarge { size: length, max: MAX_MESSAGE_SIZE as usize });
+  }
+
+  let mut frame = Vec::with_capacity(length + 4);
+  frame.extend_from_slice(&(length as u32).to_be_bytes());
+  frame.push(MessageType::Request as u8);
+  frame.push(encoding as u8);
+  frame.extend_from_slice(&payload);
+  Ok(frame)
+}
+
+/// The frame length `msg` would be sent with, to compare against
+/// `MAX_MESSAGE_SIZE` before sending, e.g. to split a large batch
+pub fn encoded_size(msg: &ClientMessage, encoding: Encoding) -> Result<usize> {
+  Ok(encode_payload(msg, encoding)?.len() + 2)
+}
+
+fn encode_payload(msg: &ClientMessage, encoding: Encoding) -> Result<Vec<u8>> {
+  Ok(match encoding {
+    Encoding::MessagePack => rmp_serde::to_vec(msg)?,
+    Encoding::Json => serde_json::to_vec(msg)?,
+  })
+}
+
+/// Compress the payload of an encoded frame with zstd.
+///
+/// Payloads shorter than `threshold` bytes, or that do not shrink, are left
+/// as they are. Compressed frames carry [`COMPRESSED_BIT`] on the encoding
+/// byte. Only send these once the server has set the compression flag.
+#[cfg(feature = "compression")]
+pub fn compress_frame(frame: Vec<u8>, threshold: usize) -> Result<Vec<u8>> {
+  let payload = &frame[FRAME_HEADER_SIZE..];
+  if payload.len() < threshold {
+    return Ok(frame);
+  }
+
+  let compressed = zstd::bulk::compress(payload, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+  if compressed.len() >= payload.len() {
+    return Ok(frame);
+  }
+
+  let mut out = Vec::with_capacity(FRAME_HEADER_SIZE + compressed.len());
+  out.extend_from_slice(&((compressed.len() + 2) as u32).to_be_bytes());
+  out.push(frame[4]);
+  out.push(frame[5] | COMPRESSED_BIT);
+  out.extend_from_slice(&compressed);
+  Ok(out)
+}
+
+/// Decode one server frame from the start of `buf`.
+///
+/// Returns the message and the number of bytes consumed. The payload is
+/// decoded with the encoding named in the frame header. A buffer holding less
+/// than a full frame is an `Error::Protocol`.
+pub fn decode_frame(buf: &[u8]) -> Result<(ServerMessage, usize)> {
+  if buf.len() < FRAME_HEADER_SIZE {
+    return Err(Error::Protocol(format!("Incomplete frame header: {} bytes", buf.len())));
+  }
+
+  let length = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+  if length > MAX_MESSAGE_SIZE {
+    return Err(Error::Protocol(format!("Message too large: {} bytes (max {})", length, MAX_MESSAGE_SIZE)));
+  }
+  if length < 2 {
+    return Err(Error::Protocol(format!("Invalid frame length: {}", length)));
+  }
+
+  let total = 4 + length as usize;
+  if buf.len() < total {
+    return Err(Error::Protocol(format!("Incomplete frame: need {} bytes, have {}", total, buf.len())));
+  }
+
+  MessageType::try_from(buf[4]).map_err(|_| Error::Protocol(format!("Invalid message type: {}", buf[4])))?;
+  let encoding = Encoding::try_from(buf[5] & !COMPRESSED_BIT)
+    .map_err(|_| Error::Protocol(format!("Invalid encoding: {}", buf[5])))?;
+
+  let payload = &buf[FRAME_HEADER_SIZE..total];
+  let msg = if buf[5] & COMPRESSED_BIT != 0 {
+    decode_payload(&decompress_payload(payload)?, encoding)?
+  } else {
+    decode_payload(payload, encoding)?
+  };
+  Ok((msg, total))
+}
+
+/// Inflate a zstd-compressed frame payload, capped at `MAX_MESSAGE_SIZE`
+#[cfg(feature = "compression")]
+pub(crate) fn decompress_payload(payload: &[u8]) -> Result<Vec<u8>> {
+  zstd::bulk::decompress(payload, MAX_MESSAGE_SIZE as usize)
+    .map_err(|e| Error::Protocol(format!("Invalid compressed payload: {}", e)))
+}
+
+#[cfg(not(feature = "compression"))]
+pub(crate) fn decompress_payload(_payload: &[u8]) -> Result<Vec<u8>> {
+  Err(Error::Protocol(
+    "Received a compressed frame but the `compression` feature is disabled".to_string(),
+  ))
+}
+
+pub(crate) fn decode_payload(payload: &[u8], encoding: Encoding) -> Result<ServerMessage> {
+  Ok(match encoding {
+    Encoding::MessagePack => rmp_serde::from_slice(payload)?,
+    Encoding::Json => serde_json::from_slice(payload)?,
+  })
+}
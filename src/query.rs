@@ -1,24 +1,58 @@
 //! SquirrelDB Rust SDK - Query Builder
 //! Generated by sdk-generator v0.1.0
 //! DO NOT EDIT MANUALLY
+//!
+//! This is the SDK's single query builder. Start from [`table`], add
+//! conditions built with [`field`], then either run the builder directly or
+//! compile it with [`QueryBuilder::compile_structured`] (typed) or
+//! [`QueryBuilder::compile`] (JSON string). Comparison operators take any
+//! value convertible to JSON, so integers, floats and strings all work.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Filter operators the server understands
+pub const OPERATORS: &[&str] = &[
+    "$eq", "$ne", "$gt", "$gte", "$lt", "$lte", "$in", "$nin", "$contains", "$startsWith",
+    "$endsWith", "$size", "$elemMatch", "$exists", "$mod", "$near", "$withinBox", "$and", "$or",
+    "$not",
+];
+
+/// Structured filter: field name to an object of operator to operand. The
+/// combinators `$and` and `$or` hold arrays of nested filters, `$not` a
+/// single one. Top-level entries must all match. Keys serialize in a fixed
+/// order, so compiling the same builder always yields the same string.
+pub type Filter = serde_json::Map<String, serde_json::Value>;
+
+/// A query rejected before being sent
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum QueryError {
+    #[error("Table name is empty")]
+    EmptyTable,
+    #[error("Limit must be positive, got {0}")]
+    InvalidLimit(i64),
+    #[error("Skip without sort returns an arbitrary page")]
+    SkipWithoutSort,
+    #[error("Empty $in list on field {0} never matches")]
+    EmptyIn(String),
+    #[error("$mod on field {0} needs [divisor, remainder] with a non-zero divisor")]
+    InvalidModulo(String),
+    #[error("{operator} on field {field} needs latitudes in [-90, 90], longitudes in [-180, 180] and a non-negative radius")]
+    InvalidGeo { field: String, operator: String },
+    #[error("Unknown operator {operator} on field {field}")]
+    UnknownOperator { field: String, operator: String },
+}
 
 /// Sort direction
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SortDirection {
+    #[default]
     Asc,
     Desc,
 }
 
-impl Default for SortDirection {
-    fn default() -> Self {
-        Self::Asc
-    }
-}
-
 /// A single filter condition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterCondition {
@@ -38,6 +72,12 @@ pub struct SortSpec {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChangesOptions {
+    /// Send the current matching documents before live changes.
+    ///
+    /// The two defaults differ on purpose: `ChangesOptions::default()` leaves
+    /// this off so existing callers keep live-only feeds, while a serialized
+    /// object that omits the field means on, as it does for the server.
+    /// [`QueryBuilder::changes`] with `None` also turns it on.
     #[serde(default = "default_true")]
     pub include_initial: bool,
 }
@@ -46,12 +86,35 @@ fn default_true() -> bool {
     true
 }
 
+/// Aggregation accumulator applied to each group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Accumulator {
+    #[serde(rename = "$count")]
+    Count,
+    #[serde(rename = "$sum")]
+    Sum(String),
+    #[serde(rename = "$avg")]
+    Avg(String),
+    #[serde(rename = "$min")]
+    Min(String),
+    #[serde(rename = "$max")]
+    Max(String),
+}
+
+/// Group-by specification; `by: None` aggregates all matching documents into one row
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupSpec {
+    pub by: Option<String>,
+    /// Output field name to accumulator
+    pub accumulators: BTreeMap<String, Accumulator>,
+}
+
 /// Structured query object (wire format)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StructuredQuery {
     pub table: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub filter: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
+    pub filter: Option<Filter>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort: Option<Vec<SortSpec>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -60,6 +123,109 @@ pub struct StructuredQuery {
     pub skip: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub changes: Option<ChangesOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distinct: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<GroupSpec>,
+    /// Output field name to server-evaluated expression, see [`QueryBuilder::compute`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub computed: Option<BTreeMap<String, String>>,
+}
+
+impl StructuredQuery {
+    /// Catch mistakes the server would silently accept: an empty table name,
+    /// a non-positive limit, skip without sort, an empty `$in` list, a
+    /// malformed `$mod`, out-of-range geo coordinates or an unknown filter
+    /// operator
+    pub fn validate(&self) -> Result<(), QueryError> {
+        self.check(true)
+    }
+
+    /// `validate`, optionally leaving operators outside [`OPERATORS`] for
+    /// the server to judge
+    fn check(&self, known_operators_only: bool) -> Result<(), QueryError> {
+        if self.table.trim().is_empty() {
+            return Err(QueryError::EmptyTable);
+        }
+        if let Some(limit) = self.limit.filter(|n| *n <= 0) {
+            return Err(QueryError::InvalidLimit(limit));
+        }
+        if self.skip.is_some() && self.sort.as_ref().is_none_or(|s| s.is_empty()) {
+            return Err(QueryError::SkipWithoutSort);
+        }
+        match &self.filter {
+            Some(filter) => validate_filter(filter, known_operators_only),
+            None => Ok(()),
+        }
+    }
+}
+
+fn validate_filter(filter: &Filter, known_operators_only: bool) -> Result<(), QueryError> {
+    for (field, operand) in filter {
+        match (field.as_str(), operand) {
+            ("$and" | "$or", serde_json::Value::Array(nested)) => {
+                for nested in nested.iter().filter_map(serde_json::Value::as_object) {
+                    validate_filter(nested, known_operators_only)?;
+                }
+            }
+            ("$not", serde_json::Value::Object(nested)) => validate_filter(nested, known_operators_only)?,
+            (_, serde_json::Value::Object(ops)) => validate_ops(field, ops, known_operators_only)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn validate_ops(
+    field: &str,
+    ops: &serde_json::Map<String, serde_json::Value>,
+    known_operators_only: bool,
+) -> Result<(), QueryError> {
+    if matches!(ops.get("$in"), Some(serde_json::Value::Array(values)) if values.is_empty()) {
+        return Err(QueryError::EmptyIn(field.to_string()));
+    }
+    if let Some(operand) = ops.get("$mod") {
+        let valid = matches!(operand.as_array().map(Vec::as_slice), Some([d, r])
+            if d.as_f64().is_some_and(|d| d != 0.0) && r.is_number());
+        if !valid {
+            return Err(QueryError::InvalidModulo(field.to_string()));
+        }
+    }
+    for operator in ["$near", "$withinBox"] {
+        if ops.get(operator).is_some_and(|operand| !valid_geo(operand)) {
+            return Err(QueryError::InvalidGeo {
+                field: field.to_string(),
+                operator: operator.to_string(),
+            });
+        }
+    }
+    if !known_operators_only {
+        return Ok(());
+    }
+    if let Some(operator) = ops.keys().find(|op| !OPERATORS.contains(&op.as_str())) {
+        return Err(QueryError::UnknownOperator {
+            field: field.to_string(),
+            operator: operator.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Whether a `$near` or `$withinBox` operand has in-range coordinates
+fn valid_geo(operand: &serde_json::Value) -> bool {
+    let number = |key: &str| operand.get(key).and_then(serde_json::Value::as_f64);
+    let lat = |key: &str| number(key).is_some_and(|v| (-90.0..=90.0).contains(&v));
+    let lng = |key: &str| number(key).is_some_and(|v| (-180.0..=180.0).contains(&v));
+
+    if operand.get("radius").is_some() {
+        lat("lat") && lng("lng") && number("radius").is_some_and(|r| r >= 0.0)
+    } else {
+        lat("minLat")
+            && lng("minLng")
+            && lat("maxLat")
+            && lng("maxLng")
+            && number("minLat") <= number("maxLat")
+    }
 }
 
 /// Field expression for building filter conditions
@@ -94,7 +260,8 @@ impl FieldExpr {
         }
     }
 
-    /// Greater than
+    /// Greater than. Accepts any JSON value; strings such as ISO dates are
+    /// compared lexicographically by the server.
     pub fn gt(self, value: impl Into<serde_json::Value>) -> FilterCondition {
         FilterCondition {
             field: self.field_name,
@@ -103,7 +270,8 @@ impl FieldExpr {
         }
     }
 
-    /// Greater than or equal to
+    /// Greater than or equal to. Accepts any JSON value; strings such as ISO
+    /// dates are compared lexicographically by the server.
     pub fn gte(self, value: impl Into<serde_json::Value>) -> FilterCondition {
         FilterCondition {
             field: self.field_name,
@@ -112,7 +280,8 @@ impl FieldExpr {
         }
     }
 
-    /// Less than
+    /// Less than. Accepts any JSON value; strings such as ISO dates are
+    /// compared lexicographically by the server.
     pub fn lt(self, value: impl Into<serde_json::Value>) -> FilterCondition {
         FilterCondition {
             field: self.field_name,
@@ -121,7 +290,8 @@ impl FieldExpr {
         }
     }
 
-    /// Less than or equal to
+    /// Less than or equal to. Accepts any JSON value; strings such as ISO
+    /// dates are compared lexicographically by the server.
     pub fn lte(self, value: impl Into<serde_json::Value>) -> FilterCondition {
         FilterCondition {
             field: self.field_name,
@@ -148,7 +318,8 @@ impl FieldExpr {
         }
     }
 
-    /// String contains substring
+    /// String contains substring. The server evaluates this as a string
+    /// match; use [`Self::array_contains`] for array fields.
     pub fn contains(self, value: impl Into<String>) -> FilterCondition {
         FilterCondition {
             field: self.field_name,
@@ -175,6 +346,98 @@ impl FieldExpr {
         }
     }
 
+    /// Case-insensitive [`Self::contains`]. The operand is sent as
+    /// `{"$contains": {"value": "x", "ci": true}}`; the `_ci` variants of the
+    /// other string operators use the same shape.
+    pub fn contains_ci(self, value: impl Into<String>) -> FilterCondition {
+        self.case_insensitive("$contains", value.into())
+    }
+
+    /// Case-insensitive [`Self::starts_with`]
+    pub fn starts_with_ci(self, value: impl Into<String>) -> FilterCondition {
+        self.case_insensitive("$startsWith", value.into())
+    }
+
+    /// Case-insensitive [`Self::ends_with`]
+    pub fn ends_with_ci(self, value: impl Into<String>) -> FilterCondition {
+        self.case_insensitive("$endsWith", value.into())
+    }
+
+    fn case_insensitive(self, operator: &str, value: String) -> FilterCondition {
+        FilterCondition {
+            field: self.field_name,
+            operator: operator.to_string(),
+            value: serde_json::json!({"value": value, "ci": true}),
+        }
+    }
+
+    /// Array has exactly `n` elements
+    pub fn size(self, n: usize) -> FilterCondition {
+        FilterCondition {
+            field: self.field_name,
+            operator: "$size".to_string(),
+            value: serde_json::Value::from(n),
+        }
+    }
+
+    /// Array has an element equal to `value`
+    pub fn array_contains(self, value: impl Into<serde_json::Value>) -> FilterCondition {
+        FilterCondition {
+            field: self.field_name,
+            operator: "$elemMatch".to_string(),
+            value: value.into(),
+        }
+    }
+
+    /// Field modulo `divisor` equals `remainder`, e.g. `modulo(10.0, 0.0)`
+    /// for a deterministic 10% sample on a hash field
+    pub fn modulo(self, divisor: f64, remainder: f64) -> FilterCondition {
+        FilterCondition {
+            field: self.field_name,
+            operator: "$mod".to_string(),
+            value: serde_json::json!([divisor, remainder]),
+        }
+    }
+
+    /// Location within `radius_meters` of a point. The field holds an object
+    /// `{"lat": .., "lng": ..}` in decimal degrees (WGS84), latitude first;
+    /// the server evaluates the distance against a geo index. Sent as
+    /// `{"$near": {"lat": .., "lng": .., "radius": ..}}`.
+    pub fn near(self, lat: f64, lng: f64, radius_meters: f64) -> FilterCondition {
+        FilterCondition {
+            field: self.field_name,
+            operator: "$near".to_string(),
+            value: serde_json::json!({"lat": lat, "lng": lng, "radius": radius_meters}),
+        }
+    }
+
+    /// Location inside the box from the south-west corner
+    /// (`min_lat`, `min_lng`) to the north-east corner (`max_lat`, `max_lng`),
+    /// in decimal degrees, latitude first as in [`Self::near`]. Sent as
+    /// `{"$withinBox": {"minLat": .., "minLng": .., "maxLat": .., "maxLng": ..}}`.
+    pub fn within_box(self, min_lat: f64, min_lng: f64, max_lat: f64, max_lng: f64) -> FilterCondition {
+        FilterCondition {
+            field: self.field_name,
+            operator: "$withinBox".to_string(),
+            value: serde_json::json!({
+                "minLat": min_lat,
+                "minLng": min_lng,
+                "maxLat": max_lat,
+                "maxLng": max_lng,
+            }),
+        }
+    }
+
+    /// Any operator, emitted verbatim as `{field: {op: value}}`, for server
+    /// operators the builder doesn't model yet. `op` includes its `$`.
+    pub fn raw_op(self, op: &str, value: impl Into<serde_json::Value>) -> FilterCondition {
+        FilterCondition {
+            field: self.field_name,
+            operator: op.to_string(),
+            value: value.into(),
+        }
+    }
+
     /// Field exists (or not)
     pub fn exists(self, value: bool) -> FilterCondition {
         FilterCondition {
@@ -199,6 +462,9 @@ pub struct QueryBuilder {
     limit_value: Option<i64>,
     skip_value: Option<i64>,
     changes_opts: Option<ChangesOptions>,
+    distinct_field: Option<String>,
+    group: Option<GroupSpec>,
+    computed: BTreeMap<String, String>,
 }
 
 impl QueryBuilder {
@@ -222,7 +488,8 @@ impl QueryBuilder {
         self
     }
 
-    /// Add a sort specification
+    /// Add a sort specification. Repeated calls build a compound sort,
+    /// earlier fields taking precedence.
     pub fn sort(mut self, field_name: impl Into<String>, direction: SortDirection) -> Self {
         self.sorts.push(SortSpec {
             field: field_name.into(),
@@ -243,7 +510,7 @@ impl QueryBuilder {
         self
     }
 
-    /// Subscribe to changes
+    /// Subscribe to changes; `None` includes the initial result set
     pub fn changes(mut self, opts: Option<ChangesOptions>) -> Self {
         self.changes_opts = Some(opts.unwrap_or(ChangesOptions {
             include_initial: true,
@@ -251,6 +518,82 @@ impl QueryBuilder {
         self
     }
 
+    /// Subscribe to changes, choosing whether the current matching documents
+    /// are sent first
+    pub fn with_initial(self, include_initial: bool) -> Self {
+        self.changes(Some(ChangesOptions { include_initial }))
+    }
+
+    /// Return the unique values of a field instead of documents
+    pub fn distinct(mut self, field_name: impl Into<String>) -> Self {
+        self.distinct_field = Some(field_name.into());
+        self
+    }
+
+    /// Add a field computed by the server to every returned document.
+    ///
+    /// `expression` is a JavaScript expression, the same language as string
+    /// queries, evaluated once per matching document with the document's
+    /// data bound to `doc`, e.g. `compute("fullName", "doc.first + ' ' + doc.last")`.
+    /// The SDK passes it through unchecked; a bad expression is reported by
+    /// the server. Computing a name that already exists replaces the stored
+    /// value in the result only.
+    pub fn compute(mut self, name: impl Into<String>, expression: impl Into<String>) -> Self {
+        self.computed.insert(name.into(), expression.into());
+        self
+    }
+
+    /// Group matching documents by a field
+    pub fn group_by(mut self, field_name: impl Into<String>) -> Self {
+        self.group.get_or_insert_with(GroupSpec::default).by = Some(field_name.into());
+        self
+    }
+
+    /// Count documents per group, output as `count`
+    pub fn count(self) -> Self {
+        self.accumulate("count".to_string(), Accumulator::Count)
+    }
+
+    /// Sum a field per group, output as `sum_<field>`
+    pub fn sum(self, field_name: impl Into<String>) -> Self {
+        let field_name = field_name.into();
+        self.accumulate(format!("sum_{}", field_name), Accumulator::Sum(field_name))
+    }
+
+    /// Average a field per group, output as `avg_<field>`
+    pub fn avg(self, field_name: impl Into<String>) -> Self {
+        let field_name = field_name.into();
+        self.accumulate(format!("avg_{}", field_name), Accumulator::Avg(field_name))
+    }
+
+    /// Minimum of a field per group, output as `min_<field>`
+    pub fn min(self, field_name: impl Into<String>) -> Self {
+        let field_name = field_name.into();
+        self.accumulate(format!("min_{}", field_name), Accumulator::Min(field_name))
+    }
+
+    /// Maximum of a field per group, output as `max_<field>`
+    pub fn max(self, field_name: impl Into<String>) -> Self {
+        let field_name = field_name.into();
+        self.accumulate(format!("max_{}", field_name), Accumulator::Max(field_name))
+    }
+
+    fn accumulate(mut self, name: String, accumulator: Accumulator) -> Self {
+        self.group
+            .get_or_insert_with(GroupSpec::default)
+            .accumulators
+            .insert(name, accumulator);
+        self
+    }
+
+    /// Check the query for obvious mistakes; see [`StructuredQuery::validate`].
+    /// Operators outside [`OPERATORS`] can only come from
+    /// [`FieldExpr::raw_op`] or hand-built conditions, so they are passed
+    /// through for the server to judge.
+    pub fn validate(&self) -> Result<(), QueryError> {
+        self.clone().compile_structured().check(false)
+    }
+
     /// Compile to structured query object
     pub fn compile_structured(self) -> StructuredQuery {
         let filter = if self.filters.is_empty() {
@@ -272,6 +615,9 @@ impl QueryBuilder {
             limit: self.limit_value,
             skip: self.skip_value,
             changes: self.changes_opts,
+            distinct: self.distinct_field,
+            group: self.group,
+            computed: (!self.computed.is_empty()).then_some(self.computed),
         }
     }
 
@@ -280,13 +626,39 @@ impl QueryBuilder {
         serde_json::to_string(&self.compile_structured())
     }
 
-    fn build_filter_object(&self) -> HashMap<String, HashMap<String, serde_json::Value>> {
-        let mut filter_obj: HashMap<String, HashMap<String, serde_json::Value>> = HashMap::new();
+    /// Merge conditions into one filter object. A condition that would
+    /// overwrite an earlier one (the same field and operator, or a repeated
+    /// combinator) goes into a top-level `$and` array instead, so every
+    /// condition still applies.
+    fn build_filter_object(&self) -> Filter {
+        let mut filter_obj = Filter::new();
+        let mut overflow = Vec::new();
         for cond in &self.filters {
-            filter_obj
+            if is_combinator(cond) {
+                if filter_obj.contains_key(&cond.operator) {
+                    overflow.push(condition_filter(cond));
+                } else {
+                    filter_obj.insert(cond.operator.clone(), cond.value.clone());
+                }
+                continue;
+            }
+            let ops = filter_obj
                 .entry(cond.field.clone())
-                .or_default()
-                .insert(cond.operator.clone(), cond.value.clone());
+                .or_insert_with(|| serde_json::Value::Object(Default::default()));
+            match ops {
+                serde_json::Value::Object(ops) if !ops.contains_key(&cond.operator) => {
+                    ops.insert(cond.operator.clone(), cond.value.clone());
+                }
+                _ => overflow.push(condition_filter(cond)),
+            }
+        }
+        if !overflow.is_empty() {
+            match filter_obj.get_mut("$and") {
+                Some(serde_json::Value::Array(all)) => all.extend(overflow),
+                _ => {
+                    filter_obj.insert("$and".to_string(), serde_json::Value::Array(overflow));
+                }
+            }
         }
         filter_obj
     }
@@ -297,29 +669,40 @@ pub fn table(name: impl Into<String>) -> QueryBuilder {
     QueryBuilder::new(name)
 }
 
-/// Combine conditions with AND
+/// Combine conditions with AND; the value is an array of one-condition filters
 pub fn and(conditions: Vec<FilterCondition>) -> FilterCondition {
-    FilterCondition {
-        field: "$and".to_string(),
-        operator: "$and".to_string(),
-        value: serde_json::to_value(conditions).unwrap_or(serde_json::Value::Array(vec![])),
-    }
+    combinator("$and", serde_json::Value::Array(conditions.iter().map(condition_filter).collect()))
 }
 
-/// Combine conditions with OR
+/// Combine conditions with OR, e.g. `or(vec![field("status").eq("a"),
+/// field("status").eq("b")])` becomes
+/// `{"$or": [{"status": {"$eq": "a"}}, {"status": {"$eq": "b"}}]}`
 pub fn or(conditions: Vec<FilterCondition>) -> FilterCondition {
-    FilterCondition {
-        field: "$or".to_string(),
-        operator: "$or".to_string(),
-        value: serde_json::to_value(conditions).unwrap_or(serde_json::Value::Array(vec![])),
-    }
+    combinator("$or", serde_json::Value::Array(conditions.iter().map(condition_filter).collect()))
 }
 
 /// Negate a condition
 pub fn not(condition: FilterCondition) -> FilterCondition {
+    combinator("$not", condition_filter(&condition))
+}
+
+fn combinator(operator: &str, value: serde_json::Value) -> FilterCondition {
     FilterCondition {
-        field: "$not".to_string(),
-        operator: "$not".to_string(),
-        value: serde_json::to_value(condition).unwrap_or(serde_json::Value::Null),
+        field: operator.to_string(),
+        operator: operator.to_string(),
+        value,
+    }
+}
+
+fn is_combinator(cond: &FilterCondition) -> bool {
+    matches!(cond.operator.as_str(), "$and" | "$or" | "$not") && cond.field == cond.operator
+}
+
+/// A condition as a filter object of its own
+fn condition_filter(cond: &FilterCondition) -> serde_json::Value {
+    if is_combinator(cond) {
+        serde_json::json!({ cond.operator.as_str(): cond.value })
+    } else {
+        serde_json::json!({ cond.field.as_str(): { cond.operator.as_str(): cond.value } })
     }
 }
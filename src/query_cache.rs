@@ -0,0 +1,157 @@
+//! Client-side memoization of query results, see
+//! [`ConnectOptions::with_query_cache`](crate::ConnectOptions::with_query_cache)
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::protocol::{ClientMessage, TransactionOp};
+
+/// LRU cache of raw query results keyed by the query text
+#[derive(Debug)]
+pub(crate) struct QueryCache {
+    ttl: Duration,
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, Entry>,
+    /// Monotonic use counter; the entry with the lowest `last_used` is evicted first
+    clock: u64,
+    /// Bumped on every invalidation, so a result fetched across one is not stored
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    data: serde_json::Value,
+    /// Table the query reads, if it could be determined
+    table: Option<String>,
+    stored: Instant,
+    last_used: u64,
+}
+
+impl QueryCache {
+    pub(crate) fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn get(&self, query: &str) -> Option<serde_json::Value> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+
+        let entry = state.entries.get_mut(query)?;
+        if entry.stored.elapsed() >= self.ttl {
+            state.entries.remove(query);
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.data.clone())
+    }
+
+    /// Current invalidation generation, to pass to [`insert`](Self::insert)
+    pub(crate) fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    /// Store a result fetched after `generation` was read. It is dropped if
+    /// a write invalidated the cache meanwhile, since the result may predate
+    /// that write.
+    pub(crate) fn insert(&self, query: &str, data: &serde_json::Value, generation: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.generation != generation {
+            return;
+        }
+        state.clock += 1;
+        let clock = state.clock;
+
+        if !state.entries.contains_key(query) && state.entries.len() >= self.capacity {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.entries.insert(
+            query.to_string(),
+            Entry {
+                data: data.clone(),
+                table: query_table(query),
+                stored: Instant::now(),
+                last_used: clock,
+            },
+        );
+    }
+
+    /// Drop every entry that may read a collection `msg` writes to, and
+    /// return those collections. Entries whose table is unknown are dropped
+    /// on any write.
+    pub(crate) fn invalidate_for(&self, msg: &ClientMessage) -> Vec<String> {
+        let written: Vec<String> = written_collections(msg).into_iter().map(str::to_string).collect();
+        self.invalidate_tables(&written);
+        written
+    }
+
+    pub(crate) fn invalidate_tables(&self, written: &[String]) {
+        if written.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state
+            .entries
+            .retain(|_, entry| entry.table.as_ref().is_some_and(|t| !written.contains(t)));
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.entries.clear();
+    }
+}
+
+/// The table a structured (JSON) or `db.table("...")` query reads from
+fn query_table(query: &str) -> Option<String> {
+    if let Ok(serde_json::Value::Object(obj)) = serde_json::from_str(query) {
+        return obj.get("table")?.as_str().map(str::to_string);
+    }
+    let start = query.find("table(\"")? + "table(\"".len();
+    let len = query[start..].find('"')?;
+    Some(query[start..start + len].to_string())
+}
+
+fn written_collections(msg: &ClientMessage) -> Vec<&str> {
+    match msg {
+        ClientMessage::Insert { collection, .. }
+        | ClientMessage::Update { collection, .. }
+        | ClientMessage::Patch { collection, .. }
+        | ClientMessage::Delete { collection, .. }
+        | ClientMessage::JsonPatch { collection, .. }
+        | ClientMessage::Modify { collection, .. }
+        | ClientMessage::UpdateMany { collection, .. }
+        | ClientMessage::DeleteMany { collection, .. } => vec![collection.as_str()],
+        ClientMessage::DropCollection { name, .. } => vec![name.as_str()],
+        ClientMessage::Transaction { ops, .. } => ops
+            .iter()
+            .map(|op| match op {
+                TransactionOp::Insert { collection, .. }
+                | TransactionOp::Update { collection, .. }
+                | TransactionOp::Delete { collection, .. } => collection.as_str(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
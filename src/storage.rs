@@ -2,11 +2,15 @@
 // Generated by sdk-generator v0.1.0
 // DO NOT EDIT MANUALLY
 
+use base64::Engine;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
+use futures::Stream;
 use hmac::{Hmac, Mac};
+use md5::Md5;
 use reqwest::{Client, StatusCode};
 use sha2::{Digest, Sha256};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -25,6 +29,8 @@ pub struct StorageObject {
     pub etag: String,
     pub last_modified: DateTime<Utc>,
     pub content_type: Option<String>,
+    /// User metadata (`x-amz-meta-*`); only populated by `head_object`
+    pub metadata: HashMap<String, String>,
 }
 
 /// Storage client options
@@ -33,6 +39,9 @@ pub struct StorageOptions {
     pub access_key: Option<String>,
     pub secret_key: Option<String>,
     pub region: String,
+    /// Verify uploads and full downloads against SHA-256/MD5 checksums,
+    /// failing with `StorageError::ChecksumMismatch`
+    pub verify_checksum: bool,
 }
 
 impl Default for StorageOptions {
@@ -42,15 +51,103 @@ impl Default for StorageOptions {
             access_key: None,
             secret_key: None,
             region: "us-east-1".to_string(),
+            verify_checksum: false,
         }
     }
 }
 
+/// Options for [`StorageClient::put_object_with`]
+#[derive(Debug, Clone, Default)]
+pub struct PutOptions {
+    /// MIME type served back to readers; defaults to `application/octet-stream`
+    pub content_type: Option<String>,
+    /// User metadata, stored as `x-amz-meta-*` headers
+    pub metadata: HashMap<String, String>,
+    pub cache_control: Option<String>,
+    /// Expected hex SHA-256 of the data; the upload is refused if it differs
+    pub sha256: Option<String>,
+}
+
+/// Options for [`StorageClient::list_objects_with`]
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub prefix: Option<String>,
+    /// Group keys sharing a prefix up to this delimiter (usually `/`) into
+    /// `common_prefixes` instead of listing them
+    pub delimiter: Option<String>,
+    pub max_keys: Option<u32>,
+    pub continuation_token: Option<String>,
+}
+
+/// One page of an object listing
+#[derive(Debug, Clone, Default)]
+pub struct ObjectListing {
+    pub objects: Vec<StorageObject>,
+    pub common_prefixes: Vec<String>,
+    /// Set when more results are available
+    pub next_continuation_token: Option<String>,
+}
+
 /// Storage error type
 #[derive(Debug)]
 pub enum StorageError {
+    /// Transport failure (connect, timeout, interrupted body)
     Http(reqwest::Error),
+    /// Unexpected status without a recognised S3 error body
     Status(StatusCode),
+    NotFound,
+    BucketNotFound,
+    AccessDenied,
+    PreconditionFailed,
+    /// S3 error response with a code not covered by a dedicated variant
+    Server { code: String, message: String },
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+impl StorageError {
+    /// Whether retrying the same request may succeed: transport failures,
+    /// throttling and 5xx responses
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            StorageError::Http(e) => e.is_timeout() || e.is_connect() || e.is_body(),
+            StorageError::Status(s) => s.is_server_error() || *s == StatusCode::TOO_MANY_REQUESTS,
+            StorageError::Server { code, .. } => matches!(
+                code.as_str(),
+                "InternalError" | "ServiceUnavailable" | "SlowDown" | "RequestTimeout"
+            ),
+            _ => false,
+        }
+    }
+
+    /// Map a failed response to an error, using the S3 `<Code>` in the body
+    /// when present and the status otherwise
+    async fn from_response(resp: reqwest::Response) -> Self {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        let tag = |name: &str| {
+            regex::Regex::new(&format!("<{0}>([^<]*)</{0}>", name))
+                .unwrap()
+                .captures(&body)
+                .map(|cap| xml_unescape(&cap[1]))
+        };
+
+        match tag("Code").as_deref() {
+            Some("NoSuchKey") => StorageError::NotFound,
+            Some("NoSuchBucket") => StorageError::BucketNotFound,
+            Some("AccessDenied") => StorageError::AccessDenied,
+            Some("PreconditionFailed") => StorageError::PreconditionFailed,
+            Some(code) => StorageError::Server {
+                code: code.to_string(),
+                message: tag("Message").unwrap_or_default(),
+            },
+            None => match status {
+                StatusCode::NOT_FOUND => StorageError::NotFound,
+                StatusCode::FORBIDDEN => StorageError::AccessDenied,
+                StatusCode::PRECONDITION_FAILED => StorageError::PreconditionFailed,
+                _ => StorageError::Status(status),
+            },
+        }
+    }
 }
 
 impl std::fmt::Display for StorageError {
@@ -58,11 +155,110 @@ impl std::fmt::Display for StorageError {
         match self {
             StorageError::Http(e) => write!(f, "HTTP error: {}", e),
             StorageError::Status(s) => write!(f, "HTTP status: {}", s),
+            StorageError::NotFound => write!(f, "Object not found"),
+            StorageError::BucketNotFound => write!(f, "Bucket not found"),
+            StorageError::AccessDenied => write!(f, "Access denied"),
+            StorageError::PreconditionFailed => write!(f, "Precondition failed"),
+            StorageError::Server { code, message } => write!(f, "Server error {}: {}", code, message),
+            StorageError::ChecksumMismatch { expected, actual } => {
+                write!(f, "Checksum mismatch: expected {}, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A part of a multipart upload that the server has accepted
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadPart {
+    pub part_number: u32,
+    pub etag: String,
+    pub size: u64,
+}
+
+/// State of an in-progress multipart upload.
+///
+/// Persist `bucket`, `key` and `upload_id` to pick the upload back up with
+/// [`StorageClient::resume_multipart`] after a crash.
+#[derive(Debug, Clone)]
+pub struct MultipartUpload {
+    pub bucket: String,
+    pub key: String,
+    pub upload_id: String,
+    parts: Vec<UploadPart>,
+}
+
+impl MultipartUpload {
+    /// Parts uploaded so far, ordered by part number
+    pub fn parts_uploaded(&self) -> Vec<UploadPart> {
+        self.parts.clone()
+    }
+
+    /// Part numbers in `1..=total_parts` that have not been uploaded yet
+    pub fn missing_parts(&self, total_parts: u32) -> Vec<u32> {
+        (1..=total_parts)
+            .filter(|n| !self.parts.iter().any(|p| p.part_number == *n))
+            .collect()
+    }
+
+    fn record(&mut self, part: UploadPart) {
+        self.parts.retain(|p| p.part_number != part.part_number);
+        self.parts.push(part);
+        self.parts.sort_by_key(|p| p.part_number);
+    }
+}
+
+/// Build an S3 canonical query string: parameters sorted by name, names and
+/// values URI-encoded
+fn canonical_query(params: &[(&str, &str)]) -> String {
+    let mut params: Vec<_> = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+        .collect();
+    params.sort();
+    params.join("&")
+}
+
+/// Check `body` against the `x-amz-checksum-sha256` header, or failing that
+/// a plain MD5 etag. Multipart etags (`<md5>-<parts>`) cannot be checked
+/// without the part boundaries and are skipped.
+fn verify_checksums(headers: &reqwest::header::HeaderMap, body: &[u8]) -> Result<(), StorageError> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(expected) = header("x-amz-checksum-sha256") {
+        let actual = base64::engine::general_purpose::STANDARD.encode(Sha256::digest(body));
+        if expected != actual {
+            return Err(StorageError::ChecksumMismatch { expected: expected.to_string(), actual });
+        }
+        return Ok(());
+    }
+
+    let etag = header("etag").unwrap_or("").trim_matches('"');
+    if etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()) {
+        let actual = hex::encode(Md5::digest(body));
+        if !etag.eq_ignore_ascii_case(&actual) {
+            return Err(StorageError::ChecksumMismatch { expected: etag.to_string(), actual });
         }
     }
+    Ok(())
 }
 
-impl std::error::Error for StorageError {}
+/// Decode the five predefined XML entities
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
 
 /// S3-compatible storage client
 pub struct StorageClient {
@@ -70,6 +266,7 @@ pub struct StorageClient {
     access_key: Option<String>,
     secret_key: Option<String>,
     region: String,
+    verify_checksum: bool,
     client: Client,
 }
 
@@ -81,11 +278,24 @@ impl StorageClient {
             access_key: opts.access_key,
             secret_key: opts.secret_key,
             region: opts.region,
+            verify_checksum: opts.verify_checksum,
             client: Client::new(),
         }
     }
 
     fn sign_request(&self, method: &str, path: &str, headers: &mut BTreeMap<String, String>, payload_hash: &str) {
+        self.sign_request_with_query(method, path, "", headers, payload_hash)
+    }
+
+    /// Sign a request whose URL carries `canonical_querystring` (see [`canonical_query`])
+    fn sign_request_with_query(
+        &self,
+        method: &str,
+        path: &str,
+        canonical_querystring: &str,
+        headers: &mut BTreeMap<String, String>,
+        payload_hash: &str,
+    ) {
         let (access_key, secret_key) = match (&self.access_key, &self.secret_key) {
             (Some(ak), Some(sk)) => (ak, sk),
             _ => return,
@@ -99,7 +309,6 @@ impl StorageClient {
         headers.insert("x-amz-content-sha256".to_string(), payload_hash.to_string());
 
         let canonical_uri = urlencoding::encode(path);
-        let canonical_querystring = "";
 
         let mut signed_headers: Vec<&str> = headers.keys().map(|s| s.as_str()).collect();
         signed_headers.push("host");
@@ -150,14 +359,14 @@ impl StorageClient {
         let mut headers = BTreeMap::new();
         self.sign_request("GET", "/", &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.get(&format!("{}/", self.endpoint));
+        let mut req = self.client.get(format!("{}/", self.endpoint));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
         if !resp.status().is_success() {
-            return Err(StorageError::Status(resp.status()));
+            return Err(StorageError::from_response(resp).await);
         }
 
         let text = resp.text().await.map_err(StorageError::Http)?;
@@ -177,14 +386,14 @@ impl StorageClient {
         let mut headers = BTreeMap::new();
         self.sign_request("PUT", &path, &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.put(&format!("{}{}", self.endpoint, path));
+        let mut req = self.client.put(format!("{}{}", self.endpoint, path));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
         if !resp.status().is_success() && resp.status() != StatusCode::OK {
-            return Err(StorageError::Status(resp.status()));
+            return Err(StorageError::from_response(resp).await);
         }
         Ok(())
     }
@@ -195,14 +404,14 @@ impl StorageClient {
         let mut headers = BTreeMap::new();
         self.sign_request("DELETE", &path, &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.delete(&format!("{}{}", self.endpoint, path));
+        let mut req = self.client.delete(format!("{}{}", self.endpoint, path));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
         if !resp.status().is_success() && resp.status() != StatusCode::NO_CONTENT {
-            return Err(StorageError::Status(resp.status()));
+            return Err(StorageError::from_response(resp).await);
         }
         Ok(())
     }
@@ -213,7 +422,7 @@ impl StorageClient {
         let mut headers = BTreeMap::new();
         self.sign_request("HEAD", &path, &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.head(&format!("{}{}", self.endpoint, path));
+        let mut req = self.client.head(format!("{}{}", self.endpoint, path));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
@@ -224,120 +433,536 @@ impl StorageClient {
 
     /// List objects in a bucket
     pub async fn list_objects(&self, bucket: &str, prefix: Option<&str>, max_keys: Option<u32>) -> Result<Vec<StorageObject>, StorageError> {
+        let opts = ListOptions {
+            prefix: prefix.map(str::to_string),
+            max_keys,
+            ..Default::default()
+        };
+        Ok(self.list_objects_with(bucket, opts).await?.objects)
+    }
+
+    /// List one page of objects, optionally grouped into folder-style common
+    /// prefixes by `delimiter`. Pass `next_continuation_token` back in
+    /// `continuation_token` to fetch the following page.
+    pub async fn list_objects_with(&self, bucket: &str, opts: ListOptions) -> Result<ObjectListing, StorageError> {
         let path = format!("/{}", bucket);
-        let mut url = format!("{}{}", self.endpoint, path);
+        let max_keys = opts.max_keys.map(|m| m.to_string());
 
-        let mut params = Vec::new();
-        if let Some(p) = prefix {
-            params.push(format!("prefix={}", urlencoding::encode(p)));
+        let mut params = vec![("list-type", "2")];
+        if let Some(p) = &opts.prefix {
+            params.push(("prefix", p));
+        }
+        if let Some(d) = &opts.delimiter {
+            params.push(("delimiter", d));
         }
-        if let Some(m) = max_keys {
-            params.push(format!("max-keys={}", m));
+        if let Some(m) = &max_keys {
+            params.push(("max-keys", m));
         }
-        if !params.is_empty() {
-            url.push('?');
-            url.push_str(&params.join("&"));
+        if let Some(t) = &opts.continuation_token {
+            params.push(("continuation-token", t));
         }
+        let query = canonical_query(&params);
 
         let mut headers = BTreeMap::new();
-        self.sign_request("GET", &path, &mut headers, "UNSIGNED-PAYLOAD");
+        self.sign_request_with_query("GET", &path, &query, &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.get(&url);
+        let mut req = self.client.get(format!("{}{}?{}", self.endpoint, path, query));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
         if !resp.status().is_success() {
-            return Err(StorageError::Status(resp.status()));
+            return Err(StorageError::from_response(resp).await);
         }
 
         let text = resp.text().await.map_err(StorageError::Http)?;
-        let mut objects = Vec::new();
-        let re = regex::Regex::new(r"<Key>([^<]+)</Key>.*?<Size>(\d+)</Size>.*?<ETag>([^<]+)</ETag>").unwrap();
-        for cap in re.captures_iter(&text) {
-            objects.push(StorageObject {
-                key: cap[1].to_string(),
-                size: cap[2].parse().unwrap_or(0),
-                etag: cap[3].trim_matches('"').to_string(),
-                last_modified: Utc::now(),
+        let tag = |block: &str, name: &str| {
+            regex::Regex::new(&format!("<{0}>([^<]*)</{0}>", name))
+                .unwrap()
+                .captures(block)
+                .map(|cap| xml_unescape(&cap[1]))
+        };
+
+        let mut listing = ObjectListing::default();
+        let contents = regex::Regex::new(r"(?s)<Contents>(.*?)</Contents>").unwrap();
+        for cap in contents.captures_iter(&text) {
+            let block = &cap[1];
+            listing.objects.push(StorageObject {
+                key: tag(block, "Key").unwrap_or_default(),
+                size: tag(block, "Size").and_then(|s| s.parse().ok()).unwrap_or(0),
+                etag: tag(block, "ETag").unwrap_or_default().trim_matches('"').to_string(),
+                last_modified: tag(block, "LastModified")
+                    .and_then(|d| DateTime::parse_from_rfc3339(&d).ok())
+                    .map(|d| d.with_timezone(&Utc))
+                    .unwrap_or_else(Utc::now),
                 content_type: None,
+                metadata: HashMap::new(),
             });
         }
-        Ok(objects)
+        let prefixes = regex::Regex::new(r"(?s)<CommonPrefixes>(.*?)</CommonPrefixes>").unwrap();
+        for cap in prefixes.captures_iter(&text) {
+            listing.common_prefixes.extend(tag(&cap[1], "Prefix"));
+        }
+        listing.next_continuation_token = tag(&text, "NextContinuationToken");
+        Ok(listing)
     }
 
     /// Get object content
     pub async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, StorageError> {
+        let resp = self.get_object_response(bucket, key, None).await?;
+        let headers = resp.headers().clone();
+        let body = resp.bytes().await.map_err(StorageError::Http)?;
+        if self.verify_checksum {
+            verify_checksums(&headers, &body)?;
+        }
+        Ok(body.to_vec())
+    }
+
+    /// Stream object content chunk by chunk instead of buffering it in memory.
+    /// A transfer interrupted mid-body yields a `StorageError` item.
+    pub async fn get_object_stream(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<impl Stream<Item = Result<Bytes, StorageError>>, StorageError> {
+        let resp = self.get_object_response(bucket, key, None).await?;
+        Ok(futures::stream::try_unfold(resp, |mut resp| async move {
+            let chunk = resp.chunk().await.map_err(StorageError::Http)?;
+            Ok(chunk.map(|c| (c, resp)))
+        }))
+    }
+
+    /// Get bytes `start..=end` of an object, or from `start` to the end when
+    /// `end` is `None`. Servers that ignore the `Range` header send the whole
+    /// object; the requested range is then sliced out locally.
+    pub async fn get_object_range(
+        &self,
+        bucket: &str,
+        key: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, StorageError> {
+        if end.is_some_and(|end| end < start) {
+            return Err(StorageError::Status(StatusCode::RANGE_NOT_SATISFIABLE));
+        }
+        let range = match end {
+            Some(end) => format!("bytes={}-{}", start, end),
+            None => format!("bytes={}-", start),
+        };
+        let resp = self.get_object_response(bucket, key, Some(range)).await?;
+        let partial = resp.status() == StatusCode::PARTIAL_CONTENT;
+        let body = resp.bytes().await.map_err(StorageError::Http)?;
+        if partial {
+            return Ok(body.to_vec());
+        }
+
+        let len = body.len() as u64;
+        if start >= len {
+            return Err(StorageError::Status(StatusCode::RANGE_NOT_SATISFIABLE));
+        }
+        let end = end.map_or(len, |e| e.saturating_add(1).min(len));
+        Ok(body[start as usize..end as usize].to_vec())
+    }
+
+    async fn get_object_response(
+        &self,
+        bucket: &str,
+        key: &str,
+        range: Option<String>,
+    ) -> Result<reqwest::Response, StorageError> {
         let path = format!("/{}/{}", bucket, key);
         let mut headers = BTreeMap::new();
+        if let Some(range) = range {
+            headers.insert("Range".to_string(), range);
+        }
         self.sign_request("GET", &path, &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.get(&format!("{}{}", self.endpoint, path));
+        let mut req = self.client.get(format!("{}{}", self.endpoint, path));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
         if !resp.status().is_success() {
-            return Err(StorageError::Status(resp.status()));
+            return Err(StorageError::from_response(resp).await);
         }
-        resp.bytes().await.map(|b| b.to_vec()).map_err(StorageError::Http)
+        Ok(resp)
     }
 
     /// Put object
     pub async fn put_object(&self, bucket: &str, key: &str, data: &[u8], content_type: Option<&str>) -> Result<String, StorageError> {
+        let opts = PutOptions {
+            content_type: content_type.map(str::to_string),
+            ..Default::default()
+        };
+        self.put_object_with(bucket, key, data, opts).await
+    }
+
+    /// Put object with content type, user metadata and cache control
+    pub async fn put_object_with(&self, bucket: &str, key: &str, data: &[u8], opts: PutOptions) -> Result<String, StorageError> {
         let path = format!("/{}/{}", bucket, key);
-        let payload_hash = hex::encode(Sha256::digest(data));
+        let digest = Sha256::digest(data);
+        let payload_hash = hex::encode(digest);
+        if let Some(expected) = opts.sha256 {
+            if !expected.eq_ignore_ascii_case(&payload_hash) {
+                return Err(StorageError::ChecksumMismatch { expected, actual: payload_hash });
+            }
+        }
 
         let mut headers = BTreeMap::new();
-        headers.insert("Content-Type".to_string(), content_type.unwrap_or("application/octet-stream").to_string());
+        if self.verify_checksum {
+            // The server rejects the body if it does not hash to this value
+            headers.insert(
+                "x-amz-checksum-sha256".to_string(),
+                base64::engine::general_purpose::STANDARD.encode(digest),
+            );
+        }
+        headers.insert(
+            "Content-Type".to_string(),
+            opts.content_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+        );
         headers.insert("Content-Length".to_string(), data.len().to_string());
+        if let Some(cache_control) = opts.cache_control {
+            headers.insert("Cache-Control".to_string(), cache_control);
+        }
+        for (k, v) in opts.metadata {
+            headers.insert(format!("x-amz-meta-{}", k.to_ascii_lowercase()), v);
+        }
         self.sign_request("PUT", &path, &mut headers, &payload_hash);
 
-        let mut req = self.client.put(&format!("{}{}", self.endpoint, path)).body(data.to_vec());
+        let mut req = self.client.put(format!("{}{}", self.endpoint, path)).body(data.to_vec());
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
         if !resp.status().is_success() {
-            return Err(StorageError::Status(resp.status()));
+            return Err(StorageError::from_response(resp).await);
+        }
+        if self.verify_checksum {
+            verify_checksums(resp.headers(), data)?;
         }
 
         Ok(resp.headers().get("etag").and_then(|v| v.to_str().ok()).unwrap_or("").trim_matches('"').to_string())
     }
 
+    /// Server-side copy of an object, keeping its content type and metadata.
+    /// Returns the new object as reported by `head_object`.
+    pub async fn copy_object(
+        &self,
+        src_bucket: &str,
+        src_key: &str,
+        dst_bucket: &str,
+        dst_key: &str,
+    ) -> Result<StorageObject, StorageError> {
+        let path = format!("/{}/{}", dst_bucket, dst_key);
+        let mut headers = BTreeMap::new();
+        headers.insert(
+            "x-amz-copy-source".to_string(),
+            format!("/{}/{}", src_bucket, urlencoding::encode(src_key)),
+        );
+        headers.insert("x-amz-metadata-directive".to_string(), "COPY".to_string());
+        self.sign_request("PUT", &path, &mut headers, "UNSIGNED-PAYLOAD");
+
+        let mut req = self.client.put(format!("{}{}", self.endpoint, path));
+        for (k, v) in &headers {
+            req = req.header(k, v);
+        }
+
+        let resp = req.send().await.map_err(StorageError::Http)?;
+        if !resp.status().is_success() {
+            return Err(StorageError::from_response(resp).await);
+        }
+
+        self.head_object(dst_bucket, dst_key).await
+    }
+
+    /// Copy an object, then delete the source. Moving an object onto itself
+    /// leaves it in place.
+    pub async fn move_object(
+        &self,
+        src_bucket: &str,
+        src_key: &str,
+        dst_bucket: &str,
+        dst_key: &str,
+    ) -> Result<StorageObject, StorageError> {
+        if src_bucket == dst_bucket && src_key == dst_key {
+            return self.head_object(dst_bucket, dst_key).await;
+        }
+        let obj = self.copy_object(src_bucket, src_key, dst_bucket, dst_key).await?;
+        self.delete_object(src_bucket, src_key).await?;
+        Ok(obj)
+    }
+
     /// Delete object
     pub async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), StorageError> {
         let path = format!("/{}/{}", bucket, key);
         let mut headers = BTreeMap::new();
         self.sign_request("DELETE", &path, &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.delete(&format!("{}{}", self.endpoint, path));
+        let mut req = self.client.delete(format!("{}{}", self.endpoint, path));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
         if !resp.status().is_success() && resp.status() != StatusCode::NO_CONTENT {
-            return Err(StorageError::Status(resp.status()));
+            return Err(StorageError::from_response(resp).await);
         }
         Ok(())
     }
 
     /// Check if object exists
     pub async fn object_exists(&self, bucket: &str, key: &str) -> Result<bool, StorageError> {
+        match self.head_object(bucket, key).await {
+            Ok(_) => Ok(true),
+            Err(StorageError::NotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fetch object metadata without the body
+    pub async fn head_object(&self, bucket: &str, key: &str) -> Result<StorageObject, StorageError> {
         let path = format!("/{}/{}", bucket, key);
         let mut headers = BTreeMap::new();
         self.sign_request("HEAD", &path, &mut headers, "UNSIGNED-PAYLOAD");
 
-        let mut req = self.client.head(&format!("{}{}", self.endpoint, path));
+        let mut req = self.client.head(format!("{}{}", self.endpoint, path));
         for (k, v) in &headers {
             req = req.header(k, v);
         }
 
         let resp = req.send().await.map_err(StorageError::Http)?;
-        Ok(resp.status() == StatusCode::OK)
+        if !resp.status().is_success() {
+            return Err(StorageError::from_response(resp).await);
+        }
+
+        let header = |name: &str| resp.headers().get(name).and_then(|v| v.to_str().ok());
+        let metadata = resp
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| {
+                let name = k.as_str().strip_prefix("x-amz-meta-")?;
+                Some((name.to_string(), v.to_str().ok()?.to_string()))
+            })
+            .collect();
+
+        Ok(StorageObject {
+            key: key.to_string(),
+            size: header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0),
+            etag: header("etag").unwrap_or("").trim_matches('"').to_string(),
+            last_modified: header("last-modified")
+                .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+                .map(|d| d.with_timezone(&Utc))
+                .unwrap_or_else(Utc::now),
+            content_type: header("content-type").map(str::to_string),
+            metadata,
+        })
+    }
+
+    /// Start a multipart upload
+    pub async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        content_type: Option<&str>,
+    ) -> Result<MultipartUpload, StorageError> {
+        let path = format!("/{}/{}", bucket, key);
+        let query = canonical_query(&[("uploads", "")]);
+        let mut headers = BTreeMap::new();
+        headers.insert("Content-Type".to_string(), content_type.unwrap_or("application/octet-stream").to_string());
+        self.sign_request_with_query("POST", &path, &query, &mut headers, "UNSIGNED-PAYLOAD");
+
+        let mut req = self.client.post(format!("{}{}?{}", self.endpoint, path, query));
+        for (k, v) in &headers {
+            req = req.header(k, v);
+        }
+
+        let resp = req.send().await.map_err(StorageError::Http)?;
+        if !resp.status().is_success() {
+            return Err(StorageError::from_response(resp).await);
+        }
+
+        let text = resp.text().await.map_err(StorageError::Http)?;
+        let upload_id = regex::Regex::new(r"<UploadId>([^<]+)</UploadId>")
+            .unwrap()
+            .captures(&text)
+            .map(|cap| cap[1].to_string())
+            .ok_or_else(|| StorageError::Server {
+                code: "InvalidResponse".to_string(),
+                message: "Missing UploadId in response".to_string(),
+            })?;
+
+        Ok(MultipartUpload {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            upload_id,
+            parts: Vec::new(),
+        })
+    }
+
+    /// Re-hydrate an interrupted multipart upload by listing the parts the
+    /// server already holds, following the listing across pages
+    pub async fn resume_multipart(&self, bucket: &str, key: &str, upload_id: &str) -> Result<MultipartUpload, StorageError> {
+        let path = format!("/{}/{}", bucket, key);
+        let invalid = |message: String| StorageError::Server {
+            code: "InvalidResponse".to_string(),
+            message,
+        };
+        let tag = |text: &str, name: &str| {
+            regex::Regex::new(&format!("<{0}>([^<]*)</{0}>", name))
+                .unwrap()
+                .captures(text)
+                .map(|cap| cap[1].to_string())
+        };
+        let re = regex::Regex::new(r"(?s)<Part>.*?<PartNumber>(\d+)</PartNumber>.*?<ETag>([^<]+)</ETag>.*?<Size>(\d+)</Size>.*?</Part>").unwrap();
+
+        let mut upload = MultipartUpload {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            upload_id: upload_id.to_string(),
+            parts: Vec::new(),
+        };
+        let mut marker: Option<String> = None;
+        loop {
+            let mut params = vec![("uploadId", upload_id)];
+            if let Some(marker) = &marker {
+                params.push(("part-number-marker", marker));
+            }
+            let query = canonical_query(&params);
+            let mut headers = BTreeMap::new();
+            self.sign_request_with_query("GET", &path, &query, &mut headers, "UNSIGNED-PAYLOAD");
+
+            let mut req = self.client.get(format!("{}{}?{}", self.endpoint, path, query));
+            for (k, v) in &headers {
+                req = req.header(k, v);
+            }
+
+            let resp = req.send().await.map_err(StorageError::Http)?;
+            if !resp.status().is_success() {
+                return Err(StorageError::from_response(resp).await);
+            }
+
+            let text = resp.text().await.map_err(StorageError::Http)?;
+            for cap in re.captures_iter(&text) {
+                let part_number = cap[1]
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| invalid(format!("Invalid part number {}", &cap[1])))?;
+                let size = cap[3]
+                    .parse()
+                    .map_err(|_| invalid(format!("Invalid size {} for part {}", &cap[3], part_number)))?;
+                upload.record(UploadPart {
+                    part_number,
+                    etag: cap[2].replace("&quot;", "").trim_matches('"').to_string(),
+                    size,
+                });
+            }
+
+            if tag(&text, "IsTruncated").as_deref() != Some("true") {
+                return Ok(upload);
+            }
+            let next = tag(&text, "NextPartNumberMarker")
+                .ok_or_else(|| invalid("Truncated part listing without NextPartNumberMarker".to_string()))?;
+            if marker.as_deref() == Some(next.as_str()) {
+                return Err(invalid(format!("Part listing did not advance past marker {}", next)));
+            }
+            marker = Some(next);
+        }
+    }
+
+    /// Upload one part (numbered from 1) and record it on `upload`.
+    /// Re-uploading a part number replaces the earlier part.
+    pub async fn upload_part(
+        &self,
+        upload: &mut MultipartUpload,
+        part_number: u32,
+        data: &[u8],
+    ) -> Result<UploadPart, StorageError> {
+        let path = format!("/{}/{}", upload.bucket, upload.key);
+        let part = part_number.to_string();
+        let query = canonical_query(&[("partNumber", &part), ("uploadId", &upload.upload_id)]);
+        let payload_hash = hex::encode(Sha256::digest(data));
+
+        let mut headers = BTreeMap::new();
+        headers.insert("Content-Length".to_string(), data.len().to_string());
+        self.sign_request_with_query("PUT", &path, &query, &mut headers, &payload_hash);
+
+        let mut req = self.client.put(format!("{}{}?{}", self.endpoint, path, query)).body(data.to_vec());
+        for (k, v) in &headers {
+            req = req.header(k, v);
+        }
+
+        let resp = req.send().await.map_err(StorageError::Http)?;
+        if !resp.status().is_success() {
+            return Err(StorageError::from_response(resp).await);
+        }
+
+        let part = UploadPart {
+            part_number,
+            etag: resp.headers().get("etag").and_then(|v| v.to_str().ok()).unwrap_or("").trim_matches('"').to_string(),
+            size: data.len() as u64,
+        };
+        upload.record(part.clone());
+        Ok(part)
+    }
+
+    /// Assemble the uploaded parts into the final object, returning its etag
+    pub async fn complete_multipart_upload(&self, upload: &MultipartUpload) -> Result<String, StorageError> {
+        let path = format!("/{}/{}", upload.bucket, upload.key);
+        let query = canonical_query(&[("uploadId", &upload.upload_id)]);
+
+        let mut body = String::from("<CompleteMultipartUpload>");
+        for part in &upload.parts {
+            body.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>\"{}\"</ETag></Part>",
+                part.part_number, part.etag
+            ));
+        }
+        body.push_str("</CompleteMultipartUpload>");
+        let payload_hash = hex::encode(Sha256::digest(body.as_bytes()));
+
+        let mut headers = BTreeMap::new();
+        headers.insert("Content-Type".to_string(), "application/xml".to_string());
+        headers.insert("Content-Length".to_string(), body.len().to_string());
+        self.sign_request_with_query("POST", &path, &query, &mut headers, &payload_hash);
+
+        let mut req = self.client.post(format!("{}{}?{}", self.endpoint, path, query)).body(body);
+        for (k, v) in &headers {
+            req = req.header(k, v);
+        }
+
+        let resp = req.send().await.map_err(StorageError::Http)?;
+        if !resp.status().is_success() {
+            return Err(StorageError::from_response(resp).await);
+        }
+
+        let text = resp.text().await.map_err(StorageError::Http)?;
+        Ok(regex::Regex::new(r"<ETag>([^<]+)</ETag>")
+            .unwrap()
+            .captures(&text)
+            .map(|cap| cap[1].replace("&quot;", "").trim_matches('"').to_string())
+            .unwrap_or_default())
+    }
+
+    /// Abandon a multipart upload and discard its parts
+    pub async fn abort_multipart_upload(&self, upload: &MultipartUpload) -> Result<(), StorageError> {
+        let path = format!("/{}/{}", upload.bucket, upload.key);
+        let query = canonical_query(&[("uploadId", &upload.upload_id)]);
+        let mut headers = BTreeMap::new();
+        self.sign_request_with_query("DELETE", &path, &query, &mut headers, "UNSIGNED-PAYLOAD");
+
+        let mut req = self.client.delete(format!("{}{}?{}", self.endpoint, path, query));
+        for (k, v) in &headers {
+            req = req.header(k, v);
+        }
+
+        let resp = req.send().await.map_err(StorageError::Http)?;
+        if !resp.status().is_success() && resp.status() != StatusCode::NO_CONTENT {
+            return Err(StorageError::from_response(resp).await);
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,99 @@
+//! SquirrelDB Rust SDK - Arrow Conversion Tests
+
+#![cfg(feature = "arrow")]
+
+use std::sync::Arc;
+
+use arrow_array::{Array, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use serde_json::json;
+use squirreldb_sdk::arrow::{infer_schema, rows_to_record_batch};
+
+#[test]
+fn test_infer_schema_heterogeneous_rows() {
+    let rows = vec![
+        json!({"name": "Alice", "age": 30, "score": 1}),
+        json!({"name": "Bob", "score": 2.5, "active": true}),
+        json!({"age": null, "tags": ["a", "b"]}),
+    ];
+
+    let schema = infer_schema(&rows, 100);
+    assert_eq!(schema.field_with_name("name").unwrap().data_type(), &DataType::Utf8);
+    assert_eq!(schema.field_with_name("age").unwrap().data_type(), &DataType::Int64);
+    assert_eq!(schema.field_with_name("score").unwrap().data_type(), &DataType::Float64);
+    assert_eq!(schema.field_with_name("active").unwrap().data_type(), &DataType::Boolean);
+    assert_eq!(schema.field_with_name("tags").unwrap().data_type(), &DataType::Utf8);
+}
+
+#[test]
+fn test_rows_to_record_batch_with_nulls() {
+    let rows = vec![
+        json!({"name": "Alice", "age": 30, "score": 1}),
+        json!({"name": "Bob", "score": 2.5, "active": true}),
+        json!({"age": null}),
+    ];
+
+    let batch = rows_to_record_batch(&rows, None).unwrap();
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(batch.num_columns(), 4);
+
+    let age = batch.column_by_name("age").unwrap();
+    let age = age.as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(age.value(0), 30);
+    assert!(age.is_null(1));
+    assert!(age.is_null(2));
+
+    let score = batch.column_by_name("score").unwrap();
+    let score = score.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(score.value(0), 1.0);
+    assert_eq!(score.value(1), 2.5);
+
+    let name = batch.column_by_name("name").unwrap();
+    let name = name.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(name.value(1), "Bob");
+    assert_eq!(name.null_count(), 1);
+
+    let active = batch.column_by_name("active").unwrap();
+    let active = active.as_any().downcast_ref::<BooleanArray>().unwrap();
+    assert!(active.value(1));
+    assert_eq!(active.null_count(), 2);
+}
+
+#[test]
+fn test_rows_to_record_batch_explicit_schema() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, true),
+        Field::new("price", DataType::Float64, true),
+    ]));
+    let rows = vec![json!({"id": "7", "price": 3}), json!({"id": 8, "extra": "ignored"})];
+
+    let batch = rows_to_record_batch(&rows, Some(schema)).unwrap();
+    assert_eq!(batch.num_columns(), 2);
+
+    let id = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(id.value(0), 7);
+    assert_eq!(id.value(1), 8);
+
+    let price = batch.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(price.value(0), 3.0);
+    assert!(price.is_null(1));
+}
+
+#[test]
+fn test_rows_to_record_batch_rejects_uncoercible_value() {
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+    let rows = vec![json!({"id": "not-a-number"})];
+
+    assert!(rows_to_record_batch(&rows, Some(schema)).is_err());
+}
+
+#[test]
+fn test_rows_to_record_batch_empty_result() {
+    let batch = rows_to_record_batch(&[], None).unwrap();
+    assert_eq!(batch.num_rows(), 0);
+    assert_eq!(batch.num_columns(), 0);
+
+    let batch = rows_to_record_batch(&[json!(1), json!("x")], None).unwrap();
+    assert_eq!(batch.num_rows(), 2);
+    assert_eq!(batch.num_columns(), 0);
+}
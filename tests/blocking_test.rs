@@ -0,0 +1,125 @@
+//! SquirrelDB Rust SDK - Blocking Client Tests
+
+#![cfg(feature = "blocking")]
+
+use squirreldb_sdk::blocking::SquirrelDB;
+use squirreldb_sdk::cache::blocking::CacheClient;
+use squirreldb_sdk::{CacheOptions, ConnectOptions, PubSubMessage, RespValue, SubEvent};
+use tokio::io::AsyncReadExt;
+
+mod common;
+use common::{accept_handshake, read_request, serve_blocking, serve_resp, write_response};
+
+#[test]
+fn test_blocking_query_and_subscription() {
+    let (port, server) = serve_blocking(|listener| async move {
+        let mut socket = accept_handshake(&listener, 0x01).await;
+        let query = read_request(&mut socket).await;
+        write_response(&mut socket, serde_json::json!({"type": "result", "id": query.id(), "data": [1, 2, 3]})).await;
+
+        let subscribe = read_request(&mut socket).await;
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": subscribe.id()})).await;
+        write_response(&mut socket, serde_json::json!({"type": "unsubscribed", "id": subscribe.id()})).await;
+        // Hold the connection until the client closes it
+        let mut buf = [0u8; 64];
+        while socket.read(&mut buf).await.unwrap_or(0) > 0 {}
+    });
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port)).unwrap();
+
+    let rows: Vec<i64> = client.query("db.table(\"users\").run()").unwrap();
+    assert_eq!(rows, [1, 2, 3]);
+
+    let subscription = client.subscribe("db.table(\"users\").changes()").unwrap();
+    let events: Vec<SubEvent> = subscription.collect();
+    assert!(matches!(events.as_slice(), [SubEvent::Ready, SubEvent::Closed]));
+
+    client.close().unwrap();
+    server.join().unwrap();
+}
+
+fn cache_client(port: u16) -> CacheClient {
+    let opts = CacheOptions {
+        host: "127.0.0.1".to_string(),
+        port,
+        ..Default::default()
+    };
+    CacheClient::connect(Some(opts)).unwrap()
+}
+
+#[test]
+fn test_blocking_cache_client() {
+    let (port, server) = serve_blocking(|listener| {
+        serve_resp(
+            listener,
+            vec!["+OK\r\n", "$5\r\nhello\r\n", ":2\r\n", "$5\r\nhello\r\n", ":11\r\n", "$3\r\nbye\r\n", ":1\r\n"],
+        )
+    });
+    let mut cache = cache_client(port);
+    cache.set("greeting", "hello", None).unwrap();
+    assert_eq!(cache.get("greeting").unwrap(), Some("hello".to_string()));
+    assert_eq!(cache.incr("visits").unwrap(), 2);
+    assert_eq!(cache.getset("greeting", "bye").unwrap(), Some("hello".to_string()));
+    assert_eq!(cache.append("greeting", " now").unwrap(), 11);
+    assert_eq!(cache.getdel("greeting").unwrap(), Some("bye".to_string()));
+    assert!(cache.pexpire("visits", 1500).unwrap());
+
+    let commands = server.join().unwrap();
+    assert_eq!(commands[1], ["GET", "greeting"]);
+    assert_eq!(commands[3], ["GETSET", "greeting", "bye"]);
+    assert_eq!(commands[6], ["PEXPIRE", "visits", "1500"]);
+}
+
+#[test]
+fn test_blocking_cache_sorted_sets_and_transactions() {
+    let (port, server) = serve_blocking(|listener| {
+        serve_resp(
+            listener,
+            vec![
+                ":1\r\n",
+                "*2\r\n$3\r\nbob\r\n$4\r\n42.5\r\n",
+                "+OK\r\n",
+                "+OK\r\n",
+                "+QUEUED\r\n",
+                "*1\r\n:3\r\n",
+            ],
+        )
+    });
+    let mut cache = cache_client(port);
+    assert_eq!(cache.zadd("board", 42.5, "bob").unwrap(), 1);
+    assert_eq!(cache.zrange("board", 0, -1, true).unwrap(), vec![("bob".to_string(), Some(42.5))]);
+
+    cache.watch(&["balance"]).unwrap();
+    cache.multi().unwrap();
+    cache.raw(&["INCR", "balance"]).unwrap();
+    let replies = cache.exec().unwrap().unwrap();
+    assert!(matches!(replies.as_slice(), [RespValue::Integer(3)]));
+
+    let commands = server.join().unwrap();
+    assert_eq!(commands[1], ["ZRANGE", "board", "0", "-1", "WITHSCORES"]);
+    assert_eq!(commands[2], ["WATCH", "balance"]);
+    assert_eq!(commands[5], ["EXEC"]);
+}
+
+#[test]
+fn test_blocking_cache_pubsub() {
+    let (port, server) = serve_blocking(|listener| {
+        serve_resp(
+            listener,
+            vec![concat!(
+                "*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n:1\r\n",
+                "*4\r\n$8\r\npmessage\r\n$6\r\nnews.*\r\n$10\r\nnews.world\r\n$5\r\nhello\r\n",
+            )],
+        )
+    });
+    let mut cache = cache_client(port);
+    cache.psubscribe(&["news.*"]).unwrap();
+    assert_eq!(
+        cache.next_message().unwrap(),
+        PubSubMessage::PMessage {
+            pattern: "news.*".to_string(),
+            channel: "news.world".to_string(),
+            payload: "hello".to_string(),
+        }
+    );
+    assert_eq!(server.join().unwrap()[0], ["PSUBSCRIBE", "news.*"]);
+}
@@ -0,0 +1,480 @@
+//! SquirrelDB Rust SDK - Cache Tests
+
+use squirreldb_sdk::{CacheClient, CacheError, CacheOptions, PubSubMessage, RespValue, SlowLogEntry};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use std::time::{Duration, Instant};
+
+mod common;
+use common::resp_server;
+
+async fn client(port: u16) -> CacheClient {
+    let opts = CacheOptions {
+        host: "127.0.0.1".to_string(),
+        port,
+        ..Default::default()
+    };
+    CacheClient::connect(Some(opts)).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_incrbyfloat_and_hincrby() {
+    let (port, server) = resp_server(vec!["$4\r\n10.5\r\n", ":7\r\n", "$3\r\nabc\r\n"]).await;
+    let mut cache = client(port).await;
+
+    assert_eq!(cache.incrbyfloat("budget", 0.5).await.unwrap(), 10.5);
+    assert_eq!(cache.hincrby("stats", "hits", 2).await.unwrap(), 7);
+    assert!(matches!(
+        cache.incrbyfloat("budget", 1.0).await,
+        Err(CacheError::Protocol(_))
+    ));
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], ["INCRBYFLOAT", "budget", "0.5"]);
+    assert_eq!(commands[1], ["HINCRBY", "stats", "hits", "2"]);
+}
+
+#[tokio::test]
+async fn test_getset_getdel_append() {
+    let (port, server) = resp_server(vec!["$1\r\n5\r\n", "$-1\r\n", "$1\r\n0\r\n", ":11\r\n"]).await;
+    let mut cache = client(port).await;
+
+    assert_eq!(cache.getset("counter", "0").await.unwrap().as_deref(), Some("5"));
+    assert_eq!(cache.getdel("missing").await.unwrap(), None);
+    assert_eq!(cache.getdel("counter").await.unwrap().as_deref(), Some("0"));
+    assert_eq!(cache.append("log", "line\n").await.unwrap(), 11);
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], ["GETSET", "counter", "0"]);
+    assert_eq!(commands[3], ["APPEND", "log", "line\n"]);
+}
+
+#[tokio::test]
+async fn test_key_type_and_rename() {
+    let (port, server) = resp_server(vec![
+        "+hash\r\n",
+        "+OK\r\n",
+        "-ERR no such key\r\n",
+        ":0\r\n",
+    ])
+    .await;
+    let mut cache = client(port).await;
+
+    assert_eq!(cache.key_type("user:1").await.unwrap(), "hash");
+    cache.rename("a", "b").await.unwrap();
+    assert!(matches!(
+        cache.rename("missing", "b").await,
+        Err(CacheError::Server(msg)) if msg.contains("no such key")
+    ));
+    assert!(!cache.renamenx("a", "b").await.unwrap());
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], ["TYPE", "user:1"]);
+    assert_eq!(commands[3], ["RENAMENX", "a", "b"]);
+}
+
+#[tokio::test]
+async fn test_sorted_sets() {
+    let (port, server) = resp_server(vec![
+        ":1\r\n",
+        "*4\r\n$5\r\nalice\r\n$2\r\n50\r\n$3\r\nbob\r\n$4\r\n42.5\r\n",
+        "*2\r\n$3\r\nbob\r\n$5\r\nalice\r\n",
+        "$4\r\n42.5\r\n",
+        "$-1\r\n",
+        ":0\r\n",
+        "$-1\r\n",
+    ])
+    .await;
+    let mut cache = client(port).await;
+
+    assert_eq!(cache.zadd("board", f64::INFINITY, "carol").await.unwrap(), 1);
+    assert_eq!(
+        cache.zrevrange("board", 0, -1, true).await.unwrap(),
+        vec![("alice".to_string(), Some(50.0)), ("bob".to_string(), Some(42.5))]
+    );
+    assert_eq!(
+        cache.zrange("board", 0, 1, false).await.unwrap(),
+        vec![("bob".to_string(), None), ("alice".to_string(), None)]
+    );
+    assert_eq!(cache.zscore("board", "bob").await.unwrap(), Some(42.5));
+    assert_eq!(cache.zscore("board", "dave").await.unwrap(), None);
+    assert_eq!(cache.zrank("board", "bob").await.unwrap(), Some(0));
+    assert_eq!(cache.zrank("board", "dave").await.unwrap(), None);
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], ["ZADD", "board", "+inf", "carol"]);
+    assert_eq!(commands[1], ["ZREVRANGE", "board", "0", "-1", "WITHSCORES"]);
+    assert_eq!(commands[2], ["ZRANGE", "board", "0", "1"]);
+}
+
+#[tokio::test]
+async fn test_millisecond_and_absolute_expiry() {
+    let (port, server) = resp_server(vec![":1\r\n", ":1\r\n", ":0\r\n", ":1500\r\n", ":-2\r\n"]).await;
+    let mut cache = client(port).await;
+
+    assert!(cache.pexpire("lock", 1500).await.unwrap());
+    assert!(cache.expireat("session", 1_700_000_000).await.unwrap());
+    assert!(!cache.pexpireat("missing", 1_700_000_000_000).await.unwrap());
+    assert_eq!(cache.pttl("lock").await.unwrap(), 1500);
+    assert_eq!(cache.pttl("missing").await.unwrap(), -2);
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], ["PEXPIRE", "lock", "1500"]);
+    assert_eq!(commands[1], ["EXPIREAT", "session", "1700000000"]);
+    assert_eq!(commands[2], ["PEXPIREAT", "missing", "1700000000000"]);
+    assert_eq!(commands[3], ["PTTL", "lock"]);
+}
+
+#[tokio::test]
+async fn test_wait_for_replicas() {
+    let (port, server) = resp_server(vec![":1\r\n"]).await;
+    let mut cache = client(port).await;
+
+    assert_eq!(cache.wait(2, 500).await.unwrap(), 1);
+    assert_eq!(server.await.unwrap()[0], ["WAIT", "2", "500"]);
+}
+
+#[tokio::test]
+async fn test_raw_command() {
+    let (port, server) = resp_server(vec!["$8\r\nlistpack\r\n", "-ERR unknown subcommand\r\n"]).await;
+    let mut cache = client(port).await;
+
+    assert!(matches!(
+        cache.raw(&["OBJECT", "ENCODING", "user:1"]).await.unwrap(),
+        RespValue::BulkString(Some(s)) if s == "listpack"
+    ));
+    assert!(matches!(cache.raw(&["OBJECT", "NOPE"]).await, Err(CacheError::Server(_))));
+    assert_eq!(server.await.unwrap()[0], ["OBJECT", "ENCODING", "user:1"]);
+}
+
+#[tokio::test]
+async fn test_memory_usage_and_idletime() {
+    let (port, server) = resp_server(vec![":72\r\n", "$-1\r\n", ":3600\r\n", "$-1\r\n"]).await;
+    let mut cache = client(port).await;
+
+    assert_eq!(cache.memory_usage("user:1").await.unwrap(), Some(72));
+    assert_eq!(cache.memory_usage("missing").await.unwrap(), None);
+    assert_eq!(cache.object_idletime("user:1").await.unwrap(), Some(3600));
+    assert_eq!(cache.object_idletime("missing").await.unwrap(), None);
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], ["MEMORY", "USAGE", "user:1"]);
+    assert_eq!(commands[2], ["OBJECT", "IDLETIME", "user:1"]);
+}
+
+#[tokio::test]
+async fn test_slowlog() {
+    let (port, server) = resp_server(vec![
+        concat!(
+            "*2\r\n",
+            "*6\r\n:14\r\n:1700000000\r\n:15000\r\n",
+            "*2\r\n$4\r\nKEYS\r\n$1\r\n*\r\n$15\r\n127.0.0.1:58890\r\n$0\r\n\r\n",
+            "*4\r\n:13\r\n:1699999999\r\n:12000\r\n*1\r\n$8\r\nFLUSHALL\r\n",
+        ),
+        "+OK\r\n",
+    ])
+    .await;
+    let mut cache = client(port).await;
+
+    let entries = cache.slowlog_get(2).await.unwrap();
+    assert_eq!(
+        entries[0],
+        SlowLogEntry {
+            id: 14,
+            timestamp: 1_700_000_000,
+            duration_micros: 15_000,
+            args: vec!["KEYS".to_string(), "*".to_string()],
+            client_addr: Some("127.0.0.1:58890".to_string()),
+            client_name: None,
+        }
+    );
+    assert_eq!(entries[1].args, ["FLUSHALL"]);
+    assert_eq!(entries[1].client_addr, None);
+    cache.slowlog_reset().await.unwrap();
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], ["SLOWLOG", "GET", "2"]);
+    assert_eq!(commands[1], ["SLOWLOG", "RESET"]);
+}
+
+#[tokio::test]
+async fn test_psubscribe_distinguishes_pattern_messages() {
+    let (port, server) = resp_server(vec![concat!(
+        "*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n:1\r\n",
+        "*3\r\n$10\r\npsubscribe\r\n$7\r\nalerts*\r\n:2\r\n",
+        "*4\r\n$8\r\npmessage\r\n$6\r\nnews.*\r\n$10\r\nnews.world\r\n$5\r\nhello\r\n",
+        "*3\r\n$7\r\nmessage\r\n$6\r\ndirect\r\n$2\r\nhi\r\n",
+    )])
+    .await;
+    let mut cache = client(port).await;
+
+    cache.psubscribe(&["news.*", "alerts*"]).await.unwrap();
+    assert_eq!(
+        cache.next_message().await.unwrap(),
+        PubSubMessage::PMessage {
+            pattern: "news.*".to_string(),
+            channel: "news.world".to_string(),
+            payload: "hello".to_string(),
+        }
+    );
+    assert_eq!(
+        cache.next_message().await.unwrap(),
+        PubSubMessage::Message { channel: "direct".to_string(), payload: "hi".to_string() }
+    );
+    assert_eq!(server.await.unwrap()[0], ["PSUBSCRIBE", "news.*", "alerts*"]);
+}
+
+#[tokio::test]
+async fn test_from_stream_over_duplex() {
+    let (client_io, mut server_io) = tokio::io::duplex(1024);
+    let mut cache = CacheClient::from_stream(client_io, None);
+
+    let server = tokio::spawn(async move {
+        let mut buf = vec![0u8; 64];
+        let n = server_io.read(&mut buf).await.unwrap();
+        server_io.write_all(b"$5\r\nhello\r\n").await.unwrap();
+        buf.truncate(n);
+        buf
+    });
+
+    assert_eq!(cache.get("greeting").await.unwrap(), Some("hello".to_string()));
+    assert_eq!(server.await.unwrap(), b"*2\r\n$3\r\nGET\r\n$8\r\ngreeting\r\n");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_connect_over_unix_socket() {
+    let path = std::env::temp_dir().join(format!("squirreldb-cache-test-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 64];
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket.write_all(b"+PONG\r\n").await.unwrap();
+    });
+
+    let opts = CacheOptions {
+        unix_socket: Some(path.clone()),
+        ..Default::default()
+    };
+    let mut cache = CacheClient::connect(Some(opts)).await.unwrap();
+    cache.ping().await.unwrap();
+    server.await.unwrap();
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(not(unix))]
+#[tokio::test]
+async fn test_unix_socket_rejected_off_unix() {
+    let opts = CacheOptions {
+        unix_socket: Some("squirreldb.sock".into()),
+        ..Default::default()
+    };
+    let result = CacheClient::connect(Some(opts)).await;
+    assert!(matches!(result, Err(CacheError::Connection(msg)) if msg.contains("not supported")));
+}
+
+#[tokio::test]
+async fn test_command_timeout_poisons_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    // Accept and read, but never reply
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        while socket.read(&mut buf).await.unwrap_or(0) > 0 {}
+    });
+
+    let opts = CacheOptions {
+        host: "127.0.0.1".to_string(),
+        port,
+        command_timeout: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let mut cache = CacheClient::connect(Some(opts)).await.unwrap();
+
+    let started = Instant::now();
+    assert!(matches!(cache.get("key").await, Err(CacheError::Timeout)));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(matches!(cache.get("key").await, Err(CacheError::Connection(_))));
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_inline_commands() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut reader = BufReader::new(socket);
+        let mut lines = Vec::new();
+        for _ in 0..2 {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            lines.push(line);
+            reader.get_mut().write_all(b"+OK\r\n").await.unwrap();
+        }
+        lines
+    });
+
+    let opts = CacheOptions {
+        host: "127.0.0.1".to_string(),
+        port,
+        inline_commands: true,
+        ..Default::default()
+    };
+    let mut cache = CacheClient::connect(Some(opts)).await.unwrap();
+    cache.set("greeting", "hello", None).await.unwrap();
+    cache.set("quote", "say \"hi\"\n", None).await.unwrap();
+
+    let lines = server.await.unwrap();
+    assert_eq!(lines[0], "SET greeting hello\r\n");
+    assert_eq!(lines[1], "SET quote \"say \\\"hi\\\"\\n\"\r\n");
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_connect_timeout_covers_tls_handshake() {
+    // Accepts the TCP connection but never answers the ClientHello
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move { listener.accept().await.unwrap() });
+
+    let opts = CacheOptions {
+        host: "localhost".to_string(),
+        port,
+        connect_timeout: Some(Duration::from_millis(200)),
+        command_timeout: None,
+        inline_commands: false,
+        tcp_nodelay: true,
+        unix_socket: None,
+        tls: true,
+        ca_cert: None,
+    };
+    let started = Instant::now();
+    let result = CacheClient::connect(Some(opts)).await;
+    assert!(matches!(result, Err(CacheError::Connection(msg)) if msg.contains("Timed out")));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    drop(server);
+}
+
+#[cfg(not(feature = "tls"))]
+#[tokio::test]
+async fn test_tls_requires_feature() {
+    let opts = CacheOptions {
+        tls: true,
+        ..Default::default()
+    };
+    let result = CacheClient::connect(Some(opts)).await;
+    assert!(matches!(result, Err(CacheError::Connection(msg)) if msg.contains("`tls` feature")));
+}
+
+#[tokio::test]
+async fn test_watch_multi_exec() {
+    let (port, server) = resp_server(vec![
+        "+OK\r\n",
+        "+OK\r\n",
+        "+QUEUED\r\n",
+        "+QUEUED\r\n",
+        "*2\r\n+OK\r\n:4\r\n",
+    ])
+    .await;
+    let mut cache = client(port).await;
+
+    cache.watch(&["balance"]).await.unwrap();
+    cache.multi().await.unwrap();
+    let queued = cache.raw(&["SET", "balance", "3"]).await.unwrap();
+    assert!(matches!(queued, RespValue::SimpleString(s) if s == "QUEUED"));
+    cache.raw(&["INCR", "balance"]).await.unwrap();
+    let replies = cache.exec().await.unwrap().unwrap();
+    assert!(matches!(replies.as_slice(), [RespValue::SimpleString(ok), RespValue::Integer(4)] if ok == "OK"));
+
+    assert_eq!(
+        server.await.unwrap(),
+        vec![
+            vec!["WATCH", "balance"],
+            vec!["MULTI"],
+            vec!["SET", "balance", "3"],
+            vec!["INCR", "balance"],
+            vec!["EXEC"],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_exec_aborted_by_watch_returns_none() {
+    let (port, server) = resp_server(vec!["+OK\r\n", "+OK\r\n", "+QUEUED\r\n", "*-1\r\n", "+OK\r\n"]).await;
+    let mut cache = client(port).await;
+
+    cache.watch(&["a", "b"]).await.unwrap();
+    cache.multi().await.unwrap();
+    cache.raw(&["SET", "a", "1"]).await.unwrap();
+    assert!(cache.exec().await.unwrap().is_none());
+    cache.unwatch().await.unwrap();
+
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0], vec!["WATCH", "a", "b"]);
+    assert_eq!(commands[4], vec!["UNWATCH"]);
+}
+
+#[tokio::test]
+async fn test_mset_and_mget_bytes() {
+    let (client_io, mut server_io) = tokio::io::duplex(1024);
+    let mut cache = CacheClient::from_stream(client_io, None);
+    let value: &[u8] = b"\xff\r\n\x00";
+
+    let server = tokio::spawn(async move {
+        let mset = b"*3\r\n$4\r\nMSET\r\n$3\r\nbin\r\n$4\r\n\xff\r\n\x00\r\n";
+        let mut buf = vec![0u8; mset.len()];
+        server_io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, mset);
+        server_io.write_all(b"+OK\r\n").await.unwrap();
+
+        let mget = b"*3\r\n$4\r\nMGET\r\n$3\r\nbin\r\n$7\r\nmissing\r\n";
+        let mut buf = vec![0u8; mget.len()];
+        server_io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, mget);
+        server_io.write_all(b"*2\r\n$4\r\n\xff\r\n\x00\r\n$-1\r\n").await.unwrap();
+    });
+
+    cache.mset_bytes(&[("bin", value)]).await.unwrap();
+    let values = cache.mget_bytes(&["bin", "missing"]).await.unwrap();
+    assert_eq!(values, vec![Some(value.to_vec()), None]);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_command_lengths_count_bytes() {
+    let (client_io, mut server_io) = tokio::io::duplex(1024);
+    let mut cache = CacheClient::from_stream(client_io, None);
+
+    let server = tokio::spawn(async move {
+        let expected = "*3\r\n$3\r\nSET\r\n$4\r\nclé\r\n$5\r\n€\r\n\r\n".as_bytes();
+        let mut buf = vec![0u8; expected.len()];
+        server_io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, expected);
+        server_io.write_all(b"+OK\r\n").await.unwrap();
+    });
+
+    cache.set("clé", "€\r\n", None).await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_hangup_is_connection_closed() {
+    // Cut off inside a bulk string, after a partial line, and between replies
+    for reply in [&b"$10\r\nhel"[..], b"$1", b""] {
+        let (client_io, mut server_io) = tokio::io::duplex(1024);
+        let mut cache = CacheClient::from_stream(client_io, None);
+
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let _ = server_io.read(&mut buf).await.unwrap();
+            server_io.write_all(reply).await.unwrap();
+        });
+
+        assert!(matches!(cache.get("key").await, Err(CacheError::ConnectionClosed)));
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+//! SquirrelDB Rust SDK - Cache TLS Tests
+//!
+//! Requires the `tls` feature and a TLS-enabled cache server. Set
+//! `SQUIRRELDB_CACHE_TLS_HOST` (and optionally `SQUIRRELDB_CACHE_TLS_PORT`
+//! and `SQUIRRELDB_CACHE_TLS_CA`) to run against one.
+
+#![cfg(feature = "tls")]
+
+use squirreldb_sdk::{CacheClient, CacheOptions};
+
+fn tls_options() -> Option<CacheOptions> {
+    let host = std::env::var("SQUIRRELDB_CACHE_TLS_HOST").ok()?;
+    let port = std::env::var("SQUIRRELDB_CACHE_TLS_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(6380);
+
+    Some(CacheOptions {
+        host,
+        port,
+        connect_timeout: None,
+        command_timeout: None,
+        inline_commands: false,
+        tcp_nodelay: true,
+        unix_socket: None,
+        tls: true,
+        ca_cert: std::env::var("SQUIRRELDB_CACHE_TLS_CA").ok().map(Into::into),
+    })
+}
+
+#[tokio::test]
+async fn test_tls_set_get_roundtrip() {
+    let Some(opts) = tls_options() else {
+        return;
+    };
+
+    let mut client = CacheClient::connect(Some(opts)).await.unwrap();
+    client.ping().await.unwrap();
+    client.set("sdk-rust:tls", "secure", Some(60)).await.unwrap();
+    assert_eq!(client.get("sdk-rust:tls").await.unwrap(), Some("secure".to_string()));
+    client.del("sdk-rust:tls").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_tls_rejects_plaintext_server() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        use tokio::io::AsyncWriteExt;
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let _ = socket.write_all(b"+OK\r\n").await;
+    });
+
+    let result = CacheClient::connect(Some(CacheOptions {
+        host: "localhost".to_string(),
+        port,
+        connect_timeout: None,
+        command_timeout: None,
+        inline_commands: false,
+        tcp_nodelay: true,
+        unix_socket: None,
+        tls: true,
+        ca_cert: None,
+    }))
+    .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,1946 @@
+//! SquirrelDB Rust SDK - Client Tests
+
+use std::time::Duration;
+
+use squirreldb_sdk::{
+    field, table, ChangeEvent, ChangesOptions, ClientMessage, ConnectOptions,
+    CreateCollectionOptions, Encoding, Error, Feature, Overflow, ReconnectPolicy, SquirrelDB,
+    SubEvent, SubscribeOptions, Subscription, TransactionOp, UpdateSpec, MAX_MESSAGE_SIZE,
+};
+use futures::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+mod common;
+use common::{
+    accept_handshake, handshake, mock_server, mock_server_with_client_flags, read_raw_frame,
+    read_request, write_response,
+};
+
+#[tokio::test]
+async fn test_connect_with_supported_features() {
+    let (port, _server) = mock_server(0x01 | 0x04 | 0x08).await;
+
+    let opts = ConnectOptions::new("127.0.0.1", port)
+        .require_features(&[Feature::StructuredQuery, Feature::Compression]);
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+
+    assert_eq!(client.session_id().as_bytes(), &[7u8; 16]);
+    assert_eq!(client.server_version(), 0x01);
+    assert!(client.server_flags().supports(Feature::Compression));
+    assert!(!client.server_flags().supports(Feature::JsonFallback));
+    assert_eq!(client.negotiated_encoding(), Encoding::MessagePack);
+}
+
+#[tokio::test]
+async fn test_negotiates_json_without_messagepack() {
+    let (port, _server) = mock_server(0x02).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+
+    assert_eq!(client.negotiated_encoding(), Encoding::Json);
+    assert!(client.server_flags().json_fallback);
+}
+
+#[tokio::test]
+async fn test_connect_fails_on_missing_features() {
+    let (port, _server) = mock_server(0x01 | 0x04).await;
+
+    let opts = ConnectOptions::new("127.0.0.1", port)
+        .require_features(&[Feature::StructuredQuery, Feature::Compression]);
+
+    match SquirrelDB::connect_with_options(opts).await {
+        Err(Error::UnsupportedServer { missing }) => {
+            assert_eq!(missing, vec![Feature::Compression]);
+        }
+        other => panic!("expected UnsupportedServer, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn test_keep_alive_pings_when_idle() {
+    let (port, server) = mock_server(0x01).await;
+
+    let opts = ConnectOptions::new("127.0.0.1", port)
+        .with_keep_alive(Duration::from_millis(50), Duration::from_secs(1));
+    let _client = SquirrelDB::connect_with_options(opts).await.unwrap();
+
+    let mut socket = server.await.unwrap();
+    let ping = tokio::time::timeout(Duration::from_secs(2), read_request(&mut socket))
+        .await
+        .unwrap();
+    let ClientMessage::Ping { id } = ping else {
+        panic!("expected ping, got {:?}", ping);
+    };
+    assert!(id.starts_with("keepalive-"));
+
+    write_response(&mut socket, serde_json::json!({"type": "pong", "id": id})).await;
+    let ping = tokio::time::timeout(Duration::from_secs(2), read_request(&mut socket))
+        .await
+        .unwrap();
+    assert!(matches!(ping, ClientMessage::Ping { .. }));
+}
+
+#[tokio::test]
+async fn test_keep_alive_times_out_dead_connection() {
+    let (port, server) = mock_server(0x01).await;
+
+    let opts = ConnectOptions::new("127.0.0.1", port)
+        .with_keep_alive(Duration::from_millis(50), Duration::from_millis(50));
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let _socket = server.await.unwrap();
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(2),
+        client.query::<serde_json::Value>("db.table(\"users\").run()"),
+    )
+    .await
+    .unwrap();
+    assert!(matches!(result, Err(Error::Timeout)));
+}
+
+#[tokio::test]
+async fn test_request_after_missed_ping_fails() {
+    let (port, server) = mock_server(0x01).await;
+
+    let opts = ConnectOptions::new("127.0.0.1", port)
+        .with_keep_alive(Duration::from_millis(50), Duration::from_millis(50));
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    // Keep the socket open but never answer, like a half-open connection
+    let _socket = server.await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while client.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(2),
+        client.query::<serde_json::Value>("db.table(\"users\").run()"),
+    )
+    .await
+    .unwrap();
+    assert!(matches!(result, Err(Error::ChannelClosed)));
+}
+
+#[tokio::test]
+async fn test_close_unsubscribes_and_shuts_down() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+
+        let unsubscribe = read_request(&mut socket).await;
+        let mut rest = Vec::new();
+        socket.read_to_end(&mut rest).await.unwrap();
+        (id, unsubscribe, rest)
+    });
+
+    let subscription = client.subscribe("db.table(\"users\").changes()").await.unwrap();
+    drop(subscription);
+    tokio::time::timeout(Duration::from_secs(2), client.close())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let (sub_id, unsubscribe, rest) = server.await.unwrap();
+    assert!(matches!(unsubscribe, ClientMessage::Unsubscribe { id } if id == sub_id));
+    assert!(rest.is_empty());
+}
+
+#[tokio::test]
+async fn test_dropped_subscription_unsubscribes() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        (id, read_request(&mut socket).await)
+    });
+
+    let subscription = client.subscribe("db.table(\"users\").changes()").await.unwrap();
+    drop(subscription);
+
+    let (sub_id, unsubscribe) = tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(unsubscribe, ClientMessage::Unsubscribe { id } if id == sub_id));
+}
+
+#[tokio::test]
+async fn test_drop_without_close_does_not_panic() {
+    let (port, _server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    drop(client);
+}
+
+#[tokio::test]
+async fn test_max_in_flight_rejects_when_full() {
+    let (port, server) = mock_server(0x01).await;
+    let opts = ConnectOptions::new("127.0.0.1", port).with_max_in_flight(1);
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let _socket = server.await.unwrap();
+
+    assert_eq!(client.in_flight(), 0);
+    let first = client.query::<serde_json::Value>("db.table(\"a\").run()");
+    tokio::pin!(first);
+    tokio::select! {
+        _ = &mut first => panic!("mock server never answers"),
+        _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+    }
+
+    assert_eq!(client.in_flight(), 1);
+    let second = client.query::<serde_json::Value>("db.table(\"b\").run()").await;
+    assert!(matches!(second, Err(Error::Overloaded)));
+}
+
+#[tokio::test]
+async fn test_subscribe_query_sends_structured_changes() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut queries = Vec::new();
+        for _ in 0..2 {
+            let ClientMessage::Subscribe { id, query } = read_request(&mut socket).await else {
+                panic!("expected subscribe");
+            };
+            write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+            queries.push(serde_json::from_str::<serde_json::Value>(&query).unwrap());
+        }
+        queries
+    });
+
+    let query = table("messages").find(field("room").eq("general"));
+    let _default = client.subscribe_query(&query).await.unwrap();
+    let query = query.changes(Some(ChangesOptions { include_initial: false }));
+    let _live_only = client.subscribe_query(&query).await.unwrap();
+
+    let queries = server.await.unwrap();
+    assert_eq!(queries[0]["table"], "messages");
+    assert_eq!(queries[0]["filter"]["room"]["$eq"], "general");
+    assert_eq!(queries[0]["changes"]["includeInitial"], true);
+    assert_eq!(queries[1]["changes"]["includeInitial"], false);
+}
+
+#[tokio::test]
+async fn test_subscription_lifecycle_events() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        write_response(
+            &mut socket,
+            serde_json::json!({
+                "type": "change",
+                "id": id,
+                "change": {
+                    "type": "insert",
+                    "new": {
+                        "id": "00000000-0000-0000-0000-000000000001",
+                        "collection": "users",
+                        "data": {"name": "Alice"},
+                        "created_at": "2024-01-01T00:00:00Z",
+                        "updated_at": "2024-01-01T00:00:00Z"
+                    }
+                }
+            }),
+        )
+        .await;
+        write_response(
+            &mut socket,
+            serde_json::json!({"type": "error", "id": id, "error": "feed interrupted"}),
+        )
+        .await;
+    });
+
+    let mut subscription = client.subscribe("db.table(\"users\").changes()").await.unwrap();
+    server.await.unwrap();
+
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    assert!(matches!(
+        subscription.next().await,
+        Some(SubEvent::Change(ChangeEvent::Insert { .. }))
+    ));
+    assert!(matches!(subscription.next().await, Some(SubEvent::Error(e)) if e == "feed interrupted"));
+    assert!(matches!(subscription.next().await, Some(SubEvent::Closed)));
+    assert!(subscription.next().await.is_none());
+}
+
+fn change_json(id: &str, kind: &str, doc: &str, name: &str) -> serde_json::Value {
+    let document = serde_json::json!({
+        "id": doc,
+        "collection": "users",
+        "data": {"name": name},
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": "2024-01-01T00:00:00Z"
+    });
+    let change = match kind {
+        "initial" => serde_json::json!({"type": "initial", "document": document}),
+        _ => serde_json::json!({"type": kind, "new": document}),
+    };
+    serde_json::json!({"type": "change", "id": id, "change": change})
+}
+
+#[tokio::test]
+async fn test_subscribe_with_snapshot_splits_initial_from_live() {
+    let (port, server) = mock_server(0x01 | 0x20).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        let a = "00000000-0000-0000-0000-000000000001";
+        let b = "00000000-0000-0000-0000-000000000002";
+        write_response(&mut socket, change_json(&id, "initial", a, "Alice")).await;
+        write_response(&mut socket, change_json(&id, "initial", b, "Bob")).await;
+        write_response(&mut socket, serde_json::json!({"type": "synced", "id": id})).await;
+        write_response(&mut socket, change_json(&id, "insert", a, "Carol")).await;
+        socket
+    });
+
+    let (snapshot, mut subscription) = client
+        .subscribe_with_snapshot("db.table(\"users\").changes({includeInitial: true})")
+        .await
+        .unwrap();
+    let _socket = server.await.unwrap();
+
+    let names: Vec<_> = snapshot.iter().map(|d| d.data["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["Alice", "Bob"]);
+    assert!(matches!(
+        subscription.next().await,
+        Some(SubEvent::Change(ChangeEvent::Insert { new })) if new.data["name"] == "Carol"
+    ));
+}
+
+#[tokio::test]
+async fn test_subscribe_with_snapshot_ends_at_first_live_change() {
+    let (port, server) = mock_server(0x01 | 0x20).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        let a = "00000000-0000-0000-0000-000000000001";
+        write_response(&mut socket, change_json(&id, "initial", a, "Alice")).await;
+        write_response(&mut socket, change_json(&id, "insert", a, "Bob")).await;
+        socket
+    });
+
+    let (snapshot, mut subscription) = client
+        .subscribe_with_snapshot("db.table(\"users\").changes({includeInitial: true})")
+        .await
+        .unwrap();
+    let socket = server.await.unwrap();
+
+    assert_eq!(snapshot.len(), 1);
+    assert!(matches!(
+        subscription.next().await,
+        Some(SubEvent::Change(ChangeEvent::Insert { new })) if new.data["name"] == "Bob"
+    ));
+    drop(socket);
+    assert!(matches!(subscription.next().await, Some(SubEvent::Closed)));
+}
+
+#[tokio::test]
+async fn test_subscribe_with_snapshot_fails_if_feed_errors() {
+    let (port, server) = mock_server(0x01 | 0x20).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        write_response(&mut socket, serde_json::json!({"type": "error", "id": id, "error": "table dropped"})).await;
+        socket
+    });
+
+    let result = client.subscribe_with_snapshot("db.table(\"users\").changes()").await;
+    assert!(matches!(result, Err(Error::Server { message, .. }) if message == "table dropped"));
+}
+
+#[tokio::test]
+async fn test_dropping_client_ends_subscription() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        socket
+    });
+
+    let mut subscription = client.subscribe("db.table(\"users\").changes()").await.unwrap();
+    let mut socket = server.await.unwrap();
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    drop(client);
+
+    let event = tokio::time::timeout(Duration::from_secs(2), subscription.next())
+        .await
+        .unwrap();
+    assert!(matches!(event, Some(SubEvent::Closed) | None));
+    // The socket is shut down even though the subscription is still alive
+    let mut rest = Vec::new();
+    tokio::time::timeout(Duration::from_secs(2), socket.read_to_end(&mut rest))
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_subscribe_with_snapshot_needs_synced_support() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let _socket = server.await.unwrap();
+
+    let result = client.subscribe_with_snapshot("db.table(\"users\").changes()").await;
+    assert!(matches!(result, Err(Error::UnsupportedServer { missing }) if missing == [Feature::SnapshotSync]));
+}
+
+/// Subscribe, push `changes` inserts, then answer one ping so the test can
+/// tell when every change has been dispatched
+async fn flood_subscription(
+    opts: SubscribeOptions,
+    changes: usize,
+) -> (SquirrelDB, Subscription, tokio::task::JoinHandle<TcpStream>) {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        for n in 0..changes {
+            let change = serde_json::json!({
+                "type": "change",
+                "id": id,
+                "change": {
+                    "type": "insert",
+                    "new": {
+                        "id": "00000000-0000-0000-0000-000000000001",
+                        "collection": "events",
+                        "data": {"n": n},
+                        "created_at": "2024-01-01T00:00:00Z",
+                        "updated_at": "2024-01-01T00:00:00Z"
+                    }
+                }
+            });
+            write_response(&mut socket, change).await;
+        }
+        let ClientMessage::Ping { id } = read_request(&mut socket).await else {
+            panic!("expected ping");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "pong", "id": id})).await;
+        socket
+    });
+
+    let subscription = client
+        .subscribe_with_options("db.table(\"events\").changes()", opts)
+        .await
+        .unwrap();
+    (client, subscription, server)
+}
+
+fn change_number(event: Option<SubEvent>) -> u64 {
+    match event {
+        Some(SubEvent::Change(ChangeEvent::Insert { new })) => new.data["n"].as_u64().unwrap(),
+        other => panic!("expected change, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_subscription_buffer_drop_oldest() {
+    let opts = SubscribeOptions { buffer: 2, overflow: Overflow::DropOldest };
+    let (client, mut subscription, _server) = flood_subscription(opts, 5).await;
+    client.ping().await.unwrap();
+
+    // The dropped changes came before the buffered ones
+    assert!(matches!(subscription.next().await, Some(SubEvent::Lagged(3))));
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    assert_eq!(change_number(subscription.next().await), 3);
+    assert_eq!(change_number(subscription.next().await), 4);
+}
+
+#[tokio::test]
+async fn test_subscription_buffer_drop_newest() {
+    let opts = SubscribeOptions { buffer: 2, overflow: Overflow::DropNewest };
+    let (client, mut subscription, _server) = flood_subscription(opts, 5).await;
+    client.ping().await.unwrap();
+
+    // The dropped changes came after the buffered ones
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    assert_eq!(change_number(subscription.next().await), 0);
+    assert_eq!(change_number(subscription.next().await), 1);
+    assert!(matches!(subscription.next().await, Some(SubEvent::Lagged(3))));
+}
+
+#[tokio::test]
+async fn test_subscription_buffer_block() {
+    let opts = SubscribeOptions { buffer: 1, overflow: Overflow::Block };
+    let (client, mut subscription, _server) = flood_subscription(opts, 3).await;
+
+    // The reader is stuck on the second change, so the pong can't get through
+    let ping = client.ping();
+    tokio::pin!(ping);
+    assert!(tokio::time::timeout(Duration::from_millis(100), &mut ping).await.is_err());
+
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    for n in 0..3 {
+        assert_eq!(change_number(subscription.next().await), n);
+    }
+    ping.await.unwrap();
+}
+
+/// Answer a subscribe on `socket` and send one insert change numbered `n`
+async fn serve_subscription(socket: &mut TcpStream, n: u64) {
+    let ClientMessage::Subscribe { id, .. } = read_request(socket).await else {
+        panic!("expected subscribe");
+    };
+    write_response(socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+    let change = serde_json::json!({
+        "type": "change",
+        "id": id,
+        "change": {
+            "type": "insert",
+            "new": {
+                "id": "00000000-0000-0000-0000-000000000001",
+                "collection": "events",
+                "data": {"n": n},
+                "created_at": "2024-01-01T00:00:00Z",
+                "updated_at": "2024-01-01T00:00:00Z"
+            }
+        }
+    });
+    write_response(socket, change).await;
+}
+
+#[tokio::test]
+async fn test_resilient_subscription_reconnects() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = tokio::spawn(async move {
+        let mut first = accept_handshake(&listener, 0x01).await;
+        serve_subscription(&mut first, 1).await;
+        drop(first);
+
+        let mut second = accept_handshake(&listener, 0x01).await;
+        serve_subscription(&mut second, 2).await;
+        second
+    });
+
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut subscription = client
+        .subscribe_resilient("db.table(\"events\").changes()")
+        .await
+        .unwrap();
+
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    assert_eq!(change_number(subscription.next().await), 1);
+    assert!(matches!(subscription.next().await, Some(SubEvent::Reconnected)));
+    assert_eq!(client.metrics().reconnects, 1);
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    assert_eq!(change_number(subscription.next().await), 2);
+    let _socket = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_resilient_subscription_ends_when_server_unsubscribes() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener, 0x01).await;
+        let ClientMessage::Subscribe { id, .. } = read_request(&mut socket).await else {
+            panic!("expected subscribe");
+        };
+        write_response(&mut socket, serde_json::json!({"type": "subscribed", "id": id})).await;
+        write_response(&mut socket, serde_json::json!({"type": "unsubscribed", "id": id})).await;
+        // Keep the connection up and the listener open
+        (listener, socket)
+    });
+
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut subscription = client
+        .subscribe_resilient("db.table(\"events\").changes()")
+        .await
+        .unwrap();
+
+    let events = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut events = Vec::new();
+        while let Some(event) = subscription.next().await {
+            events.push(event);
+        }
+        events
+    })
+    .await
+    .expect("the subscription should end without redialing");
+    assert!(matches!(events.as_slice(), [SubEvent::Ready, SubEvent::Closed]));
+    assert!(client.is_connected());
+    assert_eq!(client.metrics().reconnects, 0);
+    let _server = server.await.unwrap();
+}
+
+/// Serve one resilient subscription, then report whether the client dialed
+/// again within a grace period after `release` closes or drops it
+async fn redials_after_release<F, Fut>(release: F) -> bool
+where
+    F: FnOnce(SquirrelDB) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener, 0x01).await;
+        serve_subscription(&mut socket, 1).await;
+        (listener, socket)
+    });
+
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut subscription = client
+        .subscribe_resilient("db.table(\"events\").changes()")
+        .await
+        .unwrap();
+    let (listener, _socket) = server.await.unwrap();
+    assert!(matches!(subscription.next().await, Some(SubEvent::Ready)));
+    assert_eq!(change_number(subscription.next().await), 1);
+
+    release(client).await;
+    let events = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut events = Vec::new();
+        while let Some(event) = subscription.next().await {
+            events.push(event);
+        }
+        events
+    })
+    .await
+    .expect("the subscription should end with its client");
+    assert!(matches!(events.as_slice(), [SubEvent::Closed]));
+
+    tokio::time::timeout(Duration::from_millis(300), listener.accept()).await.is_ok()
+}
+
+#[tokio::test]
+async fn test_resilient_subscription_ends_when_client_dropped() {
+    assert!(!redials_after_release(|client| async move { drop(client) }).await);
+}
+
+#[tokio::test]
+async fn test_resilient_subscription_ends_when_client_closed() {
+    assert!(!redials_after_release(|client| async move { client.close().await.unwrap() }).await);
+}
+
+#[tokio::test]
+async fn test_resilient_subscription_gives_up() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = tokio::spawn(async move {
+        let mut socket = accept_handshake(&listener, 0x01).await;
+        serve_subscription(&mut socket, 1).await;
+        // Stop listening before hanging up so every redial is refused
+        drop(listener);
+        drop(socket);
+    });
+
+    let policy = ReconnectPolicy {
+        max_attempts: 2,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(10),
+    };
+    let opts = ConnectOptions::new("127.0.0.1", port).with_reconnect(policy);
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let mut subscription = client
+        .subscribe_resilient("db.table(\"events\").changes()")
+        .await
+        .unwrap();
+    server.await.unwrap();
+
+    let events = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut events = Vec::new();
+        while let Some(event) = subscription.next().await {
+            events.push(event);
+        }
+        events
+    })
+    .await
+    .expect("the subscription should end after the last attempt");
+    assert!(matches!(events.as_slice(), [SubEvent::Ready, SubEvent::Change(_), SubEvent::Closed]));
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum VenueKind {
+    Bar { open_late: bool },
+    Cafe,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Venue {
+    name: String,
+    #[serde(flatten)]
+    kind: VenueKind,
+    rating: Option<f32>,
+}
+
+#[tokio::test]
+async fn test_insert_typed_is_stable_across_encodings() {
+    let venue = Venue {
+        name: "Kaffeehaus".to_string(),
+        kind: VenueKind::Bar { open_late: true },
+        rating: None,
+    };
+
+    let mut received = Vec::new();
+    for force_json in [false, true] {
+        let (port, server) = mock_server(0x01 | 0x02).await;
+        let mut opts = ConnectOptions::new("127.0.0.1", port);
+        if force_json {
+            opts = opts.force_json();
+        }
+        let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+        let mut socket = server.await.unwrap();
+
+        let call = tokio::spawn({
+            let venue = venue.clone();
+            async move { client.insert_typed("venues", &venue).await }
+        });
+        let ClientMessage::Insert { id, data, .. } = read_request(&mut socket).await else {
+            panic!("expected insert");
+        };
+        let document = serde_json::json!({
+            "id": "00000000-0000-0000-0000-000000000001",
+            "collection": "venues",
+            "data": data,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z"
+        });
+        write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": document})).await;
+
+        let stored: Venue = call.await.unwrap().unwrap().into_typed().unwrap();
+        assert_eq!(stored, venue);
+        received.push(data);
+    }
+
+    assert_eq!(received[0], received[1]);
+    assert_eq!(
+        received[0],
+        serde_json::json!({"name": "Kaffeehaus", "kind": "bar", "open_late": true, "rating": null})
+    );
+}
+
+#[tokio::test]
+async fn test_server_advertised_message_size_limit() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut handshake = [0u8; 8];
+        socket.read_exact(&mut handshake).await.unwrap();
+        assert_ne!(handshake[5] & 0x10, 0);
+
+        socket.write_u8(0x00).await.unwrap();
+        socket.write_u8(0x01).await.unwrap();
+        socket.write_u8(0x01 | 0x10).await.unwrap();
+        socket.write_all(&[7u8; 16]).await.unwrap();
+        socket.write_u32(256).await.unwrap();
+        socket.flush().await.unwrap();
+        socket
+    });
+
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+    assert_eq!(client.max_message_size(), 256);
+    assert!(client.server_flags().supports(Feature::MessageSizeLimit));
+
+    // Fits the client's own limit but not the server's, so it is never sent
+    let result = client.insert("users", serde_json::json!({"bio": "x".repeat(512)})).await;
+    assert!(matches!(result, Err(Error::MessageTooLarge { max: 256, .. })));
+
+    let ping = tokio::spawn(async move { client.ping().await });
+    let request = read_request(&mut socket).await;
+    assert_eq!(request.kind(), "ping");
+    write_response(&mut socket, serde_json::json!({"type": "pong", "id": request.id()})).await;
+    ping.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_oversized_request_can_be_retried_under_same_id() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut handshake = [0u8; 8];
+        socket.read_exact(&mut handshake).await.unwrap();
+        socket.write_u8(0x00).await.unwrap();
+        socket.write_u8(0x01).await.unwrap();
+        socket.write_u8(0x01 | 0x10).await.unwrap();
+        socket.write_all(&[7u8; 16]).await.unwrap();
+        socket.write_u32(256).await.unwrap();
+        socket.flush().await.unwrap();
+        socket
+    });
+
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let oversized = format!("db.table(\"{}\").run()", "x".repeat(512));
+    let result = client.query_with_id::<serde_json::Value>("trace-1", &oversized).await;
+    assert!(matches!(result, Err(Error::MessageTooLarge { .. })));
+
+    let retry = tokio::spawn(async move {
+        client
+            .query_with_id::<serde_json::Value>("trace-1", "db.table(\"users\").run()")
+            .await
+    });
+    let request = read_request(&mut socket).await;
+    assert_eq!(request.id(), "trace-1");
+    write_response(&mut socket, serde_json::json!({"type": "result", "id": "trace-1", "data": []})).await;
+    assert_eq!(retry.await.unwrap().unwrap(), serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_max_message_size_defaults_to_client_limit() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let _socket = server.await.unwrap();
+    assert_eq!(client.max_message_size(), MAX_MESSAGE_SIZE);
+}
+
+#[tokio::test]
+async fn test_oversized_frame_fails_fast() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let _query = read_request(&mut socket).await;
+        socket.write_u32(0x8000_0000).await.unwrap();
+        socket.flush().await.unwrap();
+        socket
+    });
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(2),
+        client.query::<serde_json::Value>("db.table(\"users\").run()"),
+    )
+    .await
+    .unwrap();
+    assert!(matches!(result, Err(Error::Protocol(msg)) if msg.contains("too large")));
+    let _socket = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_list_collections_cache() {
+    let (port, server) = mock_server(0x01).await;
+    let opts = ConnectOptions::new("127.0.0.1", port).with_collections_cache(Duration::from_secs(60));
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        for count in [1, 2] {
+            let ClientMessage::ListCollections { id } = read_request(&mut socket).await else {
+                panic!("expected list collections");
+            };
+            write_response(
+                &mut socket,
+                serde_json::json!({
+                    "type": "result",
+                    "id": id,
+                    "data": [{"name": "users", "document_count": count}]
+                }),
+            )
+            .await;
+        }
+        socket
+    });
+
+    let first = client.list_collections().await.unwrap();
+    let cached = client.list_collections().await.unwrap();
+    assert_eq!(first, cached);
+    assert_eq!(cached[0].document_count, Some(1));
+
+    let refreshed = client.refresh_collections().await.unwrap();
+    assert_eq!(refreshed[0].document_count, Some(2));
+    assert_eq!(client.list_collections().await.unwrap(), refreshed);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_create_and_drop_collection() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::CreateCollection { id, name, options } = read_request(&mut socket).await
+        else {
+            panic!("expected create collection");
+        };
+        assert_eq!(name, "users");
+        assert_eq!(options.indexes[0].field, "email");
+        assert!(options.indexes[0].unique);
+        write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": null}))
+            .await;
+
+        let ClientMessage::DropCollection { id, name } = read_request(&mut socket).await else {
+            panic!("expected drop collection");
+        };
+        assert_eq!(name, "missing");
+        write_response(
+            &mut socket,
+            serde_json::json!({"type": "error", "id": id, "error": "collection not found: missing"}),
+        )
+        .await;
+        socket
+    });
+
+    let options = CreateCollectionOptions::default().index("email", true);
+    client.create_collection("users", options).await.unwrap();
+    let dropped = client.drop_collection("missing").await;
+    assert!(matches!(dropped, Err(Error::Server { message: msg, .. }) if msg.contains("not found")));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_index_management() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::CreateIndex { id, collection, field, unique } =
+            read_request(&mut socket).await
+        else {
+            panic!("expected create index");
+        };
+        assert_eq!((collection.as_str(), field.as_str(), unique), ("users", "email", true));
+        write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": null}))
+            .await;
+
+        let ClientMessage::ListIndexes { id, collection } = read_request(&mut socket).await else {
+            panic!("expected list indexes");
+        };
+        assert_eq!(collection, "users");
+        write_response(
+            &mut socket,
+            serde_json::json!({
+                "type": "result",
+                "id": id,
+                "data": [{"field": "email", "unique": true, "type": "btree"}]
+            }),
+        )
+        .await;
+
+        let ClientMessage::DropIndex { id, field, .. } = read_request(&mut socket).await else {
+            panic!("expected drop index");
+        };
+        assert_eq!(field, "email");
+        write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": null}))
+            .await;
+        socket
+    });
+
+    client.create_index("users", "email", true).await.unwrap();
+    let indexes = client.list_indexes("users").await.unwrap();
+    assert_eq!(indexes.len(), 1);
+    assert_eq!(indexes[0].field, "email");
+    assert!(indexes[0].unique);
+    assert_eq!(indexes[0].index_type, "btree");
+    client.drop_index("users", "email").await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_transaction_sends_buffered_ops() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let from = uuid::Uuid::new_v4();
+    let to = uuid::Uuid::new_v4();
+    let server = tokio::spawn(async move {
+        let ClientMessage::Transaction { id, ops } = read_request(&mut socket).await else {
+            panic!("expected transaction");
+        };
+        write_response(
+            &mut socket,
+            serde_json::json!({"type": "error", "id": id, "error": "insufficient funds; rolled back"}),
+        )
+        .await;
+        ops
+    });
+
+    let result = client
+        .transaction(|tx| {
+            tx.update("accounts", from, serde_json::json!({"balance": -50}))
+                .update("accounts", to, serde_json::json!({"balance": 50}));
+        })
+        .await;
+    assert!(matches!(result, Err(Error::Server { message: msg, .. }) if msg.contains("rolled back")));
+
+    let ops = server.await.unwrap();
+    assert_eq!(ops.len(), 2);
+    assert!(matches!(&ops[0], TransactionOp::Update { document_id, .. } if *document_id == from));
+    assert!(matches!(&ops[1], TransactionOp::Update { document_id, .. } if *document_id == to));
+}
+
+#[tokio::test]
+async fn test_caller_supplied_request_id() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Query { id, .. } = read_request(&mut socket).await else {
+            panic!("expected query");
+        };
+        write_response(
+            &mut socket,
+            serde_json::json!({"type": "error", "id": id, "error": "table not found"}),
+        )
+        .await;
+        (id, socket)
+    });
+
+    let result = client
+        .query_with_id::<serde_json::Value>("trace-abc", "db.table(\"missing\").run()")
+        .await;
+    let (id, _socket) = server.await.unwrap();
+    assert_eq!(id, "trace-abc");
+    assert!(
+        matches!(result, Err(Error::Server { message: msg, .. }) if msg.contains("table not found") && msg.contains("trace-abc"))
+    );
+}
+
+#[tokio::test]
+async fn test_query_page_reads_total() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let replies = [
+            serde_json::json!({"documents": [{"name": "Alice"}, {"name": "Bob"}], "total": 42}),
+            serde_json::json!([{"name": "Carol"}]),
+        ];
+        let mut queries = Vec::new();
+        for data in replies {
+            let ClientMessage::Query { id, query } = read_request(&mut socket).await else {
+                panic!("expected query");
+            };
+            write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": data})).await;
+            queries.push(serde_json::from_str::<serde_json::Value>(&query).unwrap());
+        }
+        queries
+    });
+
+    let query = table("users").sort("name", squirreldb_sdk::SortDirection::Asc).limit(2);
+    let page = client.query_page::<serde_json::Value>(&query).await.unwrap();
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(page.total, Some(42));
+
+    let page = client.query_page::<serde_json::Value>(&query.skip(2)).await.unwrap();
+    assert_eq!(page.items, [serde_json::json!({"name": "Carol"})]);
+    assert_eq!(page.total, None);
+
+    let queries = server.await.unwrap();
+    assert_eq!(queries[0]["limit"], 2);
+    assert_eq!(queries[1]["skip"], 2);
+}
+
+#[tokio::test]
+async fn test_query_cache_serves_repeats_until_write() {
+    let (port, server) = mock_server(0x01).await;
+    let opts = ConnectOptions::new("127.0.0.1", port).with_query_cache(Duration::from_secs(60), 8);
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut kinds = Vec::new();
+        for n in 0..3 {
+            let msg = read_request(&mut socket).await;
+            let data = match &msg {
+                ClientMessage::Insert { .. } => serde_json::json!({
+                    "id": "00000000-0000-0000-0000-000000000001",
+                    "collection": "users",
+                    "data": {"name": "Bob"},
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-01T00:00:00Z"
+                }),
+                _ => serde_json::json!([{"version": n}]),
+            };
+            write_response(&mut socket, serde_json::json!({"type": "result", "id": msg.id(), "data": data})).await;
+            kinds.push(msg.kind());
+        }
+        kinds
+    });
+
+    let query = table("users").compile().unwrap();
+    let first: serde_json::Value = client.query(&query).await.unwrap();
+    let cached: serde_json::Value = client.query(&query).await.unwrap();
+    assert_eq!(first, cached);
+
+    client.insert("users", serde_json::json!({"name": "Bob"})).await.unwrap();
+    let fresh: serde_json::Value = client.query(&query).await.unwrap();
+    assert_eq!(fresh, serde_json::json!([{"version": 2}]));
+
+    assert_eq!(server.await.unwrap(), ["query", "insert", "query"]);
+}
+
+#[tokio::test]
+async fn test_query_cache_drops_result_fetched_during_write() {
+    let (port, server) = mock_server(0x01).await;
+    let opts = ConnectOptions::new("127.0.0.1", port).with_query_cache(Duration::from_secs(60), 8);
+    let client = std::sync::Arc::new(SquirrelDB::connect_with_options(opts).await.unwrap());
+    let mut socket = server.await.unwrap();
+    let query = table("users").compile().unwrap();
+
+    let insert = tokio::spawn({
+        let client = client.clone();
+        async move { client.insert("users", serde_json::json!({"name": "Bob"})).await }
+    });
+    let insert_request = read_request(&mut socket).await;
+    assert_eq!(insert_request.kind(), "insert");
+
+    // A query sent while the insert is in flight sees the old data
+    let stale = tokio::spawn({
+        let client = client.clone();
+        let query = query.clone();
+        async move { client.query::<serde_json::Value>(&query).await }
+    });
+    let query_request = read_request(&mut socket).await;
+    write_response(
+        &mut socket,
+        serde_json::json!({"type": "result", "id": query_request.id(), "data": [{"version": 0}]}),
+    )
+    .await;
+    stale.await.unwrap().unwrap();
+    write_response(
+        &mut socket,
+        serde_json::json!({
+            "type": "result",
+            "id": insert_request.id(),
+            "data": {
+                "id": "00000000-0000-0000-0000-000000000001",
+                "collection": "users",
+                "data": {"name": "Bob"},
+                "created_at": "2024-01-01T00:00:00Z",
+                "updated_at": "2024-01-01T00:00:00Z"
+            }
+        }),
+    )
+    .await;
+    insert.await.unwrap().unwrap();
+
+    let fresh = tokio::spawn({
+        let client = client.clone();
+        async move { client.query::<serde_json::Value>(&query).await }
+    });
+    let request = read_request(&mut socket).await;
+    assert_eq!(request.kind(), "query");
+    write_response(
+        &mut socket,
+        serde_json::json!({"type": "result", "id": request.id(), "data": [{"version": 1}]}),
+    )
+    .await;
+    assert_eq!(fresh.await.unwrap().unwrap(), serde_json::json!([{"version": 1}]));
+}
+
+#[tokio::test]
+async fn test_query_with_encoding_overrides_one_request() {
+    let (port, server) = mock_server(0x01 | 0x02).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    assert_eq!(client.negotiated_encoding(), Encoding::MessagePack);
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let (encoding, payload) = read_raw_frame(&mut socket).await;
+        let msg: ClientMessage = serde_json::from_slice(&payload).unwrap();
+
+        let reply = serde_json::to_vec(&serde_json::json!({"type": "result", "id": msg.id(), "data": [1, 2]})).unwrap();
+        socket.write_u32(reply.len() as u32 + 2).await.unwrap();
+        socket.write_all(&[0x02, Encoding::Json as u8]).await.unwrap();
+        socket.write_all(&reply).await.unwrap();
+
+        // The next request is back on the negotiated encoding
+        let ping = read_request(&mut socket).await;
+        write_response(&mut socket, serde_json::json!({"type": "pong", "id": ping.id()})).await;
+        encoding
+    });
+
+    let rows: Vec<i64> = client
+        .query_with_encoding("db.table(\"users\").run()", Encoding::Json)
+        .await
+        .unwrap();
+    assert_eq!(rows, [1, 2]);
+    client.ping().await.unwrap();
+    assert_eq!(server.await.unwrap(), Encoding::Json as u8);
+}
+
+#[tokio::test]
+async fn test_query_with_encoding_requires_server_support() {
+    let (port, _server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+
+    let result = client
+        .query_with_encoding::<serde_json::Value>("db.table(\"users\").run()", Encoding::Json)
+        .await;
+    assert!(matches!(result, Err(Error::UnsupportedServer { missing }) if missing == [Feature::JsonFallback]));
+}
+
+#[tokio::test]
+async fn test_delete_reports_whether_document_existed() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let replies = [
+            serde_json::json!({"type": "result", "data": {
+                "id": "00000000-0000-0000-0000-000000000001",
+                "collection": "users",
+                "data": {},
+                "created_at": "2024-01-01T00:00:00Z",
+                "updated_at": "2024-01-01T00:00:00Z"
+            }}),
+            serde_json::json!({"type": "result", "data": {"deleted": false}}),
+            serde_json::json!({"type": "result", "data": null}),
+            serde_json::json!({"type": "error", "error": "no such document", "code": "NOT_FOUND"}),
+            serde_json::json!({"type": "error", "error": "permission denied", "code": "FORBIDDEN"}),
+        ];
+        for mut reply in replies {
+            let msg = read_request(&mut socket).await;
+            assert_eq!(msg.kind(), "delete");
+            reply["id"] = msg.id().into();
+            write_response(&mut socket, reply).await;
+        }
+    });
+
+    let id = uuid::Uuid::nil();
+    assert!(client.delete("users", id).await.unwrap());
+    assert!(!client.delete("users", id).await.unwrap());
+    assert!(!client.delete("users", id).await.unwrap());
+    assert!(!client.delete("users", id).await.unwrap());
+    assert!(matches!(
+        client.delete("users", id).await,
+        Err(Error::Server { code: Some(c), .. }) if c == "FORBIDDEN"
+    ));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_modify_sends_update_operators() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Modify { id, collection, update, .. } = read_request(&mut socket).await else {
+            panic!("expected modify");
+        };
+        assert_eq!(collection, "posts");
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            serde_json::json!({"$addToSet": {"tags": "rust"}})
+        );
+        let document = serde_json::json!({
+            "id": "00000000-0000-0000-0000-000000000001",
+            "collection": "posts",
+            "data": {"tags": ["async", "rust"]},
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-02T00:00:00Z"
+        });
+        write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": document})).await;
+    });
+
+    let document = client
+        .modify("posts", uuid::Uuid::nil(), UpdateSpec::new().add_to_set("tags", "rust"))
+        .await
+        .unwrap();
+    assert_eq!(document.data["tags"], serde_json::json!(["async", "rust"]));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_modify_increments_counter() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Modify { id, update, .. } = read_request(&mut socket).await else {
+            panic!("expected modify");
+        };
+        assert_eq!(serde_json::to_value(&update).unwrap(), serde_json::json!({"$inc": {"views": 1.0}}));
+        let document = serde_json::json!({
+            "id": "00000000-0000-0000-0000-000000000001",
+            "collection": "posts",
+            "data": {"views": 42},
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-02T00:00:00Z"
+        });
+        write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": document})).await;
+    });
+
+    let document = client
+        .modify("posts", uuid::Uuid::nil(), UpdateSpec::new().inc("views", 1.0))
+        .await
+        .unwrap();
+    assert_eq!(document.get_as::<i64>("views").unwrap(), Some(42));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_query_stream_fetches_batches_on_demand() {
+    let (port, server) = mock_server(0x01).await;
+    let opts = ConnectOptions::new("127.0.0.1", port).with_cursor_batch_size(2);
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::OpenCursor { id, batch_size, .. } = read_request(&mut socket).await else {
+            panic!("expected opencursor");
+        };
+        assert_eq!(batch_size, 2);
+        let batch = serde_json::json!({
+            "type": "cursorbatch", "id": id, "cursor_id": "c1",
+            "documents": [{"n": 1}, {"n": 2}], "exhausted": false
+        });
+        write_response(&mut socket, batch).await;
+
+        let ClientMessage::FetchCursor { id, cursor_id } = read_request(&mut socket).await else {
+            panic!("expected fetchcursor");
+        };
+        assert_eq!(cursor_id, "c1");
+        let batch = serde_json::json!({
+            "type": "cursorbatch", "id": id, "cursor_id": "c1",
+            "documents": [{"n": 3}], "exhausted": true
+        });
+        write_response(&mut socket, batch).await;
+        socket
+    });
+
+    let stream = client.query_stream::<serde_json::Value>("db.table(\"events\").run()");
+    let numbers: Vec<u64> = stream
+        .map(|doc| doc.unwrap()["n"].as_u64().unwrap())
+        .collect()
+        .await;
+    assert_eq!(numbers, vec![1, 2, 3]);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_dropped_query_stream_closes_cursor() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let request = read_request(&mut socket).await;
+        let batch = serde_json::json!({
+            "type": "cursorbatch", "id": request.id(), "cursor_id": "c9",
+            "documents": [{"n": 1}, {"n": 2}], "exhausted": false
+        });
+        write_response(&mut socket, batch).await;
+        read_request(&mut socket).await
+    });
+
+    {
+        let mut stream = Box::pin(client.query_stream::<serde_json::Value>("db.table(\"events\").run()"));
+        assert_eq!(stream.next().await.unwrap().unwrap()["n"], 1);
+    }
+
+    let close = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+    assert!(matches!(close, ClientMessage::CloseCursor { cursor_id, .. } if cursor_id == "c9"));
+}
+
+#[tokio::test]
+async fn test_cursor_open_fetch_close() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for documents in [serde_json::json!([{"n": 1}]), serde_json::json!([{"n": 2}])] {
+            let request = read_request(&mut socket).await;
+            let batch = serde_json::json!({
+                "type": "cursorbatch", "id": request.id(), "cursor_id": "c4",
+                "documents": documents, "exhausted": false
+            });
+            write_response(&mut socket, batch).await;
+            requests.push(request);
+        }
+        requests.push(read_request(&mut socket).await);
+        requests
+    });
+
+    let mut cursor = client.open_cursor("db.table(\"events\").run()", 1).await.unwrap();
+    assert_eq!(cursor.cursor_id(), "c4");
+    assert_eq!(cursor.next_batch().await.unwrap(), Some(vec![serde_json::json!({"n": 1})]));
+    assert_eq!(cursor.next_batch().await.unwrap(), Some(vec![serde_json::json!({"n": 2})]));
+    assert!(!cursor.is_exhausted());
+    cursor.close().await.unwrap();
+
+    let requests = server.await.unwrap();
+    let kinds: Vec<&str> = requests.iter().map(ClientMessage::kind).collect();
+    assert_eq!(kinds, ["opencursor", "fetchcursor", "closecursor"]);
+    assert!(matches!(&requests[2], ClientMessage::CloseCursor { cursor_id, .. } if cursor_id == "c4"));
+}
+
+#[tokio::test]
+async fn test_server_error_code() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let server = tokio::spawn(async move {
+        let ClientMessage::Query { id, .. } = read_request(&mut socket).await else {
+            panic!("expected query");
+        };
+        write_response(
+            &mut socket,
+            serde_json::json!({
+                "type": "error",
+                "id": id,
+                "error": "permission denied",
+                "code": "PERMISSION_DENIED"
+            }),
+        )
+        .await;
+        socket
+    });
+
+    let err = client
+        .query::<serde_json::Value>("db.table(\"secrets\").run()")
+        .await
+        .unwrap_err();
+    let _socket = server.await.unwrap();
+    assert!(err.to_string().starts_with("Server error: permission denied"));
+    match err {
+        Error::Server { code: Some(code), .. } => assert_eq!(code, "PERMISSION_DENIED"),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_force_json_encoding() {
+    // Server supports both encodings; the client must still pick JSON
+    let (port, server) = mock_server_with_client_flags(0x01 | 0x02).await;
+    let server = tokio::spawn(async move {
+        let (client_flags, mut socket) = server.await.unwrap();
+        let (encoding, payload) = read_raw_frame(&mut socket).await;
+        let request: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        (client_flags, encoding, request, socket)
+    });
+
+    let opts = ConnectOptions::new("127.0.0.1", port).force_json();
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    assert_eq!(client.negotiated_encoding(), Encoding::Json);
+    let ping = tokio::spawn(async move { client.ping().await });
+
+    let (client_flags, encoding, request, _socket) = server.await.unwrap();
+    // JSON fallback, plus acceptance of a server message size limit
+    assert_eq!(client_flags, 0x02 | 0x10 | 0x20);
+    assert_eq!(encoding, Encoding::Json as u8);
+    assert_eq!(request["type"], "ping");
+    ping.abort();
+}
+
+#[tokio::test]
+async fn test_custom_id_generator() {
+    let (port, server) = mock_server(0x01).await;
+    let counter = std::sync::atomic::AtomicU64::new(100);
+    let opts = ConnectOptions::new("127.0.0.1", port)
+        .with_id_generator(move || format!("req-{}", counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst)));
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let mut socket = server.await.unwrap();
+
+    let ping = tokio::spawn(async move { client.ping().await });
+    let request = read_request(&mut socket).await;
+    assert!(matches!(&request, ClientMessage::Ping { id } if id == "req-100"));
+    write_response(&mut socket, serde_json::json!({"type": "pong", "id": "req-100"})).await;
+    ping.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_in_memory_transport() {
+    let (client_io, mut server_io) = tokio::io::duplex(64 * 1024);
+
+    let server = tokio::spawn(async move {
+        let mut handshake = [0u8; 8];
+        server_io.read_exact(&mut handshake).await.unwrap();
+        assert_eq!(&handshake[..4], b"SQRL");
+        server_io.write_all(&[0x00, 0x01, 0x01]).await.unwrap();
+        server_io.write_all(&[9u8; 16]).await.unwrap();
+
+        let length = server_io.read_u32().await.unwrap();
+        let mut frame = vec![0u8; length as usize];
+        server_io.read_exact(&mut frame).await.unwrap();
+        let request: ClientMessage = rmp_serde::from_slice(&frame[2..]).unwrap();
+        let ClientMessage::Query { id, .. } = request else {
+            panic!("expected query, got {:?}", request);
+        };
+
+        let payload = rmp_serde::to_vec_named(&serde_json::json!({
+            "type": "result",
+            "id": id,
+            "data": [{"id": "00000000-0000-0000-0000-000000000001", "collection": "users",
+                      "data": {"name": "Alice"}, "created_at": "", "updated_at": ""}]
+        }))
+        .unwrap();
+        server_io.write_u32(payload.len() as u32 + 2).await.unwrap();
+        server_io.write_all(&[0x02, 0x01]).await.unwrap();
+        server_io.write_all(&payload).await.unwrap();
+        server_io
+    });
+
+    let client = SquirrelDB::with_transport(client_io, ConnectOptions::new("in-memory", 0))
+        .await
+        .unwrap();
+    assert_eq!(client.session_id().as_bytes(), &[9u8; 16]);
+
+    let docs: Vec<squirreldb_sdk::Document> =
+        client.query(r#"db.table("users").run()"#).await.unwrap();
+    assert_eq!(docs[0].data["name"], "Alice");
+    let _server_io = server.await.unwrap();
+}
+
+#[cfg(not(feature = "compression"))]
+#[tokio::test]
+async fn test_compression_requires_feature() {
+    let (client_io, _server_io) = tokio::io::duplex(1024);
+    let opts = ConnectOptions {
+        compression_threshold: Some(1024),
+        ..ConnectOptions::new("in-memory", 0)
+    };
+    let result = SquirrelDB::with_transport(client_io, opts).await;
+    assert!(matches!(result, Err(Error::Config(msg)) if msg.contains("`compression` feature")));
+}
+
+#[tokio::test]
+async fn test_query_structured() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let mut query: squirreldb_sdk::StructuredQuery =
+        serde_json::from_str(r#"{"table": "users", "filter": {"age": {"$nope": 1}}}"#).unwrap();
+    assert!(matches!(client.query_structured(&query).await, Err(Error::Query(_))));
+
+    query.filter = table("users").find(field("age").gt(21)).compile_structured().filter;
+    let run = tokio::spawn(async move { client.query_structured(&query).await });
+    let ClientMessage::Query { id, query } = read_request(&mut socket).await else {
+        panic!("expected query");
+    };
+    let sent: serde_json::Value = serde_json::from_str(&query).unwrap();
+    assert_eq!(sent["filter"]["age"]["$gt"], 21);
+    write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": [{"age": 30}]})).await;
+    assert_eq!(run.await.unwrap().unwrap(), vec![serde_json::json!({"age": 30})]);
+}
+
+#[tokio::test]
+async fn test_raw_request() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let call = tokio::spawn(async move {
+        client
+            .request(ClientMessage::ListIndexes {
+                id: String::new(),
+                collection: "users".to_string(),
+            })
+            .await
+    });
+    let request = read_request(&mut socket).await;
+    assert!(!request.id().is_empty());
+    write_response(
+        &mut socket,
+        serde_json::json!({"type": "error", "id": request.id(), "error": "nope", "code": "EXPERIMENTAL"}),
+    )
+    .await;
+
+    let response = call.await.unwrap().unwrap();
+    assert!(matches!(response, squirreldb_sdk::ServerMessage::Error { code: Some(c), .. } if c == "EXPERIMENTAL"));
+}
+
+#[tokio::test]
+async fn test_delete_many() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let query = table("sessions").find(field("expired").eq(true)).compile_structured();
+    let call = tokio::spawn(async move { client.delete_many(&query).await });
+
+    let ClientMessage::DeleteMany { id, collection, filter } = read_request(&mut socket).await else {
+        panic!("expected delete_many");
+    };
+    assert_eq!(collection, "sessions");
+    assert_eq!(filter["expired"]["$eq"], true);
+    write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": 0})).await;
+    assert_eq!(call.await.unwrap().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_delete_many_ignores_sort_limit_and_skip() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    // Skip without sort would fail validation as a read query
+    let query = table("sessions").find(field("expired").eq(true)).skip(10).compile_structured();
+    let call = tokio::spawn(async move { client.delete_many(&query).await });
+
+    let ClientMessage::DeleteMany { id, filter, .. } = read_request(&mut socket).await else {
+        panic!("expected delete_many");
+    };
+    assert_eq!(filter["expired"]["$eq"], true);
+    write_response(&mut socket, serde_json::json!({"type": "result", "id": id, "data": 3})).await;
+    assert_eq!(call.await.unwrap().unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_patch_document() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let doc_id = uuid::Uuid::new_v4();
+    let call = tokio::spawn(async move {
+        client.patch("users", doc_id, serde_json::json!({"email": "new@example.com"})).await
+    });
+
+    let ClientMessage::Patch { id, document_id, data, .. } = read_request(&mut socket).await else {
+        panic!("expected patch");
+    };
+    assert_eq!(document_id, doc_id);
+    assert_eq!(data, serde_json::json!({"email": "new@example.com"}));
+    write_response(
+        &mut socket,
+        serde_json::json!({"type": "result", "id": id, "data": {
+            "id": doc_id.to_string(), "collection": "users",
+            "data": {"name": "Alice", "email": "new@example.com"},
+            "created_at": "", "updated_at": ""
+        }}),
+    )
+    .await;
+
+    let doc = call.await.unwrap().unwrap();
+    assert_eq!(doc.data["name"], "Alice");
+    assert_eq!(doc.data["email"], "new@example.com");
+}
+
+#[tokio::test]
+async fn test_update_if_unchanged_conflict() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    let doc_id = uuid::Uuid::new_v4();
+    let call = tokio::spawn(async move {
+        client
+            .update_if_unchanged("docs", doc_id, serde_json::json!({"title": "v2"}), "2024-01-01T00:00:00Z")
+            .await
+    });
+
+    let ClientMessage::Update { id, expected_updated_at, .. } = read_request(&mut socket).await else {
+        panic!("expected update");
+    };
+    assert_eq!(expected_updated_at.as_deref(), Some("2024-01-01T00:00:00Z"));
+    write_response(
+        &mut socket,
+        serde_json::json!({"type": "error", "id": id, "error": "document changed", "code": "CONFLICT"}),
+    )
+    .await;
+
+    assert!(matches!(call.await.unwrap(), Err(Error::Conflict(msg)) if msg.contains("document changed")));
+}
+
+#[tokio::test]
+async fn test_health_check_and_is_connected() {
+    let (port, server) = mock_server(0x01).await;
+    let client = std::sync::Arc::new(
+        SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+            .await
+            .unwrap(),
+    );
+    let mut socket = server.await.unwrap();
+    assert!(client.is_connected());
+
+    let probe = tokio::spawn({
+        let client = client.clone();
+        async move { client.health_check().await }
+    });
+    let request = read_request(&mut socket).await;
+    write_response(&mut socket, serde_json::json!({"type": "pong", "id": request.id()})).await;
+    assert!(probe.await.unwrap().unwrap() < Duration::from_secs(5));
+
+    drop(socket);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while client.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("is_connected should flip to false after the socket closes");
+}
+
+#[tokio::test]
+async fn test_ping_rtt_measures_round_trip() {
+    let (port, server) = mock_server(0x01).await;
+    let client = std::sync::Arc::new(
+        SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+            .await
+            .unwrap(),
+    );
+    let mut socket = server.await.unwrap();
+
+    let probe = tokio::spawn({
+        let client = client.clone();
+        async move { client.ping_rtt().await }
+    });
+    let request = read_request(&mut socket).await;
+    assert_eq!(request.kind(), "ping");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    write_response(&mut socket, serde_json::json!({"type": "pong", "id": request.id()})).await;
+
+    let rtt = probe.await.unwrap().unwrap();
+    assert!(rtt >= Duration::from_millis(50) && rtt < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_metrics_snapshot() {
+    let (port, server) = mock_server(0x01).await;
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut socket = server.await.unwrap();
+
+    // Handshake: 8 bytes out, 19 bytes in
+    let metrics = client.metrics();
+    assert_eq!(metrics.bytes_written, 8);
+    assert_eq!(metrics.bytes_read, 19);
+
+    let client = std::sync::Arc::new(client);
+    let ping = tokio::spawn({
+        let client = client.clone();
+        async move { client.ping().await }
+    });
+    let request = read_request(&mut socket).await;
+    write_response(&mut socket, serde_json::json!({"type": "pong", "id": request.id()})).await;
+    ping.await.unwrap().unwrap();
+
+    let failing = tokio::spawn({
+        let client = client.clone();
+        async move { client.ping().await }
+    });
+    let request = read_request(&mut socket).await;
+    write_response(&mut socket, serde_json::json!({"type": "error", "id": request.id(), "error": "x"})).await;
+    assert!(failing.await.unwrap().is_err());
+
+    let metrics = client.metrics();
+    assert_eq!(metrics.requests_sent, 2);
+    assert_eq!(metrics.responses_received, 2);
+    assert_eq!(metrics.errors.get("Server"), Some(&1));
+    assert_eq!(metrics.in_flight, 0);
+    assert!(metrics.bytes_written > 8 && metrics.bytes_read > 19);
+}
+
+#[tokio::test]
+async fn test_small_buffers_without_nodelay() {
+    let (port, server) = mock_server(0x01).await;
+    let mut opts = ConnectOptions::new("127.0.0.1", port).with_buffer_sizes(16, 16);
+    assert!(opts.tcp_nodelay);
+    opts.tcp_nodelay = false;
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let mut socket = server.await.unwrap();
+
+    let big = "x".repeat(4096);
+    let call = tokio::spawn(async move {
+        client.insert("logs", serde_json::json!({"line": big})).await
+    });
+    let ClientMessage::Insert { id, data, .. } = read_request(&mut socket).await else {
+        panic!("expected insert");
+    };
+    assert_eq!(data["line"].as_str().unwrap().len(), 4096);
+    write_response(
+        &mut socket,
+        serde_json::json!({"type": "result", "id": id, "data": {
+            "id": "00000000-0000-0000-0000-000000000002", "collection": "logs",
+            "data": data, "created_at": "", "updated_at": ""
+        }}),
+    )
+    .await;
+    assert_eq!(call.await.unwrap().unwrap().data["line"].as_str().unwrap().len(), 4096);
+}
+
+#[tokio::test]
+async fn test_frame_logger() {
+    use squirreldb_sdk::Direction;
+    use std::sync::{Arc, Mutex};
+
+    let (port, server) = mock_server(0x01).await;
+    let frames = Arc::new(Mutex::new(Vec::<(Direction, Vec<u8>)>::new()));
+    let opts = ConnectOptions::new("127.0.0.1", port).with_frame_logger({
+        let frames = frames.clone();
+        move |direction, frame| frames.lock().unwrap().push((direction, frame.to_vec()))
+    });
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let mut socket = server.await.unwrap();
+
+    let ping = tokio::spawn(async move { client.ping().await });
+    let request = read_request(&mut socket).await;
+    write_response(&mut socket, serde_json::json!({"type": "pong", "id": request.id()})).await;
+    ping.await.unwrap().unwrap();
+
+    let frames = frames.lock().unwrap();
+    assert_eq!(frames.len(), 2);
+    let (direction, outbound) = &frames[0];
+    assert_eq!(*direction, Direction::Outbound);
+    assert_eq!(outbound, &squirreldb_sdk::encode_frame(&request, Encoding::MessagePack).unwrap());
+    let (direction, inbound) = &frames[1];
+    assert_eq!(*direction, Direction::Inbound);
+    assert_eq!(u32::from_be_bytes(inbound[..4].try_into().unwrap()) as usize, inbound.len() - 4);
+    assert_eq!(inbound[4], 0x02);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_connect_over_unix_socket() {
+    let path = std::env::temp_dir().join(format!("squirreldb-test-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        handshake(&mut socket, 0x01).await;
+        socket
+    });
+
+    let client = SquirrelDB::connect_with_options(ConnectOptions::new("ignored", 0).unix_socket(&path))
+        .await
+        .unwrap();
+    assert_eq!(client.session_id().to_string(), uuid::Uuid::from_bytes([7u8; 16]).to_string());
+    drop(server.await.unwrap());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(not(unix))]
+#[tokio::test]
+async fn test_unix_socket_rejected_off_unix() {
+    let opts = ConnectOptions::new("ignored", 0).unix_socket("squirreldb.sock");
+    let result = SquirrelDB::connect_with_options(opts).await;
+    assert!(matches!(result, Err(Error::Config(msg)) if msg.contains("not supported")));
+}
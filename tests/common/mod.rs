@@ -0,0 +1,147 @@
+//! SquirrelDB Rust SDK - Shared mock server fixtures for the integration tests
+
+#![allow(dead_code)]
+
+use squirreldb_sdk::{ClientMessage, Encoding};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// Accept one connection and answer the handshake with the given flags byte
+pub async fn mock_server(server_flags: u8) -> (u16, JoinHandle<TcpStream>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = tokio::spawn(async move { accept_handshake(&listener, server_flags).await });
+
+    (port, handle)
+}
+
+/// Like [`mock_server`], also returning the handshake flags the client offered
+pub async fn mock_server_with_client_flags(server_flags: u8) -> (u16, JoinHandle<(u8, TcpStream)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let client_flags = handshake(&mut socket, server_flags).await;
+        (client_flags, socket)
+    });
+
+    (port, handle)
+}
+
+/// Accept the next connection on `listener` and answer its handshake
+pub async fn accept_handshake(listener: &TcpListener, server_flags: u8) -> TcpStream {
+    let (mut socket, _) = listener.accept().await.unwrap();
+    handshake(&mut socket, server_flags).await;
+    socket
+}
+
+/// Read the client's handshake and answer it with `server_flags`, returning
+/// the flags the client offered
+pub async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, server_flags: u8) -> u8 {
+    let mut magic = [0u8; 4];
+    stream.read_exact(&mut magic).await.unwrap();
+    assert_eq!(&magic, b"SQRL");
+    let _version = stream.read_u8().await.unwrap();
+    let client_flags = stream.read_u8().await.unwrap();
+    let token_len = stream.read_u16().await.unwrap();
+    let mut token = vec![0u8; token_len as usize];
+    stream.read_exact(&mut token).await.unwrap();
+
+    stream.write_u8(0x00).await.unwrap();
+    stream.write_u8(0x01).await.unwrap();
+    stream.write_u8(server_flags).await.unwrap();
+    stream.write_all(&[7u8; 16]).await.unwrap();
+    stream.flush().await.unwrap();
+    client_flags
+}
+
+/// Read one raw request frame, returning the encoding byte and payload
+pub async fn read_raw_frame<S: AsyncRead + Unpin>(stream: &mut S) -> (u8, Vec<u8>) {
+    let length = stream.read_u32().await.unwrap();
+    let _msg_type = stream.read_u8().await.unwrap();
+    let encoding = stream.read_u8().await.unwrap();
+    let mut payload = vec![0u8; length as usize - 2];
+    stream.read_exact(&mut payload).await.unwrap();
+    (encoding, payload)
+}
+
+/// Read one request frame from the client, MessagePack or JSON
+pub async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> ClientMessage {
+    let (encoding, payload) = read_raw_frame(stream).await;
+    if encoding == Encoding::Json as u8 {
+        return serde_json::from_slice(&payload).unwrap();
+    }
+    rmp_serde::from_slice(&payload).unwrap()
+}
+
+/// Write one MessagePack response frame to the client
+pub async fn write_response<S: AsyncWrite + Unpin>(stream: &mut S, msg: serde_json::Value) {
+    let payload = rmp_serde::to_vec_named(&msg).unwrap();
+    stream.write_u32(payload.len() as u32 + 2).await.unwrap();
+    stream.write_u8(0x02).await.unwrap();
+    stream.write_u8(Encoding::MessagePack as u8).await.unwrap();
+    stream.write_all(&payload).await.unwrap();
+    stream.flush().await.unwrap();
+}
+
+/// Serve one canned RESP reply per command and collect the commands received
+pub async fn resp_server(replies: Vec<&'static str>) -> (u16, JoinHandle<Vec<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = tokio::spawn(serve_resp(listener, replies));
+
+    (port, handle)
+}
+
+/// Accept one cache connection on `listener` and answer each command with the
+/// next canned reply, returning the commands received
+pub async fn serve_resp(listener: TcpListener, replies: Vec<&'static str>) -> Vec<Vec<String>> {
+    let (socket, _) = listener.accept().await.unwrap();
+    let mut reader = BufReader::new(socket);
+    let mut commands = Vec::new();
+    for reply in replies {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        let count: usize = line.trim_end()[1..].parse().unwrap();
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            let len: usize = line.trim_end()[1..].parse().unwrap();
+            let mut arg = vec![0u8; len + 2];
+            reader.read_exact(&mut arg).await.unwrap();
+            arg.truncate(len);
+            args.push(String::from_utf8(arg).unwrap());
+        }
+        commands.push(args);
+        reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+    }
+    commands
+}
+
+/// Run an async mock server on a runtime of its own thread, for tests of the
+/// blocking clients, which must not be driven from inside a runtime
+pub fn serve_blocking<F, Fut, T>(serve: F) -> (u16, std::thread::JoinHandle<T>)
+where
+    F: FnOnce(TcpListener) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = T>,
+    T: Send + 'static,
+{
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    listener.set_nonblocking(true).unwrap();
+
+    let handle = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move { serve(TcpListener::from_std(listener).unwrap()).await })
+    });
+
+    (port, handle)
+}
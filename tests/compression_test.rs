@@ -0,0 +1,91 @@
+//! SquirrelDB Rust SDK - Compression Tests
+
+#![cfg(feature = "compression")]
+
+use serde_json::json;
+use squirreldb_sdk::{
+    compress_frame, decode_frame, encode_frame, ClientMessage, ConnectOptions, Encoding,
+    ServerMessage, SquirrelDB, COMPRESSED_BIT, FRAME_HEADER_SIZE,
+};
+
+mod common;
+use common::{mock_server_with_client_flags, read_raw_frame};
+
+fn large_query() -> ClientMessage {
+    ClientMessage::Query {
+        id: "1".to_string(),
+        query: format!("db.table(\"users\").filter(\"{}\").run()", "a".repeat(4096)),
+    }
+}
+
+#[test]
+fn test_compress_frame_above_threshold() {
+    let frame = encode_frame(&large_query(), Encoding::MessagePack).unwrap();
+    let compressed = compress_frame(frame.clone(), 1024).unwrap();
+
+    assert!(compressed.len() < frame.len());
+    assert_eq!(compressed[5], Encoding::MessagePack as u8 | COMPRESSED_BIT);
+    let payload = zstd::bulk::decompress(&compressed[FRAME_HEADER_SIZE..], 1 << 20).unwrap();
+    assert_eq!(payload, &frame[FRAME_HEADER_SIZE..]);
+}
+
+#[test]
+fn test_compress_frame_below_threshold_is_unchanged() {
+    let frame = encode_frame(&ClientMessage::Ping { id: "1".to_string() }, Encoding::Json).unwrap();
+    assert_eq!(compress_frame(frame.clone(), 1024).unwrap(), frame);
+}
+
+#[test]
+fn test_decode_compressed_server_frame() {
+    let payload = serde_json::to_vec(&json!({"type": "pong", "id": "9"})).unwrap();
+    let compressed = zstd::bulk::compress(&payload, 3).unwrap();
+
+    let mut frame = ((compressed.len() + 2) as u32).to_be_bytes().to_vec();
+    frame.push(0x02);
+    frame.push(Encoding::Json as u8 | COMPRESSED_BIT);
+    frame.extend_from_slice(&compressed);
+
+    let (msg, consumed) = decode_frame(&frame).unwrap();
+    assert_eq!(consumed, frame.len());
+    assert!(matches!(msg, ServerMessage::Pong { id } if id == "9"));
+}
+
+#[tokio::test]
+async fn test_client_compresses_when_negotiated() {
+    let (port, server) = mock_server_with_client_flags(0x01 | 0x08).await;
+    let opts = ConnectOptions::new("127.0.0.1", port).with_compression(256);
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let (client_flags, mut socket) = server.await.unwrap();
+    assert_ne!(client_flags & 0x08, 0);
+
+    let ClientMessage::Query { query, .. } = large_query() else { unreachable!() };
+    let request = tokio::spawn(async move {
+        let _ = client.query::<serde_json::Value>(&query).await;
+    });
+
+    let (encoding, payload) = read_raw_frame(&mut socket).await;
+    assert_ne!(encoding & COMPRESSED_BIT, 0);
+    let payload = zstd::bulk::decompress(&payload, 1 << 20).unwrap();
+    let msg: ClientMessage = rmp_serde::from_slice(&payload).unwrap();
+    assert!(matches!(msg, ClientMessage::Query { .. }));
+    request.abort();
+}
+
+#[tokio::test]
+async fn test_client_sends_uncompressed_without_server_support() {
+    let (port, server) = mock_server_with_client_flags(0x01).await;
+    let opts = ConnectOptions::new("127.0.0.1", port).with_compression(256);
+    let client = SquirrelDB::connect_with_options(opts).await.unwrap();
+    let (_, mut socket) = server.await.unwrap();
+
+    let ClientMessage::Query { query, .. } = large_query() else { unreachable!() };
+    let request = tokio::spawn(async move {
+        let _ = client.query::<serde_json::Value>(&query).await;
+    });
+
+    let (encoding, payload) = read_raw_frame(&mut socket).await;
+    assert_eq!(encoding, Encoding::MessagePack as u8);
+    let msg: ClientMessage = rmp_serde::from_slice(&payload).unwrap();
+    assert!(matches!(msg, ClientMessage::Query { .. }));
+    request.abort();
+}
@@ -0,0 +1,53 @@
+//! SquirrelDB Rust SDK - Error Tests
+
+use std::error::Error as _;
+use std::io;
+
+use squirreldb_sdk::Error;
+
+#[test]
+fn test_retryable_errors() {
+    assert!(Error::Timeout.is_retryable());
+    assert!(Error::ChannelClosed.is_retryable());
+    assert!(Error::Overloaded.is_retryable());
+    assert!(Error::Io(io::Error::from(io::ErrorKind::ConnectionReset)).is_retryable());
+}
+
+#[test]
+fn test_terminal_errors() {
+    assert!(!Error::AuthFailed.is_retryable());
+    assert!(!Error::VersionMismatch { server: 2, client: 1 }.is_retryable());
+    assert!(!Error::Serialization("bad".into()).is_retryable());
+    assert!(!Error::Io(io::Error::from(io::ErrorKind::PermissionDenied)).is_retryable());
+}
+
+#[test]
+fn test_server_errors_retryable_by_code() {
+    let server = |code: Option<&str>| Error::Server {
+        code: code.map(str::to_string),
+        message: "boom".to_string(),
+    };
+    assert!(server(Some("UNAVAILABLE")).is_retryable());
+    assert!(server(Some("OVERLOADED")).is_retryable());
+    assert!(!server(Some("TABLE_NOT_FOUND")).is_retryable());
+    assert!(!server(None).is_retryable());
+}
+
+#[test]
+fn test_connection_errors() {
+    assert!(Error::Connection("refused".to_string()).is_connection_error());
+    assert!(Error::Io(io::Error::from(io::ErrorKind::BrokenPipe)).is_connection_error());
+    assert!(!Error::Timeout.is_connection_error());
+    assert!(!Error::Server { code: None, message: "boom".to_string() }.is_connection_error());
+}
+
+#[test]
+fn test_serialization_error_keeps_source() {
+    let json_err = serde_json::from_str::<u32>("\"nope\"").unwrap_err();
+    let expected = json_err.to_string();
+    let err = Error::from(json_err);
+
+    assert_eq!(err.to_string(), format!("Serialization error: {}", expected));
+    let source = err.source().expect("source preserved");
+    assert!(source.downcast_ref::<serde_json::Error>().is_some());
+}
@@ -0,0 +1,126 @@
+//! SquirrelDB Rust SDK - Connection Pool Tests
+
+use std::time::Duration;
+
+use squirreldb_sdk::{ConnectOptions, SquirrelDBPool};
+use tokio::net::{TcpListener, TcpStream};
+
+mod common;
+use common::{accept_handshake, read_request, write_response};
+
+/// Answer a ping request
+async fn write_pong(socket: &mut TcpStream, id: &str) {
+    write_response(socket, serde_json::json!({"type": "pong", "id": id})).await;
+}
+
+#[tokio::test]
+async fn test_pool_dispatches_to_least_loaded_and_replaces_dead() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let opts = ConnectOptions::new("127.0.0.1", port);
+
+    let connect = tokio::spawn(async move { SquirrelDBPool::connect(opts, 2).await });
+    let mut first = accept_handshake(&listener, 0x01).await;
+    let mut second = accept_handshake(&listener, 0x01).await;
+    let pool = std::sync::Arc::new(connect.await.unwrap().unwrap());
+    assert_eq!(pool.size(), 2);
+
+    // Park a request on the first connection, then the next one must go to the second
+    let slow = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.ping().await }
+    });
+    let parked = read_request(&mut first).await;
+    let fast = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.ping().await }
+    });
+    let request = read_request(&mut second).await;
+    write_pong(&mut second, request.id()).await;
+    fast.await.unwrap().unwrap();
+    write_pong(&mut first, parked.id()).await;
+    slow.await.unwrap().unwrap();
+
+    // Kill the second connection; dispatching notices and reconnects it
+    drop(second);
+    let dispatcher = tokio::spawn({
+        let pool = pool.clone();
+        async move {
+            loop {
+                pool.get().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    });
+    let mut replacement = tokio::time::timeout(Duration::from_secs(5), accept_handshake(&listener, 0x01))
+        .await
+        .expect("pool should reconnect the dead connection");
+
+    let ping = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.ping().await }
+    });
+    tokio::select! {
+        request = read_request(&mut first) => write_pong(&mut first, request.id()).await,
+        request = read_request(&mut replacement) => write_pong(&mut replacement, request.id()).await,
+    }
+    ping.await.unwrap().unwrap();
+    dispatcher.abort();
+}
+
+#[tokio::test]
+async fn test_pool_serves_live_connection_while_redial_hangs() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let opts = ConnectOptions::new("127.0.0.1", port);
+
+    let connect = tokio::spawn(async move { SquirrelDBPool::connect(opts, 2).await });
+    let mut first = accept_handshake(&listener, 0x01).await;
+    let second = accept_handshake(&listener, 0x01).await;
+    let pool = std::sync::Arc::new(connect.await.unwrap().unwrap());
+
+    // The replacement for the second connection is never handshaken
+    drop(second);
+    for _ in 0..3 {
+        let ping = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.ping().await }
+        });
+        let request = tokio::time::timeout(Duration::from_secs(2), read_request(&mut first))
+            .await
+            .expect("request should go to the live connection");
+        write_pong(&mut first, request.id()).await;
+        ping.await.unwrap().unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_pool_size_zero_is_not_retryable() {
+    let result = SquirrelDBPool::connect(ConnectOptions::new("127.0.0.1", 1), 0).await;
+    let Err(error) = result else {
+        panic!("a pool of no connections should be rejected");
+    };
+    assert!(matches!(error, squirreldb_sdk::Error::Config(_)));
+    assert!(!error.is_retryable());
+    assert!(!error.is_connection_error());
+}
+
+#[tokio::test]
+async fn test_pool_counts_reconnects() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let opts = ConnectOptions::new("127.0.0.1", port);
+
+    let connect = tokio::spawn(async move { SquirrelDBPool::connect(opts, 1).await });
+    let original = accept_handshake(&listener, 0x01).await;
+    let pool = connect.await.unwrap().unwrap();
+    let client = pool.get().await.unwrap();
+    assert_eq!(client.metrics().reconnects, 0);
+
+    drop(original);
+    while client.is_connected() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let (replacement, _socket) = tokio::join!(pool.get(), accept_handshake(&listener, 0x01));
+    assert_eq!(replacement.unwrap().metrics().reconnects, 1);
+}
@@ -1,6 +1,22 @@
 //! SquirrelDB Rust SDK - Protocol Tests
 
 use serde_json::json;
+use squirreldb_sdk::{
+    decode_frame, encode_frame, encoded_size, ClientMessage, Collection, Encoding, Error, ServerMessage,
+    UpdateOp, UpdateSpec, MAX_MESSAGE_SIZE,
+};
+
+fn server_frame(msg: &serde_json::Value, encoding: Encoding) -> Vec<u8> {
+    let payload = match encoding {
+        Encoding::MessagePack => rmp_serde::to_vec_named(msg).unwrap(),
+        Encoding::Json => serde_json::to_vec(msg).unwrap(),
+    };
+    let mut frame = ((payload.len() + 2) as u32).to_be_bytes().to_vec();
+    frame.push(0x02);
+    frame.push(encoding as u8);
+    frame.extend_from_slice(&payload);
+    frame
+}
 
 #[test]
 fn test_ping_message() {
@@ -174,3 +190,287 @@ fn test_resp_array_format() {
     let response = "*2\r\n$3\r\nfoo\r\n$3\r\nbar\r\n";
     assert!(response.starts_with("*2"));
 }
+
+#[test]
+fn test_encode_frame_header() {
+    let msg = ClientMessage::Ping { id: "1".to_string() };
+    let frame = encode_frame(&msg, Encoding::Json).unwrap();
+
+    let payload = br#"{"type":"ping","id":"1"}"#;
+    assert_eq!(&frame[..4], &((payload.len() + 2) as u32).to_be_bytes());
+    assert_eq!(frame[4], 0x01);
+    assert_eq!(frame[5], Encoding::Json as u8);
+    assert_eq!(&frame[6..], payload);
+}
+
+#[test]
+fn test_encode_frame_messagepack_roundtrip() {
+    let msg = ClientMessage::Query {
+        id: "7".to_string(),
+        query: "db.table(\"users\").run()".to_string(),
+    };
+    let frame = encode_frame(&msg, Encoding::MessagePack).unwrap();
+    assert_eq!(frame[5], Encoding::MessagePack as u8);
+
+    let decoded: ClientMessage = rmp_serde::from_slice(&frame[6..]).unwrap();
+    assert!(matches!(decoded, ClientMessage::Query { id, .. } if id == "7"));
+}
+
+#[test]
+fn test_encode_frame_rejects_oversized_message() {
+    let msg = ClientMessage::Insert {
+        id: "1".to_string(),
+        collection: "blobs".to_string(),
+        data: json!({"body": "x".repeat(MAX_MESSAGE_SIZE as usize)}),
+    };
+
+    let size = encoded_size(&msg, Encoding::MessagePack).unwrap();
+    assert!(size > MAX_MESSAGE_SIZE as usize);
+    match encode_frame(&msg, Encoding::MessagePack) {
+        Err(Error::MessageTooLarge { size: reported, max }) => {
+            assert_eq!(reported, size);
+            assert_eq!(max, MAX_MESSAGE_SIZE as usize);
+        }
+        other => panic!("expected MessageTooLarge, got {:?}", other.map(|f| f.len())),
+    }
+
+    let small = ClientMessage::Ping { id: "1".to_string() };
+    let frame = encode_frame(&small, Encoding::Json).unwrap();
+    assert_eq!(encoded_size(&small, Encoding::Json).unwrap(), frame.len() - 4);
+}
+
+#[test]
+fn test_decode_frame_consumes_one_frame() {
+    let mut buf = server_frame(&json!({"type": "pong", "id": "1"}), Encoding::MessagePack);
+    let first_len = buf.len();
+    buf.extend(server_frame(&json!({"type": "pong", "id": "2"}), Encoding::Json));
+
+    let (msg, consumed) = decode_frame(&buf).unwrap();
+    assert_eq!(consumed, first_len);
+    assert!(matches!(msg, ServerMessage::Pong { id } if id == "1"));
+
+    let (msg, consumed) = decode_frame(&buf[first_len..]).unwrap();
+    assert_eq!(consumed, buf.len() - first_len);
+    assert!(matches!(msg, ServerMessage::Pong { id } if id == "2"));
+}
+
+#[test]
+fn test_decode_frame_incomplete() {
+    let frame = server_frame(&json!({"type": "pong", "id": "1"}), Encoding::Json);
+    assert!(decode_frame(&frame[..3]).is_err());
+    assert!(decode_frame(&frame[..frame.len() - 1]).is_err());
+}
+
+#[test]
+fn test_decode_frame_rejects_oversized_length() {
+    let mut frame = (MAX_MESSAGE_SIZE + 1).to_be_bytes().to_vec();
+    frame.extend_from_slice(&[0x02, 0x01]);
+    assert!(decode_frame(&frame).is_err());
+}
+
+#[cfg(not(feature = "compression"))]
+#[test]
+fn test_decode_compressed_frame_without_feature() {
+    let mut frame = server_frame(&json!({"type": "pong", "id": "1"}), Encoding::Json);
+    frame[5] |= squirreldb_sdk::COMPRESSED_BIT;
+    assert!(matches!(
+        decode_frame(&frame),
+        Err(squirreldb_sdk::Error::Protocol(msg)) if msg.contains("compression")
+    ));
+}
+
+#[test]
+fn test_list_collections_response() {
+    let frame = server_frame(
+        &json!({
+            "type": "result",
+            "id": "4",
+            "data": [
+                {"name": "users", "document_count": 42, "created_at": "2024-01-01T00:00:00Z"},
+                {"name": "orders"},
+                "logs"
+            ]
+        }),
+        Encoding::MessagePack,
+    );
+
+    let (ServerMessage::Result { data, .. }, _) = decode_frame(&frame).unwrap() else {
+        panic!("expected result");
+    };
+    let collections: Vec<Collection> = serde_json::from_value(data).unwrap();
+    assert_eq!(collections[0].name, "users");
+    assert_eq!(collections[0].document_count, Some(42));
+    assert_eq!(collections[0].created_at.as_deref(), Some("2024-01-01T00:00:00Z"));
+    assert_eq!(collections[1].document_count, None);
+    assert_eq!(collections[2].name, "logs");
+}
+
+#[test]
+fn test_error_response_code() {
+    let frame = server_frame(
+        &json!({"type": "error", "id": "1", "error": "table not found", "code": "TABLE_NOT_FOUND"}),
+        Encoding::MessagePack,
+    );
+    let (msg, _) = decode_frame(&frame).unwrap();
+    assert!(matches!(msg, ServerMessage::Error { code: Some(c), .. } if c == "TABLE_NOT_FOUND"));
+
+    let frame = server_frame(&json!({"type": "error", "id": "1", "error": "boom"}), Encoding::Json);
+    let (msg, _) = decode_frame(&frame).unwrap();
+    assert!(matches!(msg, ServerMessage::Error { code: None, .. }));
+}
+
+#[test]
+fn test_update_many_roundtrip() {
+    let filter = squirreldb_sdk::table("users")
+        .find(squirreldb_sdk::field("plan").eq("trial"))
+        .compile_structured()
+        .filter
+        .unwrap();
+    let msg = ClientMessage::UpdateMany {
+        id: "9".to_string(),
+        collection: "users".to_string(),
+        filter,
+        patch: json!({"plan": "free", "limits": {"seats": 1}}),
+    };
+
+    for encoding in [Encoding::MessagePack, Encoding::Json] {
+        let frame = encode_frame(&msg, encoding).unwrap();
+        let decoded: ClientMessage = match encoding {
+            Encoding::MessagePack => rmp_serde::from_slice(&frame[6..]).unwrap(),
+            Encoding::Json => serde_json::from_slice(&frame[6..]).unwrap(),
+        };
+        assert_eq!(decoded, msg);
+    }
+
+    let frame = encode_frame(&msg, Encoding::Json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&frame[6..]).unwrap();
+    assert_eq!(json["type"], "updatemany");
+    assert_eq!(json["filter"]["plan"]["$eq"], "trial");
+}
+
+#[test]
+fn test_modify_groups_ops_by_operator() {
+    let msg = ClientMessage::Modify {
+        id: "4".to_string(),
+        collection: "posts".to_string(),
+        document_id: uuid::Uuid::nil(),
+        update: UpdateSpec::new()
+            .push("tags", "rust")
+            .add_to_set("tags_seen", "rust")
+            .pull("tags", "draft")
+            .op(UpdateOp::ArrayPush { field: "history".to_string(), value: json!({"by": "ana"}) }),
+    };
+
+    let json = serde_json::to_value(&msg).unwrap();
+    assert_eq!(json["type"], msg.kind());
+    assert_eq!(
+        json["update"],
+        json!({
+            "$push": {"tags": "rust", "history": {"by": "ana"}},
+            "$pull": {"tags": "draft"},
+            "$addToSet": {"tags_seen": "rust"}
+        })
+    );
+
+    for encoding in [Encoding::MessagePack, Encoding::Json] {
+        let frame = encode_frame(&msg, encoding).unwrap();
+        let decoded: ClientMessage = match encoding {
+            Encoding::MessagePack => rmp_serde::from_slice(&frame[6..]).unwrap(),
+            Encoding::Json => serde_json::from_slice(&frame[6..]).unwrap(),
+        };
+        let ClientMessage::Modify { update, .. } = decoded else { panic!("expected modify") };
+        assert_eq!(update.ops().len(), 4);
+        assert!(update.ops().contains(&UpdateOp::ArrayPull { field: "tags".to_string(), value: json!("draft") }));
+    }
+}
+
+#[test]
+fn test_inc_roundtrips_and_rejects_non_numbers() {
+    let spec = UpdateSpec::new().inc("views", 1.0).inc("stock", -2.5);
+    let json = serde_json::to_value(&spec).unwrap();
+    assert_eq!(json, json!({"$inc": {"views": 1.0, "stock": -2.5}}));
+
+    let decoded: UpdateSpec = serde_json::from_value(json).unwrap();
+    assert!(decoded.ops().contains(&UpdateOp::Inc { field: "stock".to_string(), by: -2.5 }));
+    assert!(serde_json::from_value::<UpdateSpec>(json!({"$inc": {"views": "one"}})).is_err());
+}
+
+#[test]
+fn test_cursor_messages_roundtrip() {
+    let messages = [
+        ClientMessage::OpenCursor {
+            id: "1".to_string(),
+            query: "db.table(\"events\").run()".to_string(),
+            batch_size: 500,
+        },
+        ClientMessage::FetchCursor { id: "2".to_string(), cursor_id: "c1".to_string() },
+        ClientMessage::CloseCursor { id: "3".to_string(), cursor_id: "c1".to_string() },
+    ];
+
+    for msg in &messages {
+        for encoding in [Encoding::MessagePack, Encoding::Json] {
+            let frame = encode_frame(msg, encoding).unwrap();
+            let decoded: ClientMessage = match encoding {
+                Encoding::MessagePack => rmp_serde::from_slice(&frame[6..]).unwrap(),
+                Encoding::Json => serde_json::from_slice(&frame[6..]).unwrap(),
+            };
+            assert_eq!(&decoded, msg);
+        }
+        assert_eq!(serde_json::to_value(msg).unwrap()["type"], msg.kind());
+    }
+    assert_eq!(serde_json::to_value(&messages[0]).unwrap()["batch_size"], 500);
+
+    let batch = json!({
+        "type": "cursorbatch", "id": "2", "cursor_id": "c1",
+        "documents": [{"n": 1}], "exhausted": true
+    });
+    for encoding in [Encoding::MessagePack, Encoding::Json] {
+        let (msg, _) = decode_frame(&server_frame(&batch, encoding)).unwrap();
+        assert!(matches!(
+            msg,
+            ServerMessage::CursorBatch { id, cursor_id, documents, exhausted: true }
+                if id == "2" && cursor_id == "c1" && documents == vec![json!({"n": 1})]
+        ));
+    }
+}
+
+#[test]
+fn test_message_kind_matches_type_tag() {
+    let msg = ClientMessage::ListCollections { id: "1".to_string() };
+    let json = serde_json::to_value(&msg).unwrap();
+    assert_eq!(json["type"], msg.kind());
+    assert_eq!(msg.id(), "1");
+}
+
+#[test]
+fn test_json_patch_ops_follow_rfc6902() {
+    use squirreldb_sdk::PatchOp;
+
+    let ops = vec![
+        PatchOp::Add { path: "/tags/-".to_string(), value: json!("new") },
+        PatchOp::Remove { path: "/tags/0".to_string() },
+        PatchOp::Replace { path: "/address/city".to_string(), value: json!("Oslo") },
+        PatchOp::Move { from: "/draft".to_string(), path: "/body".to_string() },
+        PatchOp::Test { path: "/version".to_string(), value: json!(3) },
+    ];
+    assert_eq!(
+        serde_json::to_value(&ops).unwrap(),
+        json!([
+            {"op": "add", "path": "/tags/-", "value": "new"},
+            {"op": "remove", "path": "/tags/0"},
+            {"op": "replace", "path": "/address/city", "value": "Oslo"},
+            {"op": "move", "from": "/draft", "path": "/body"},
+            {"op": "test", "path": "/version", "value": 3}
+        ])
+    );
+
+    let msg = ClientMessage::JsonPatch {
+        id: "5".to_string(),
+        collection: "posts".to_string(),
+        document_id: uuid::Uuid::nil(),
+        ops,
+    };
+    let frame = encode_frame(&msg, Encoding::MessagePack).unwrap();
+    let decoded: ClientMessage = rmp_serde::from_slice(&frame[6..]).unwrap();
+    assert_eq!(decoded, msg);
+}
@@ -2,7 +2,7 @@
 
 use squirreldb_sdk::{
     field, table, and, or, not,
-    SortDirection, ChangesOptions,
+    SortDirection, ChangesOptions, Accumulator, QueryError, StructuredQuery,
 };
 use serde_json::json;
 
@@ -28,6 +28,35 @@ fn test_field_gt() {
     assert_eq!(cond.value, json!(100));
 }
 
+#[test]
+fn test_field_range_on_strings() {
+    let result = table("events")
+        .find(field("created_at").gt("2024-01-01"))
+        .find(field("created_at").lte("2024-12-31T23:59:59Z"))
+        .compile_structured();
+
+    let filter = result.filter.unwrap();
+    assert_eq!(filter["created_at"]["$gt"], json!("2024-01-01"));
+    assert_eq!(filter["created_at"]["$lte"], json!("2024-12-31T23:59:59Z"));
+}
+
+#[test]
+fn test_comparisons_accept_ints_and_floats() {
+    let result = table("products")
+        .find(field("price").gt(9.99))
+        .find(field("price").lt(100))
+        .find(field("stock").gte(0u32))
+        .find(field("rating").lte(4.5f32))
+        .compile_structured();
+
+    let filter = result.filter.unwrap();
+    assert_eq!(filter["price"]["$gt"], json!(9.99));
+    assert_eq!(filter["price"]["$lt"], json!(100));
+    assert!(filter["price"]["$lt"].is_i64());
+    assert_eq!(filter["stock"]["$gte"], json!(0));
+    assert_eq!(filter["rating"]["$lte"], json!(4.5));
+}
+
 #[test]
 fn test_field_gte() {
     let cond = field("count").gte(10);
@@ -66,6 +95,36 @@ fn test_field_contains() {
     assert_eq!(cond.value, json!("test"));
 }
 
+#[test]
+fn test_case_insensitive_string_filters() {
+    let result = table("users")
+        .find(field("name").contains_ci("ali"))
+        .find(field("email").starts_with_ci("Admin"))
+        .find(field("domain").ends_with_ci(".COM"))
+        .compile_structured();
+
+    assert!(result.validate().is_ok());
+    let filter = result.filter.unwrap();
+    assert_eq!(filter["name"]["$contains"], json!({"value": "ali", "ci": true}));
+    assert_eq!(filter["email"]["$startsWith"], json!({"value": "Admin", "ci": true}));
+    assert_eq!(filter["domain"]["$endsWith"], json!({"value": ".COM", "ci": true}));
+}
+
+#[test]
+fn test_field_size() {
+    let cond = field("tags").size(3);
+    assert_eq!(cond.operator, "$size");
+    assert_eq!(cond.value, json!(3));
+}
+
+#[test]
+fn test_field_array_contains() {
+    let cond = field("tags").array_contains("rust");
+    assert_eq!(cond.operator, "$elemMatch");
+    assert_eq!(cond.value, json!("rust"));
+    assert_ne!(cond.operator, field("tags").contains("rust").operator);
+}
+
 #[test]
 fn test_field_starts_with() {
     let cond = field("email").starts_with("admin");
@@ -78,6 +137,18 @@ fn test_field_ends_with() {
     assert_eq!(cond.operator, "$endsWith");
 }
 
+#[test]
+fn test_field_modulo() {
+    let result = table("events").find(field("id_hash").modulo(10.0, 0.0)).compile_structured();
+    assert_eq!(result.filter.as_ref().unwrap()["id_hash"]["$mod"], json!([10.0, 0.0]));
+    assert!(result.validate().is_ok());
+
+    assert_eq!(
+        table("events").find(field("id_hash").modulo(0.0, 1.0)).validate(),
+        Err(QueryError::InvalidModulo("id_hash".to_string()))
+    );
+}
+
 #[test]
 fn test_field_exists() {
     let cond = field("avatar").exists(true);
@@ -166,6 +237,36 @@ fn test_multiple_sorts() {
     assert_eq!(sorts[1].field, "created_at");
 }
 
+#[test]
+fn test_compound_sort_mixed_directions() {
+    let query = table("posts")
+        .sort("pinned", SortDirection::Desc)
+        .sort("score", SortDirection::Asc)
+        .sort("created_at", SortDirection::Desc);
+
+    let sorts = query.clone().compile_structured().sort.unwrap();
+    let keys: Vec<_> = sorts.iter().map(|s| (s.field.as_str(), s.direction)).collect();
+    assert_eq!(
+        keys,
+        [
+            ("pinned", SortDirection::Desc),
+            ("score", SortDirection::Asc),
+            ("created_at", SortDirection::Desc),
+        ]
+    );
+
+    // compile() sends the whole compound sort as one ordered array
+    let compiled: serde_json::Value = serde_json::from_str(&query.compile().unwrap()).unwrap();
+    assert_eq!(
+        compiled["sort"],
+        json!([
+            {"field": "pinned", "direction": "desc"},
+            {"field": "score", "direction": "asc"},
+            {"field": "created_at", "direction": "desc"}
+        ])
+    );
+}
+
 #[test]
 fn test_limit_sets_max_results() {
     let result = table("users")
@@ -203,6 +304,18 @@ fn test_changes_with_options() {
     assert!(!result.changes.unwrap().include_initial);
 }
 
+#[test]
+fn test_changes_include_initial_reaches_wire() {
+    assert!(!ChangesOptions::default().include_initial);
+
+    let result = table("messages").with_initial(false).compile_structured();
+    assert!(!result.changes.unwrap().include_initial);
+
+    let compiled: serde_json::Value =
+        serde_json::from_str(&table("messages").with_initial(true).compile().unwrap()).unwrap();
+    assert_eq!(compiled["changes"], json!({"includeInitial": true}));
+}
+
 #[test]
 fn test_full_query() {
     let result = table("users")
@@ -255,3 +368,224 @@ fn test_not_negates_condition() {
 
     assert_eq!(cond.field, "$not");
 }
+
+#[test]
+fn test_or_on_same_field_serializes_as_array() {
+    let result = table("tasks")
+        .find(or(vec![field("status").eq("a"), field("status").eq("b")]))
+        .compile_structured();
+
+    let filter = result.filter.unwrap();
+    assert_eq!(filter["$or"], json!([{"status": {"$eq": "a"}}, {"status": {"$eq": "b"}}]));
+}
+
+#[test]
+fn test_repeated_conditions_accumulate_into_and() {
+    let result = table("tasks")
+        .find(or(vec![field("status").eq("a"), field("status").eq("b")]))
+        .find(or(vec![field("owner").eq("x"), field("owner").eq("y")]))
+        .find(field("tags").contains("urgent"))
+        .find(field("tags").contains("billing"))
+        .compile_structured();
+
+    let filter = result.filter.unwrap();
+    assert_eq!(filter["$or"], json!([{"status": {"$eq": "a"}}, {"status": {"$eq": "b"}}]));
+    assert_eq!(filter["tags"], json!({"$contains": "urgent"}));
+    assert_eq!(
+        filter["$and"],
+        json!([
+            {"$or": [{"owner": {"$eq": "x"}}, {"owner": {"$eq": "y"}}]},
+            {"tags": {"$contains": "billing"}}
+        ])
+    );
+}
+
+#[test]
+fn test_nested_combinators_and_validation() {
+    let result = table("users")
+        .find(not(and(vec![field("age").lt(18), field("role").is_in(vec![])])))
+        .compile_structured();
+
+    assert_eq!(
+        result.filter.as_ref().unwrap()["$not"],
+        json!({"$and": [{"age": {"$lt": 18}}, {"role": {"$in": []}}]})
+    );
+    assert_eq!(result.validate(), Err(QueryError::EmptyIn("role".to_string())));
+}
+
+#[test]
+fn test_geo_filters() {
+    let result = table("venues")
+        .find(field("location").near(52.52, 13.405, 1500.0))
+        .find(field("area").within_box(52.3, 13.0, 52.7, 13.8))
+        .compile_structured();
+
+    assert!(result.validate().is_ok());
+    let filter = result.filter.unwrap();
+    assert_eq!(filter["location"]["$near"], json!({"lat": 52.52, "lng": 13.405, "radius": 1500.0}));
+    assert_eq!(
+        filter["area"]["$withinBox"],
+        json!({"minLat": 52.3, "minLng": 13.0, "maxLat": 52.7, "maxLng": 13.8})
+    );
+}
+
+#[test]
+fn test_geo_filters_reject_bad_coordinates() {
+    let invalid = [
+        field("location").near(91.0, 0.0, 10.0),
+        field("location").near(0.0, 0.0, -1.0),
+        field("location").near(f64::NAN, 0.0, 10.0),
+        field("location").within_box(10.0, 0.0, 5.0, 1.0),
+        field("location").within_box(0.0, -181.0, 1.0, 1.0),
+    ];
+    for cond in invalid {
+        let operator = cond.operator.clone();
+        let result = table("venues").find(cond).validate();
+        assert_eq!(
+            result,
+            Err(QueryError::InvalidGeo { field: "location".to_string(), operator })
+        );
+    }
+}
+
+#[test]
+fn test_raw_op_passes_through() {
+    let query = table("docs")
+        .find(field("body").raw_op("$fuzzy", json!({"term": "squirel", "distance": 1})))
+        .find(or(vec![field("tags").raw_op("$all", json!(["a", "b"])), field("draft").eq(false)]));
+
+    assert!(query.validate().is_ok());
+    let result = query.compile_structured();
+    let filter = result.filter.as_ref().unwrap();
+    assert_eq!(filter["body"], json!({"$fuzzy": {"term": "squirel", "distance": 1}}));
+    assert_eq!(filter["$or"][0], json!({"tags": {"$all": ["a", "b"]}}));
+
+    // Structured queries from elsewhere are still held to the known operators
+    assert!(matches!(result.validate(), Err(QueryError::UnknownOperator { .. })));
+}
+
+#[test]
+fn test_compile_is_byte_identical() {
+    let build = || {
+        table("users")
+            .find(field("status").eq("active"))
+            .find(field("age").gte(18))
+            .find(field("age").lt(65))
+            .find(or(vec![field("role").eq("admin"), field("role").eq("owner")]))
+            .compute("years", "age")
+            .compute("name_upper", "upper(name)")
+    };
+    let compiled = build().compile().unwrap();
+
+    for _ in 0..10 {
+        assert_eq!(build().compile().unwrap(), compiled);
+    }
+    assert_eq!(
+        compiled,
+        r#"{"table":"users","filter":{"$or":[{"role":{"$eq":"admin"}},{"role":{"$eq":"owner"}}],"age":{"$gte":18,"$lt":65},"status":{"$eq":"active"}},"computed":{"name_upper":"upper(name)","years":"age"}}"#
+    );
+}
+
+#[test]
+fn test_distinct_sets_field() {
+    let result = table("products")
+        .find(field("active").eq(true))
+        .distinct("category")
+        .compile_structured();
+
+    assert_eq!(result.distinct, Some("category".to_string()));
+}
+
+#[test]
+fn test_distinct_serialization() {
+    let result = table("products").distinct("category").compile().unwrap();
+    assert!(result.contains("\"distinct\":\"category\""));
+
+    let result = table("products").compile().unwrap();
+    assert!(!result.contains("distinct"));
+}
+
+#[test]
+fn test_group_by_with_accumulators() {
+    let result = table("orders")
+        .group_by("status")
+        .count()
+        .sum("total")
+        .avg("total")
+        .compile_structured();
+
+    let group = result.group.unwrap();
+    assert_eq!(group.by, Some("status".to_string()));
+    assert_eq!(group.accumulators["count"], Accumulator::Count);
+    assert_eq!(group.accumulators["sum_total"], Accumulator::Sum("total".to_string()));
+    assert_eq!(group.accumulators["avg_total"], Accumulator::Avg("total".to_string()));
+}
+
+#[test]
+fn test_group_serialization() {
+    let result = table("orders")
+        .group_by("status")
+        .count()
+        .max("total")
+        .compile()
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+    assert_eq!(value["group"]["by"], json!("status"));
+    assert_eq!(value["group"]["accumulators"]["count"], json!("$count"));
+    assert_eq!(value["group"]["accumulators"]["max_total"], json!({"$max": "total"}));
+}
+
+#[test]
+fn test_computed_fields_pass_through() {
+    let compiled: serde_json::Value = serde_json::from_str(
+        &table("users")
+            .compute("fullName", "doc.first + ' ' + doc.last")
+            .compute("isAdult", "doc.age >= 18")
+            .compile()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        compiled["computed"],
+        json!({"fullName": "doc.first + ' ' + doc.last", "isAdult": "doc.age >= 18"})
+    );
+
+    assert!(table("users").compile_structured().computed.is_none());
+}
+
+#[test]
+fn test_structured_query_from_json_validates_operators() {
+    let query: StructuredQuery = serde_json::from_value(json!({
+        "table": "users",
+        "filter": {"age": {"$gte": 18, "$lt": 65}}
+    }))
+    .unwrap();
+    assert!(query.validate().is_ok());
+
+    let query: StructuredQuery = serde_json::from_value(json!({
+        "table": "users",
+        "filter": {"age": {"$between": [18, 65]}}
+    }))
+    .unwrap();
+    assert_eq!(
+        query.validate(),
+        Err(QueryError::UnknownOperator {
+            field: "age".to_string(),
+            operator: "$between".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_validate_catches_common_mistakes() {
+    assert!(table("users").find(field("age").gt(18)).limit(10).validate().is_ok());
+    assert_eq!(table("").validate(), Err(QueryError::EmptyTable));
+    assert_eq!(table("users").limit(0).validate(), Err(QueryError::InvalidLimit(0)));
+    assert_eq!(table("users").skip(20).validate(), Err(QueryError::SkipWithoutSort));
+    assert!(table("users").sort("name", SortDirection::Asc).skip(20).validate().is_ok());
+    assert_eq!(
+        table("users").find(field("role").is_in(vec![])).validate(),
+        Err(QueryError::EmptyIn("role".to_string()))
+    );
+}
@@ -0,0 +1,434 @@
+//! SquirrelDB Rust SDK - Storage Tests
+
+use futures::TryStreamExt;
+use squirreldb_sdk::{ListOptions, PutOptions, StorageClient, StorageError, StorageOptions};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Serve one canned HTTP response per connection, returning the raw requests
+async fn http_server(responses: Vec<String>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for response in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let body_len = text[..end]
+                        .lines()
+                        .find_map(|l| {
+                            let l = l.to_ascii_lowercase();
+                            l.strip_prefix("content-length:").map(|v| v.trim().parse().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + body_len {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            requests.push(String::from_utf8_lossy(&request).into_owned());
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+        }
+        requests
+    });
+
+    (endpoint, handle)
+}
+
+fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let mut out = format!(
+        "HTTP/1.1 {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        status,
+        body.len()
+    );
+    for (k, v) in headers {
+        out.push_str(&format!("{}: {}\r\n", k, v));
+    }
+    out.push_str("\r\n");
+    out.push_str(body);
+    out
+}
+
+fn client(endpoint: String) -> StorageClient {
+    StorageClient::new(StorageOptions {
+        endpoint,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_get_object_stream() {
+    let body = "x".repeat(64 * 1024);
+    let (endpoint, server) = http_server(vec![response("200 OK", &[], &body)]).await;
+
+    let stream = client(endpoint).get_object_stream("files", "big.bin").await.unwrap();
+    let chunks: Vec<_> = stream.try_collect().await.unwrap();
+    let received: usize = chunks.iter().map(|c| c.len()).sum();
+    assert_eq!(received, body.len());
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].starts_with("GET /files/big.bin "));
+}
+
+#[tokio::test]
+async fn test_get_object_stream_error_status() {
+    let (endpoint, _server) = http_server(vec![response("404 Not Found", &[], "")]).await;
+
+    let result = client(endpoint).get_object_stream("files", "missing.bin").await;
+    assert!(matches!(result, Err(StorageError::NotFound)));
+}
+
+#[tokio::test]
+async fn test_get_object_range_partial_content() {
+    let (endpoint, server) = http_server(vec![response(
+        "206 Partial Content",
+        &[("Content-Range", "bytes 2-5/10")],
+        "2345",
+    )])
+    .await;
+
+    let body = client(endpoint).get_object_range("files", "digits.txt", 2, Some(5)).await.unwrap();
+    assert_eq!(body, b"2345");
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].to_ascii_lowercase().contains("range: bytes=2-5"));
+}
+
+#[tokio::test]
+async fn test_get_object_range_falls_back_on_full_body() {
+    let (endpoint, _server) = http_server(vec![
+        response("200 OK", &[], "0123456789"),
+        response("200 OK", &[], "0123456789"),
+        response("200 OK", &[], "0123456789"),
+    ])
+    .await;
+    let client = client(endpoint);
+
+    assert_eq!(client.get_object_range("files", "digits.txt", 7, None).await.unwrap(), b"789");
+    assert_eq!(client.get_object_range("files", "digits.txt", 8, Some(20)).await.unwrap(), b"89");
+    assert!(matches!(
+        client.get_object_range("files", "digits.txt", 10, None).await,
+        Err(StorageError::Status(s)) if s.as_u16() == 416
+    ));
+}
+
+#[tokio::test]
+async fn test_get_object_range_rejects_bad_ranges() {
+    let (endpoint, server) = http_server(vec![
+        response("200 OK", &[], "0123456789"),
+        response("200 OK", &[], "0123456789"),
+    ])
+    .await;
+    let client = client(endpoint);
+
+    // Inverted, so rejected before any request is made
+    assert!(matches!(
+        client.get_object_range("files", "digits.txt", 5, Some(2)).await,
+        Err(StorageError::Status(s)) if s.as_u16() == 416
+    ));
+    assert!(matches!(
+        client.get_object_range("files", "digits.txt", 20, Some(30)).await,
+        Err(StorageError::Status(s)) if s.as_u16() == 416
+    ));
+    assert_eq!(client.get_object_range("files", "digits.txt", 9, Some(u64::MAX)).await.unwrap(), b"9");
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].to_ascii_lowercase().contains("range: bytes=20-30"));
+}
+
+#[tokio::test]
+async fn test_resume_multipart_upload() {
+    let list_parts = "<ListPartsResult><UploadId>up-1</UploadId>\
+        <Part><PartNumber>1</PartNumber><ETag>&quot;aaa&quot;</ETag><Size>5</Size></Part>\
+        <Part><PartNumber>3</PartNumber><ETag>&quot;ccc&quot;</ETag><Size>5</Size></Part>\
+        </ListPartsResult>";
+    let complete = "<CompleteMultipartUploadResult><ETag>&quot;final-3&quot;</ETag></CompleteMultipartUploadResult>";
+    let (endpoint, server) = http_server(vec![
+        response("200 OK", &[], list_parts),
+        response("200 OK", &[("ETag", "\"bbb\"")], ""),
+        response("200 OK", &[], complete),
+    ])
+    .await;
+    let client = client(endpoint);
+
+    let mut upload = client.resume_multipart("files", "video.mp4", "up-1").await.unwrap();
+    assert_eq!(upload.parts_uploaded().len(), 2);
+    assert_eq!(upload.parts_uploaded()[1].etag, "ccc");
+    assert_eq!(upload.missing_parts(3), vec![2]);
+
+    let part = client.upload_part(&mut upload, 2, b"hello").await.unwrap();
+    assert_eq!(part.etag, "bbb");
+    assert!(upload.missing_parts(3).is_empty());
+
+    let etag = client.complete_multipart_upload(&upload).await.unwrap();
+    assert_eq!(etag, "final-3");
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].starts_with("GET /files/video.mp4?uploadId=up-1 "));
+    assert!(requests[1].starts_with("PUT /files/video.mp4?partNumber=2&uploadId=up-1 "));
+    let body = &requests[2];
+    let first = body.find("<PartNumber>1<").unwrap();
+    let second = body.find("<PartNumber>2<").unwrap();
+    let third = body.find("<PartNumber>3<").unwrap();
+    assert!(first < second && second < third);
+}
+
+#[tokio::test]
+async fn test_resume_multipart_follows_part_pages() {
+    let first = "<ListPartsResult><UploadId>up-1</UploadId>\
+        <IsTruncated>true</IsTruncated><NextPartNumberMarker>2</NextPartNumberMarker>\
+        <Part><PartNumber>1</PartNumber><ETag>&quot;aaa&quot;</ETag><Size>5</Size></Part>\
+        <Part><PartNumber>2</PartNumber><ETag>&quot;bbb&quot;</ETag><Size>5</Size></Part>\
+        </ListPartsResult>";
+    let second = "<ListPartsResult><UploadId>up-1</UploadId><IsTruncated>false</IsTruncated>\
+        <Part><PartNumber>3</PartNumber><ETag>&quot;ccc&quot;</ETag><Size>5</Size></Part>\
+        </ListPartsResult>";
+    let (endpoint, server) = http_server(vec![
+        response("200 OK", &[], first),
+        response("200 OK", &[], second),
+    ])
+    .await;
+
+    let upload = client(endpoint).resume_multipart("files", "video.mp4", "up-1").await.unwrap();
+    assert_eq!(upload.parts_uploaded().len(), 3);
+    assert!(upload.missing_parts(3).is_empty());
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].starts_with("GET /files/video.mp4?uploadId=up-1 "));
+    assert!(requests[1].starts_with("GET /files/video.mp4?part-number-marker=2&uploadId=up-1 "));
+}
+
+#[tokio::test]
+async fn test_resume_multipart_rejects_part_zero() {
+    let list_parts = "<ListPartsResult><UploadId>up-1</UploadId>\
+        <Part><PartNumber>0</PartNumber><ETag>&quot;aaa&quot;</ETag><Size>5</Size></Part>\
+        </ListPartsResult>";
+    let (endpoint, _server) = http_server(vec![response("200 OK", &[], list_parts)]).await;
+
+    let result = client(endpoint).resume_multipart("files", "video.mp4", "up-1").await;
+    assert!(matches!(result, Err(StorageError::Server { code, .. }) if code == "InvalidResponse"));
+}
+
+#[tokio::test]
+async fn test_put_object_with_metadata() {
+    let (endpoint, server) = http_server(vec![response("200 OK", &[("ETag", "\"abc\"")], "")]).await;
+
+    let opts = PutOptions {
+        content_type: Some("image/png".to_string()),
+        metadata: [("Owner".to_string(), "alice".to_string())].into(),
+        cache_control: Some("max-age=3600".to_string()),
+        ..Default::default()
+    };
+    let etag = client(endpoint).put_object_with("files", "logo.png", b"png", opts).await.unwrap();
+    assert_eq!(etag, "abc");
+
+    let request = server.await.unwrap().remove(0).to_ascii_lowercase();
+    assert!(request.contains("content-type: image/png"));
+    assert!(request.contains("x-amz-meta-owner: alice"));
+    assert!(request.contains("cache-control: max-age=3600"));
+}
+
+#[tokio::test]
+async fn test_head_object() {
+    let (endpoint, _server) = http_server(vec![response(
+        "200 OK",
+        &[
+            ("ETag", "\"abc\""),
+            ("Content-Type", "image/png"),
+            ("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ("x-amz-meta-owner", "alice"),
+        ],
+        "",
+    )])
+    .await;
+
+    let obj = client(endpoint).head_object("files", "logo.png").await.unwrap();
+    assert_eq!(obj.key, "logo.png");
+    assert_eq!(obj.etag, "abc");
+    assert_eq!(obj.content_type.as_deref(), Some("image/png"));
+    assert_eq!(obj.last_modified.to_rfc3339(), "2015-10-21T07:28:00+00:00");
+    assert_eq!(obj.metadata["owner"], "alice");
+}
+
+#[tokio::test]
+async fn test_object_exists_maps_not_found() {
+    let (endpoint, _server) = http_server(vec![
+        response("404 Not Found", &[], ""),
+        response("403 Forbidden", &[], ""),
+    ])
+    .await;
+    let client = client(endpoint);
+
+    assert!(!client.object_exists("files", "missing").await.unwrap());
+    assert!(client.object_exists("files", "secret").await.is_err());
+}
+
+#[tokio::test]
+async fn test_list_objects_with_delimiter_and_pagination() {
+    let page = "<ListBucketResult>\
+        <Contents><Key>photos/a&amp;b.jpg</Key><LastModified>2024-01-01T00:00:00.000Z</LastModified>\
+        <ETag>&quot;e1&quot;</ETag><Size>10</Size></Contents>\
+        <CommonPrefixes><Prefix>photos/2023/</Prefix></CommonPrefixes>\
+        <CommonPrefixes><Prefix>photos/2024/</Prefix></CommonPrefixes>\
+        <NextContinuationToken>tok-2</NextContinuationToken>\
+        </ListBucketResult>";
+    let (endpoint, server) = http_server(vec![response("200 OK", &[], page)]).await;
+
+    let opts = ListOptions {
+        prefix: Some("photos/".to_string()),
+        delimiter: Some("/".to_string()),
+        max_keys: Some(1),
+        continuation_token: Some("tok-1".to_string()),
+    };
+    let listing = client(endpoint).list_objects_with("files", opts).await.unwrap();
+
+    assert_eq!(listing.objects.len(), 1);
+    assert_eq!(listing.objects[0].key, "photos/a&b.jpg");
+    assert_eq!(listing.objects[0].etag, "e1");
+    assert_eq!(listing.objects[0].size, 10);
+    assert_eq!(listing.common_prefixes, vec!["photos/2023/", "photos/2024/"]);
+    assert_eq!(listing.next_continuation_token.as_deref(), Some("tok-2"));
+
+    let request = server.await.unwrap().remove(0);
+    assert!(request.starts_with(
+        "GET /files?continuation-token=tok-1&delimiter=%2F&list-type=2&max-keys=1&prefix=photos%2F "
+    ));
+}
+
+#[tokio::test]
+async fn test_move_object() {
+    let copied = "<CopyObjectResult><ETag>&quot;abc&quot;</ETag></CopyObjectResult>";
+    let (endpoint, server) = http_server(vec![
+        response("200 OK", &[], copied),
+        response("200 OK", &[("ETag", "\"abc\""), ("Content-Type", "text/plain")], ""),
+        response("204 No Content", &[], ""),
+    ])
+    .await;
+
+    let obj = client(endpoint).move_object("files", "old name.txt", "archive", "new.txt").await.unwrap();
+    assert_eq!(obj.key, "new.txt");
+    assert_eq!(obj.content_type.as_deref(), Some("text/plain"));
+
+    let requests = server.await.unwrap();
+    let copy = requests[0].to_ascii_lowercase();
+    assert!(copy.starts_with("put /archive/new.txt "));
+    assert!(copy.contains("x-amz-copy-source: /files/old%20name.txt"));
+    assert!(copy.contains("x-amz-metadata-directive: copy"));
+    assert!(requests[1].starts_with("HEAD /archive/new.txt "));
+    assert!(requests[2].starts_with("DELETE /files/old%20name.txt "));
+}
+
+#[tokio::test]
+async fn test_move_object_onto_itself_keeps_it() {
+    let (endpoint, server) = http_server(vec![response(
+        "200 OK",
+        &[("ETag", "\"abc\""), ("Content-Type", "text/plain")],
+        "",
+    )])
+    .await;
+
+    let obj = client(endpoint).move_object("files", "a.txt", "files", "a.txt").await.unwrap();
+    assert_eq!(obj.key, "a.txt");
+
+    let requests = server.await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("HEAD /files/a.txt "));
+}
+
+#[tokio::test]
+async fn test_copy_missing_source() {
+    let (endpoint, _server) = http_server(vec![response("404 Not Found", &[], "")]).await;
+
+    let result = client(endpoint).copy_object("files", "missing", "files", "copy").await;
+    assert!(matches!(result, Err(StorageError::NotFound)));
+}
+
+fn verifying_client(endpoint: String) -> StorageClient {
+    StorageClient::new(StorageOptions {
+        endpoint,
+        verify_checksum: true,
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_get_object_verifies_md5_etag() {
+    // md5("hello")
+    let good = "\"5d41402abc4b2a76b9719d911017c592\"";
+    let (endpoint, _server) = http_server(vec![
+        response("200 OK", &[("ETag", good)], "hello"),
+        response("200 OK", &[("ETag", good)], "hellp"),
+        response("200 OK", &[("ETag", "\"5d41402abc4b2a76b9719d911017c592-2\"")], "hellp"),
+    ])
+    .await;
+    let client = verifying_client(endpoint);
+
+    assert_eq!(client.get_object("files", "a").await.unwrap(), b"hello");
+    assert!(matches!(
+        client.get_object("files", "a").await,
+        Err(StorageError::ChecksumMismatch { .. })
+    ));
+    assert!(client.get_object("files", "a").await.is_ok());
+}
+
+#[tokio::test]
+async fn test_put_object_checksums() {
+    let (endpoint, server) = http_server(vec![response("200 OK", &[], "")]).await;
+    let client = verifying_client(endpoint);
+
+    let wrong = PutOptions {
+        sha256: Some("00".repeat(32)),
+        ..Default::default()
+    };
+    assert!(matches!(
+        client.put_object_with("files", "a", b"hello", wrong).await,
+        Err(StorageError::ChecksumMismatch { .. })
+    ));
+
+    client.put_object("files", "a", b"hello", None).await.unwrap();
+    let request = server.await.unwrap().remove(0).to_ascii_lowercase();
+    // base64(sha256("hello"))
+    assert!(request.contains("x-amz-checksum-sha256: lpjnul+wow4m6dsqxbninhswhlwfp0jecwqzypolmcq="));
+}
+
+#[tokio::test]
+async fn test_error_codes_map_to_variants() {
+    let error = |code: &str| {
+        format!("<Error><Code>{}</Code><Message>details</Message></Error>", code)
+    };
+    let (endpoint, _server) = http_server(vec![
+        response("404 Not Found", &[], &error("NoSuchBucket")),
+        response("403 Forbidden", &[], &error("AccessDenied")),
+        response("412 Precondition Failed", &[], ""),
+        response("503 Service Unavailable", &[], &error("SlowDown")),
+        response("400 Bad Request", &[], &error("InvalidArgument")),
+    ])
+    .await;
+    let client = client(endpoint);
+
+    let bucket = client.get_object("missing", "a").await.unwrap_err();
+    assert!(matches!(bucket, StorageError::BucketNotFound));
+    let denied = client.get_object("files", "secret").await.unwrap_err();
+    assert!(matches!(denied, StorageError::AccessDenied));
+    assert!(!denied.is_retryable());
+    let precondition = client.get_object("files", "a").await.unwrap_err();
+    assert!(matches!(precondition, StorageError::PreconditionFailed));
+
+    let throttled = client.get_object("files", "a").await.unwrap_err();
+    assert!(matches!(&throttled, StorageError::Server { code, .. } if code == "SlowDown"));
+    assert!(throttled.is_retryable());
+    let invalid = client.get_object("files", "a").await.unwrap_err();
+    assert!(matches!(&invalid, StorageError::Server { message, .. } if message == "details"));
+    assert!(!invalid.is_retryable());
+}
@@ -1,9 +1,11 @@
 //! SquirrelDB Rust SDK - Types Tests
 
-use squirreldb_sdk::{Document, ChangeEvent, Bucket, StorageObject};
+use squirreldb_sdk::{Document, ChangeEvent, Bucket, StorageObject, TypedChangeEvent};
+use serde::Deserialize;
 use serde_json::json;
 use uuid::Uuid;
 use chrono::Utc;
+use std::collections::HashMap;
 
 #[test]
 fn test_document_from_json() {
@@ -22,6 +24,48 @@ fn test_document_from_json() {
     assert_eq!(doc.created_at, "2024-01-01T00:00:00Z");
 }
 
+#[test]
+fn test_document_timestamps_as_datetime() {
+    let doc = Document {
+        id: Uuid::nil(),
+        collection: "users".to_string(),
+        data: json!({}),
+        created_at: "2024-01-01T02:00:00+02:00".to_string(),
+        updated_at: "not a timestamp".to_string(),
+    };
+
+    let created = doc.created_at_dt().unwrap();
+    assert_eq!(created.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+    assert_eq!(created.timezone(), Utc);
+    assert!(doc.updated_at_dt().is_err());
+}
+
+#[test]
+fn test_document_typed_access() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    let doc = Document {
+        id: Uuid::nil(),
+        collection: "users".to_string(),
+        data: json!({"name": "Alice", "age": 30, "nickname": null}),
+        created_at: String::new(),
+        updated_at: String::new(),
+    };
+
+    assert_eq!(doc.get_as::<String>("name").unwrap().as_deref(), Some("Alice"));
+    assert_eq!(doc.get_as::<u32>("missing").unwrap(), None);
+    assert_eq!(doc.get_as::<String>("nickname").unwrap(), None);
+    assert!(doc.get_as::<u32>("name").is_err());
+    assert_eq!(
+        doc.into_typed::<User>().unwrap(),
+        User { name: "Alice".to_string(), age: 30 }
+    );
+}
+
 #[test]
 fn test_document_to_json() {
     let id = Uuid::new_v4();
@@ -74,6 +118,57 @@ fn test_change_event_insert() {
     assert!(matches!(event, ChangeEvent::Insert { .. }));
 }
 
+#[derive(Debug, Deserialize, PartialEq)]
+struct User {
+    name: String,
+}
+
+#[test]
+fn test_typed_change_event_update() {
+    let id = Uuid::new_v4();
+    let data = json!({
+        "type": "update",
+        "old": {"name": "Old"},
+        "new": {
+            "id": id.to_string(),
+            "collection": "users",
+            "data": {"name": "New"},
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-02T00:00:00Z"
+        }
+    });
+
+    let event: ChangeEvent = serde_json::from_value(data).unwrap();
+    match TypedChangeEvent::<User>::from(event) {
+        TypedChangeEvent::Update { old, new } => {
+            assert_eq!(old, User { name: "Old".to_string() });
+            assert_eq!(new, User { name: "New".to_string() });
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+#[test]
+fn test_typed_change_event_schema_drift() {
+    let id = Uuid::new_v4();
+    let data = json!({
+        "type": "insert",
+        "new": {
+            "id": id.to_string(),
+            "collection": "users",
+            "data": {"username": "Test"},
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z"
+        }
+    });
+
+    let event: ChangeEvent = serde_json::from_value(data).unwrap();
+    match TypedChangeEvent::<User>::from(event) {
+        TypedChangeEvent::Error { event, .. } => assert!(matches!(event, ChangeEvent::Insert { .. })),
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
 #[test]
 fn test_bucket_structure() {
     let bucket = Bucket {
@@ -92,6 +187,7 @@ fn test_storage_object_structure() {
         etag: "d41d8cd98f00b204e9800998ecf8427e".to_string(),
         last_modified: Utc::now(),
         content_type: Some("text/plain".to_string()),
+        metadata: HashMap::new(),
     };
 
     assert_eq!(obj.key, "path/to/file.txt");
@@ -107,6 +203,7 @@ fn test_storage_object_null_content_type() {
         etag: "abc123".to_string(),
         last_modified: Utc::now(),
         content_type: None,
+        metadata: HashMap::new(),
     };
 
     assert!(obj.content_type.is_none());